@@ -0,0 +1,32 @@
+// Decoders for account data as fetched from an RPC node.
+
+use amm::state::Config;
+use amm_math::Pool;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+// Any account of the program, e.g. `Config`, `PairIndex` or `LimitOrder`, checked
+// against its discriminator. Also works for token accounts and mints of either
+// token program.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_config(data: &[u8]) -> Result<Config> {
+    decode(data)
+}
+
+pub fn decode_token_account(data: &[u8]) -> Result<TokenAccount> {
+    decode(data)
+}
+
+pub fn decode_mint(data: &[u8]) -> Result<Mint> {
+    decode(data)
+}
+
+// The pool as the program prices the next instruction, given its config, the
+// vault balances and the LP supply. Fails while a flash loan is outstanding.
+pub fn quote_pool(config: &Config, vault_x: u64, vault_y: u64, lp_supply: u64) -> Result<Pool> {
+    let (reserve_x, reserve_y) = config.reserves(vault_x, vault_y)?;
+    Ok(config.pool(reserve_x, reserve_y, lp_supply))
+}
@@ -0,0 +1,146 @@
+// Builders for the pool instructions. Pools of Token-2022 mints with transfer
+// hooks also need the hooks' extra accounts appended to `accounts`.
+
+use amm::{accounts, instruction, state::CurveType};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+    InstructionData,
+};
+use anchor_spl::associated_token::spl_associated_token_account;
+
+use crate::pda::{pair_index_address, registry_address, PoolKeys};
+
+pub fn initialize(
+    keys: &PoolKeys,
+    signer: &Pubkey,
+    authority: Option<Pubkey>,
+    fee: u16,
+    curve: CurveType,
+) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: accounts::Initialize {
+            signer: *signer,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            vault_x: keys.vault_x,
+            vault_y: keys.vault_y,
+            mint_lp: keys.mint_lp,
+            vault_lp: keys.vault_lp,
+            config: keys.config,
+            registry: registry_address(),
+            pair_index: pair_index_address(&keys.mint_x, &keys.mint_y, curve),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {
+            seed: keys.seed,
+            authority,
+            fee,
+            curve,
+        }
+        .data(),
+    }
+}
+
+// The first deposit into a pool deposits exactly `max_x` and `max_y`, and
+// ignores `amount`.
+pub fn deposit(keys: &PoolKeys, user: &Pubkey, amount: u64, max_x: u64, max_y: u64) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: accounts::Deposit {
+            signer: *user,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            vault_x: keys.vault_x,
+            vault_y: keys.vault_y,
+            user_x: keys.user_x(user),
+            user_y: keys.user_y(user),
+            mint_lp: keys.mint_lp,
+            vault_lp: keys.vault_lp,
+            user_lp: keys.user_lp(user),
+            config: keys.config,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+        }
+        .data(),
+    }
+}
+
+pub fn swap(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    is_x: bool,
+    amount: u64,
+    min: u64,
+    expiration: i64,
+) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: accounts::Swap {
+            signer: *user,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            vault_x: keys.vault_x,
+            vault_y: keys.vault_y,
+            user_x: keys.user_x(user),
+            user_y: keys.user_y(user),
+            config: keys.config,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Swap {
+            is_x,
+            amount,
+            min,
+            expiration,
+        }
+        .data(),
+    }
+}
+
+pub fn withdraw(
+    keys: &PoolKeys,
+    user: &Pubkey,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: accounts::Withdraw {
+            signer: *user,
+            mint_x: keys.mint_x,
+            mint_y: keys.mint_y,
+            vault_x: keys.vault_x,
+            vault_y: keys.vault_y,
+            user_x: keys.user_x(user),
+            user_y: keys.user_y(user),
+            mint_lp: keys.mint_lp,
+            user_lp: keys.user_lp(user),
+            config: keys.config,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Withdraw {
+            amount,
+            min_x,
+            min_y,
+        }
+        .data(),
+    }
+}
@@ -0,0 +1,10 @@
+// Client of the amm program for off-chain Rust code: account addresses,
+// instruction builders and account decoders. Quotes come from `amm_math`.
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use amm::{state, ID};
+pub use amm_math;
+pub use pda::PoolKeys;
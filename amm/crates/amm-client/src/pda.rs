@@ -0,0 +1,89 @@
+use amm::state::CurveType;
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+pub fn config_address(seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"config", &seed.to_le_bytes()], &amm::ID).0
+}
+
+pub fn mint_lp_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint", config.as_ref()], &amm::ID).0
+}
+
+// Vaults are associated token accounts of the config.
+pub fn vault_address(config: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(config, mint, token_program)
+}
+
+pub fn registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"registry"], &amm::ID).0
+}
+
+// The mints must be sorted, see `sort_mints`.
+pub fn pair_index_address(mint_x: &Pubkey, mint_y: &Pubkey, curve: CurveType) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"pair",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            &curve.tag().to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+    .0
+}
+
+// The canonical order of a pair, with mint_x before mint_y.
+pub fn sort_mints(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    match a < b {
+        true => (a, b),
+        false => (b, a),
+    }
+}
+
+// Addresses of a pool, and of a user's token accounts for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub seed: u64,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program: Pubkey,
+    pub config: Pubkey,
+    pub mint_lp: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub vault_lp: Pubkey,
+}
+
+impl PoolKeys {
+    // The mints can be given in either order.
+    pub fn new(seed: u64, mint_a: Pubkey, mint_b: Pubkey, token_program: Pubkey) -> PoolKeys {
+        let (mint_x, mint_y) = sort_mints(mint_a, mint_b);
+        let config = config_address(seed);
+        let mint_lp = mint_lp_address(&config);
+
+        PoolKeys {
+            seed,
+            mint_x,
+            mint_y,
+            token_program,
+            config,
+            mint_lp,
+            vault_x: vault_address(&config, &mint_x, &token_program),
+            vault_y: vault_address(&config, &mint_y, &token_program),
+            vault_lp: vault_address(&config, &mint_lp, &token_program),
+        }
+    }
+
+    pub fn user_x(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_x, &self.token_program)
+    }
+
+    pub fn user_y(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_y, &self.token_program)
+    }
+
+    pub fn user_lp(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_lp, &self.token_program)
+    }
+}
@@ -0,0 +1,194 @@
+// Runs the program in process through the client's builders and decoders.
+
+use amm::state::CurveType;
+use amm_client::{
+    accounts::{decode_config, decode_mint, decode_token_account, quote_pool},
+    amm_math::{Amounts, Pool, MINIMUM_LIQUIDITY},
+    instructions, PoolKeys,
+};
+use amm_test::{
+    signature::{Keypair, Signer},
+    ProcessResult, Svm,
+};
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use anchor_spl::token::spl_token;
+
+const SEED: u64 = 42;
+
+struct Test {
+    svm: Svm,
+    keys: PoolKeys,
+    user: Keypair,
+}
+
+impl Test {
+    fn new() -> Test {
+        let mut svm = Svm::new();
+        let user = Keypair::new();
+        svm.airdrop(&user.pubkey(), 1_000_000_000_000);
+
+        let mint_a = svm.create_mint(&spl_token::ID, 6);
+        let mint_b = svm.create_mint(&spl_token::ID, 6);
+        let keys = PoolKeys::new(SEED, mint_a, mint_b, spl_token::ID);
+
+        for mint in [mint_a, mint_b] {
+            let account = svm.create_ata(&user.pubkey(), &mint, &spl_token::ID);
+            svm.mint_to(&mint, &account, 1_000_000_000_000);
+        }
+
+        let initialize = instructions::initialize(
+            &keys,
+            &user.pubkey(),
+            Some(user.pubkey()),
+            30,
+            CurveType::ConstantProduct,
+        );
+        let mut test = Test { svm, keys, user };
+        test.process(&[initialize]).unwrap();
+
+        test
+    }
+
+    fn process(&mut self, instructions: &[Instruction]) -> ProcessResult {
+        self.svm.process(instructions, &[&self.user])
+    }
+
+    fn data(&self, key: &Pubkey) -> Vec<u8> {
+        self.svm.account(key).unwrap().data
+    }
+
+    fn balance(&self, key: &Pubkey) -> u64 {
+        decode_token_account(&self.data(key)).unwrap().amount
+    }
+
+    fn balances(&self) -> (u64, u64, u64) {
+        let user = self.user.pubkey();
+        (
+            self.balance(&self.keys.user_x(&user)),
+            self.balance(&self.keys.user_y(&user)),
+            self.svm
+                .account(&self.keys.user_lp(&user))
+                .map_or(0, |_| self.balance(&self.keys.user_lp(&user))),
+        )
+    }
+
+    fn pool(&self) -> Pool {
+        let config = decode_config(&self.data(&self.keys.config)).unwrap();
+        let supply = decode_mint(&self.data(&self.keys.mint_lp)).unwrap().supply;
+        quote_pool(
+            &config,
+            self.balance(&self.keys.vault_x),
+            self.balance(&self.keys.vault_y),
+            supply,
+        )
+        .unwrap()
+    }
+
+    fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> ProcessResult {
+        let deposit = instructions::deposit(&self.keys, &self.user.pubkey(), amount, max_x, max_y);
+        self.process(&[deposit])
+    }
+
+    fn swap(&mut self, is_x: bool, amount: u64, min: u64) -> ProcessResult {
+        let swap = instructions::swap(&self.keys, &self.user.pubkey(), is_x, amount, min, i64::MAX);
+        self.process(&[swap])
+    }
+
+    fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> ProcessResult {
+        let withdraw =
+            instructions::withdraw(&self.keys, &self.user.pubkey(), amount, min_x, min_y);
+        self.process(&[withdraw])
+    }
+}
+
+#[test]
+fn initialize_creates_the_pool() {
+    let test = Test::new();
+    let config = decode_config(&test.data(&test.keys.config)).unwrap();
+
+    assert_eq!(config.seed, SEED);
+    assert_eq!(
+        (config.mint_x, config.mint_y),
+        (test.keys.mint_x, test.keys.mint_y)
+    );
+    assert_eq!(config.curve, CurveType::ConstantProduct);
+    assert_eq!(config.fee, 30);
+    assert_eq!(test.balance(&test.keys.vault_x), 0);
+}
+
+#[test]
+fn first_deposit_mints_the_geometric_mean() {
+    let mut test = Test::new();
+    let (x, y, _) = test.balances();
+
+    test.deposit(0, 4_000_000, 1_000_000).unwrap();
+
+    assert_eq!(
+        test.balances(),
+        (x - 4_000_000, y - 1_000_000, 2_000_000 - MINIMUM_LIQUIDITY)
+    );
+    assert_eq!(test.balance(&test.keys.vault_lp), MINIMUM_LIQUIDITY);
+    assert_eq!(test.balance(&test.keys.vault_x), 4_000_000);
+    assert_eq!(test.balance(&test.keys.vault_y), 1_000_000);
+}
+
+#[test]
+fn deposit_is_proportional_to_the_reserves() {
+    let mut test = Test::new();
+    test.deposit(0, 4_000_000, 1_000_000).unwrap();
+    let (x, y, lp) = test.balances();
+
+    let Amounts { x: dx, y: dy } = test.pool().quote_deposit(500_000).unwrap();
+    assert_eq!((dx, dy), (1_000_000, 250_000));
+
+    assert!(test.deposit(500_000, dx - 1, dy).is_err());
+    assert!(test.deposit(500_000, dx, dy - 1).is_err());
+    test.deposit(500_000, dx, dy).unwrap();
+
+    assert_eq!(test.balances(), (x - dx, y - dy, lp + 500_000));
+}
+
+#[test]
+fn swap_x_for_y() {
+    let mut test = Test::new();
+    test.deposit(0, 1_000_000_000, 1_000_000_000).unwrap();
+    let (x, y, lp) = test.balances();
+
+    let quote = test.pool().quote_swap(true, 1_000_000).unwrap();
+    assert!(test.swap(true, 1_000_000, quote.withdraw + 1).is_err());
+    test.swap(true, 1_000_000, quote.withdraw).unwrap();
+
+    assert_eq!(test.balances(), (x - 1_000_000, y + quote.withdraw, lp));
+}
+
+#[test]
+fn swap_y_for_x() {
+    let mut test = Test::new();
+    test.deposit(0, 1_000_000_000, 1_000_000_000).unwrap();
+    let (x, y, lp) = test.balances();
+
+    let quote = test.pool().quote_swap(false, 1_000_000).unwrap();
+    assert!(test.swap(false, 1_000_000, quote.withdraw + 1).is_err());
+    test.swap(false, 1_000_000, quote.withdraw).unwrap();
+
+    assert_eq!(test.balances(), (x + quote.withdraw, y - 1_000_000, lp));
+}
+
+#[test]
+fn withdraw_everything() {
+    let mut test = Test::new();
+    test.deposit(0, 4_000_000, 1_000_000).unwrap();
+    test.swap(true, 100_000, 0).unwrap();
+    let (x, y, lp) = test.balances();
+
+    let Amounts { x: dx, y: dy } = test.pool().quote_withdraw(lp).unwrap();
+    assert!(test.withdraw(lp, dx + 1, dy).is_err());
+    assert!(test.withdraw(lp, dx, dy + 1).is_err());
+    test.withdraw(lp, dx, dy).unwrap();
+
+    assert_eq!(test.balances(), (x + dx, y + dy, 0));
+    // Only the share of the locked minimum liquidity is left in the pool.
+    let pool = test.pool();
+    assert_eq!(pool.supply, MINIMUM_LIQUIDITY);
+    assert!(pool.reserve_x > 0 && pool.reserve_y > 0);
+}
@@ -0,0 +1,208 @@
+use super::{CurveError, SwapResult};
+use crate::math::{mul_div, sqrt_mul};
+
+pub fn swap(
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    min_out: u64,
+) -> Result<SwapResult, CurveError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+
+    let fee_amount = (amount_in as u128 * fee as u128 / 10_000) as u64;
+    let amount_in_after_fee = (amount_in - fee_amount) as u128;
+
+    // Rounds down so the pool never ends up below its invariant.
+    let amount_out = mul_div(
+        reserve_out as u128,
+        amount_in_after_fee,
+        reserve_in as u128 + amount_in_after_fee,
+    )
+    .ok_or(CurveError::Overflow)? as u64;
+
+    if amount_out < min_out {
+        return Err(CurveError::SlippageLimitExceeded);
+    }
+
+    Ok(SwapResult {
+        deposit: amount_in,
+        withdraw: amount_out,
+        fee: fee_amount,
+    })
+}
+
+pub fn swap_exact_out(
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    max_in: u64,
+) -> Result<SwapResult, CurveError> {
+    let amount_in = amount_in_for_exact_out(reserve_in, reserve_out, fee, amount_out)?;
+    if amount_in > max_in {
+        return Err(CurveError::SlippageLimitExceeded);
+    }
+
+    // Run the computed input through the curve so fees and rounding match a
+    // regular swap, with the requested output as the minimum.
+    swap(fee, reserve_in, reserve_out, amount_in, amount_out)
+}
+
+// Gross input (including the LP fee) needed to take exactly `amount_out` out of
+// a constant-product pool with reserves `x` (input side) and `y` (output side).
+// Both divisions round up so the pool never ends up below its invariant.
+pub fn amount_in_for_exact_out(
+    x: u64,
+    y: u64,
+    fee: u16,
+    amount_out: u64,
+) -> Result<u64, CurveError> {
+    if x == 0 || y == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+    if amount_out >= y {
+        return Err(CurveError::InsufficientBalance);
+    }
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+
+    let numerator = (x as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(CurveError::Overflow)?;
+    let denominator = (y - amount_out) as u128;
+    let amount_in_after_fee = numerator.div_ceil(denominator);
+
+    let amount_in = amount_in_after_fee
+        .checked_mul(10_000)
+        .ok_or(CurveError::Overflow)?
+        .div_ceil(10_000 - fee as u128);
+
+    u64::try_from(amount_in).map_err(|_| CurveError::Overflow)
+}
+
+pub fn spot_price(reserve_in: u64, reserve_out: u64) -> Result<u128, CurveError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    Ok(((reserve_out as u128) << 64) / reserve_in as u128)
+}
+
+// Part of a single-sided deposit of `amount_in` to swap so that the rest and the
+// swap output are in the ratio of the reserves after the swap, which mints the
+// most LP tokens. With g = (10000 - fee) / 10000 it is the positive root of
+// g * s^2 + reserve_in * (1 + g) * s - reserve_in * amount_in = 0, rounded down.
+pub fn single_sided_split(fee: u16, reserve_in: u64, amount_in: u64) -> Result<u64, CurveError> {
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+
+    let (reserve_in, amount_in) = (reserve_in as u128, amount_in as u128);
+    let g = 10_000 - fee as u128;
+    let b = 10_000 + g;
+
+    // s = (sqrt(reserve_in * k) - reserve_in * b) / 2g, with
+    // k = reserve_in * b^2 + 4 * 10000 * g * amount_in.
+    let k = (reserve_in * b * b)
+        .checked_add(4 * 10_000 * g * amount_in)
+        .ok_or(CurveError::Overflow)?;
+    let split = (sqrt_mul(reserve_in, k) - reserve_in * b) / (2 * g);
+
+    Ok(split.min(amount_in) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn swap_rounds_output_down() {
+        // 1_000 * 100 / 1_100 = 90.9
+        let res = swap(0, 1_000, 1_000, 100, 0).unwrap();
+        assert_eq!(res.withdraw, 90);
+
+        let res = swap(30, 1_000_000, 1_000_000, 10_000, 0).unwrap();
+        assert_eq!(res.fee, 30);
+        assert_eq!(res.withdraw, 9_871);
+    }
+
+    #[test]
+    fn swap_enforces_min_out() {
+        let res = swap(0, 1_000, 1_000, 100, 91);
+        assert!(matches!(res, Err(CurveError::SlippageLimitExceeded)));
+    }
+
+    #[test]
+    fn swap_exact_out_cannot_drain_pool() {
+        let res = swap_exact_out(30, 1_000, 1_000, 1_000, u64::MAX);
+        assert!(matches!(res, Err(CurveError::InsufficientBalance)));
+    }
+
+    // g * s^2 + reserve_in * (1 + g) * s <= reserve_in * amount_in, scaled by 10000.
+    fn at_most_root(fee: u16, reserve_in: u64, amount_in: u64, s: u64) -> bool {
+        let (r, a, s) = (reserve_in as u128, amount_in as u128, s as u128);
+        let g = 10_000 - fee as u128;
+        g * s * s + r * (10_000 + g) * s <= r * a * 10_000
+    }
+
+    #[test]
+    fn single_sided_split_is_the_root_rounded_down() {
+        for fee in [0, 30, 100, 9_999] {
+            for reserve_in in [1, 1_000, 1_000_000_007, 1 << 50] {
+                for amount_in in [1, 999, 1_000_000, 1 << 50] {
+                    let s = single_sided_split(fee, reserve_in, amount_in).unwrap();
+                    assert!(at_most_root(fee, reserve_in, amount_in, s));
+                    assert!(!at_most_root(fee, reserve_in, amount_in, s + 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_sided_split_swaps_about_half_of_a_small_deposit() {
+        let s = single_sided_split(0, 1_000_000_000, 1_000).unwrap();
+        assert_eq!(s, 499);
+    }
+
+    fn reserve() -> impl Strategy<Value = u64> {
+        1_000u64..1_000_000_000_000_000
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_decreases_k(
+            fee in 0u16..1_000,
+            x in reserve(),
+            y in reserve(),
+            amount in 1u64..1_000_000_000_000,
+        ) {
+            let res = swap(fee, x, y, amount, 0).unwrap();
+            let k = x as u128 * y as u128;
+            let k_after = (x + res.deposit - res.fee) as u128 * (y - res.withdraw) as u128;
+            prop_assert!(k_after >= k);
+        }
+
+        #[test]
+        fn swap_exact_out_delivers_the_output(
+            fee in 0u16..1_000,
+            x in reserve(),
+            y in reserve(),
+            share in 1u64..1_000,
+        ) {
+            let amount_out = (y as u128 * share as u128 / 1_000) as u64;
+            prop_assume!(amount_out > 0);
+
+            let res = swap_exact_out(fee, x, y, amount_out, u64::MAX).unwrap();
+            prop_assert!(res.withdraw >= amount_out);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+pub mod constant_product;
+pub mod stable_swap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveError {
+    InvalidPrecision,
+    Overflow,
+    Underflow,
+    InvalidFeeAmount,
+    InsufficientBalance,
+    InsufficientLiquidity,
+    ZeroBalance,
+    SlippageLimitExceeded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    ConstantProduct,
+    StableSwap { amp: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapResult {
+    pub deposit: u64,
+    pub withdraw: u64,
+    pub fee: u64,
+}
+
+// Swap exactly `amount_in` of the input token for at least `min_out` of the output token.
+pub fn swap(
+    curve: Curve,
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    min_out: u64,
+) -> Result<SwapResult, CurveError> {
+    match curve {
+        Curve::ConstantProduct => {
+            constant_product::swap(fee, reserve_in, reserve_out, amount_in, min_out)
+        }
+        Curve::StableSwap { amp } => {
+            stable_swap::swap(amp, fee, reserve_in, reserve_out, amount_in, min_out)
+        }
+    }
+}
+
+// Swap at most `max_in` of the input token for exactly `amount_out` of the output token.
+pub fn swap_exact_out(
+    curve: Curve,
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    max_in: u64,
+) -> Result<SwapResult, CurveError> {
+    match curve {
+        Curve::ConstantProduct => {
+            constant_product::swap_exact_out(fee, reserve_in, reserve_out, amount_out, max_in)
+        }
+        Curve::StableSwap { amp } => {
+            stable_swap::swap_exact_out(amp, fee, reserve_in, reserve_out, amount_out, max_in)
+        }
+    }
+}
+
+// Marginal price of the input token in the output token, as Q64.64.
+pub fn spot_price(curve: Curve, reserve_in: u64, reserve_out: u64) -> Result<u128, CurveError> {
+    match curve {
+        Curve::ConstantProduct => constant_product::spot_price(reserve_in, reserve_out),
+        Curve::StableSwap { amp } => stable_swap::spot_price(amp, reserve_in, reserve_out),
+    }
+}
@@ -0,0 +1,377 @@
+use super::{CurveError, SwapResult};
+use crate::math::{mul_div, mul_div_rem};
+
+// StableSwap invariant for two coins, as in Curve:
+// A * n^n * (x + y) + D = A * D * n^n + D^(n+1) / (n^n * x * y)
+//
+// The amplification coefficient `amp` moves the curve between constant sum
+// (amp -> infinity) and constant product (amp -> 0).
+
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+// Solves the invariant for D with Newton's method.
+pub fn compute_d(amp: u64, x: u64, y: u64) -> Result<u128, CurveError> {
+    let (x, y) = (x as u128, y as u128);
+    let sum = x + y;
+    if sum == 0 {
+        return Ok(0);
+    }
+    if x == 0 || y == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    let ann = amp as u128 * N_COINS;
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^(n+1) / (n^n * x * y)
+        let mut d_p = d;
+        d_p = mul_div(d_p, d, x * N_COINS).ok_or(CurveError::Overflow)?;
+        d_p = mul_div(d_p, d, y * N_COINS).ok_or(CurveError::Overflow)?;
+
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|n| n.checked_add(d_p.checked_mul(N_COINS)?))
+            .ok_or(CurveError::Overflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|n| n.checked_add(d_p.checked_mul(N_COINS + 1)?))
+            .ok_or(CurveError::Overflow)?;
+
+        let d_prev = d;
+        d = mul_div(numerator, d, denominator).ok_or(CurveError::Overflow)?;
+
+        if d.abs_diff(d_prev) <= 1 {
+            return Ok(d);
+        }
+    }
+
+    // Newton's method did not reach the required precision.
+    Err(CurveError::InvalidPrecision)
+}
+
+// Solves the invariant for the balance of one coin, given the balance of the
+// other coin and D.
+pub fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128, CurveError> {
+    if x == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    let ann = amp as u128 * N_COINS;
+
+    // c = D^(n+1) / (n^n * x * Ann)
+    let c = mul_div(d, d, x * N_COINS).ok_or(CurveError::Overflow)?;
+    let c = mul_div(c, d, ann * N_COINS).ok_or(CurveError::Overflow)?;
+    let b = x.checked_add(d / ann).ok_or(CurveError::Overflow)?;
+
+    let mut y = d;
+
+    for _ in 0..MAX_ITERATIONS {
+        // y = (y^2 + c) / (2y + b - D), split so that y^2 never has to fit in a u128.
+        let denominator = y
+            .checked_mul(2)
+            .and_then(|n| n.checked_add(b))
+            .and_then(|n| n.checked_sub(d))
+            .ok_or(CurveError::Underflow)?;
+        if denominator == 0 {
+            return Err(CurveError::Underflow);
+        }
+
+        let (q1, r1) = mul_div_rem(y, y, denominator).ok_or(CurveError::Overflow)?;
+        let (q2, r2) = (c / denominator, c % denominator);
+
+        let y_prev = y;
+        y = q1 + q2 + (r1 + r2) / denominator;
+
+        if y.abs_diff(y_prev) <= 1 {
+            return Ok(y);
+        }
+    }
+
+    Err(CurveError::InvalidPrecision)
+}
+
+pub fn swap(
+    amp: u64,
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    min_out: u64,
+) -> Result<SwapResult, CurveError> {
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+
+    let fee_amount = (amount_in as u128 * fee as u128 / 10_000) as u64;
+    let amount_in_after_fee = amount_in - fee_amount;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = reserve_in as u128 + amount_in_after_fee as u128;
+    let new_reserve_out = compute_y(amp, new_reserve_in, d)?;
+
+    // Take one off the output so rounding always favours the pool.
+    let amount_out = (reserve_out as u128)
+        .saturating_sub(new_reserve_out)
+        .saturating_sub(1) as u64;
+
+    if amount_out < min_out {
+        return Err(CurveError::SlippageLimitExceeded);
+    }
+
+    Ok(SwapResult {
+        deposit: amount_in,
+        withdraw: amount_out,
+        fee: fee_amount,
+    })
+}
+
+pub fn swap_exact_out(
+    amp: u64,
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    max_in: u64,
+) -> Result<SwapResult, CurveError> {
+    if fee >= 10_000 {
+        return Err(CurveError::InvalidFeeAmount);
+    }
+    if amount_out >= reserve_out {
+        return Err(CurveError::InsufficientBalance);
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_out = (reserve_out - amount_out) as u128;
+    let new_reserve_in = compute_y(amp, new_reserve_out, d)?;
+
+    // Add one to the input so rounding always favours the pool.
+    let amount_in_after_fee = new_reserve_in
+        .checked_sub(reserve_in as u128)
+        .ok_or(CurveError::Underflow)?
+        + 1;
+    let amount_in = amount_in_after_fee
+        .checked_mul(10_000)
+        .ok_or(CurveError::Overflow)?
+        .div_ceil(10_000 - fee as u128);
+    let amount_in = u64::try_from(amount_in).map_err(|_| CurveError::Overflow)?;
+
+    if amount_in > max_in {
+        return Err(CurveError::SlippageLimitExceeded);
+    }
+
+    Ok(SwapResult {
+        deposit: amount_in,
+        withdraw: amount_out,
+        fee: amount_in - amount_in_after_fee as u64,
+    })
+}
+
+// Marginal price of the input token in the output token, as Q64.64. With F the
+// invariant rearranged to zero, this is -dy/dx = (dF/dx) / (dF/dy), i.e.
+// (Ann + D^3 / (4 * x^2 * y)) / (Ann + D^3 / (4 * x * y^2)).
+pub fn spot_price(amp: u64, reserve_in: u64, reserve_out: u64) -> Result<u128, CurveError> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let ann = (amp as u128 * N_COINS) << 64;
+
+    // D^3 / (4 * a^2 * b) as Q64.64.
+    let term = |a: u64, b: u64| -> Option<u128> {
+        let ratio = mul_div(d, 1 << 64, a as u128)?;
+        let ratio = mul_div(ratio, ratio, 1 << 64)?;
+        mul_div(ratio, d, 4 * b as u128)
+    };
+
+    let numerator = term(reserve_in, reserve_out)
+        .and_then(|n| n.checked_add(ann))
+        .ok_or(CurveError::Overflow)?;
+    let denominator = term(reserve_out, reserve_in)
+        .and_then(|n| n.checked_add(ann))
+        .ok_or(CurveError::Overflow)?;
+
+    mul_div(numerator, 1 << 64, denominator).ok_or(CurveError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const AMP: u64 = 100;
+
+    #[test]
+    fn d_of_balanced_pool_is_sum() {
+        assert_eq!(compute_d(AMP, 1_000_000, 1_000_000).unwrap(), 2_000_000);
+        assert_eq!(
+            compute_d(1, u64::MAX, u64::MAX).unwrap(),
+            2 * u64::MAX as u128
+        );
+    }
+
+    #[test]
+    fn d_of_empty_pool_is_zero() {
+        assert_eq!(compute_d(AMP, 0, 0).unwrap(), 0);
+        assert!(matches!(compute_d(AMP, 0, 1), Err(CurveError::ZeroBalance)));
+    }
+
+    #[test]
+    fn compute_y_inverts_compute_d() {
+        let d = compute_d(AMP, 1_000_000, 3_000_000).unwrap();
+        let y = compute_y(AMP, 1_000_000, d).unwrap();
+        assert!(y.abs_diff(3_000_000) <= 1);
+    }
+
+    #[test]
+    fn balanced_swap_is_close_to_one_to_one() {
+        let res = swap(AMP, 0, 1_000_000_000, 1_000_000_000, 1_000_000, 0).unwrap();
+        assert_eq!(res.deposit, 1_000_000);
+        assert_eq!(res.fee, 0);
+        assert!(res.withdraw < 1_000_000);
+        assert!(res.withdraw > 999_900);
+    }
+
+    #[test]
+    fn swap_beats_constant_product_near_peg() {
+        let (x, y, amount) = (1_000_000_000u64, 1_000_000_000u64, 10_000_000u64);
+        let constant_product = (y as u128 * amount as u128 / (x as u128 + amount as u128)) as u64;
+        let res = swap(AMP, 0, x, y, amount, 0).unwrap();
+        assert!(res.withdraw > constant_product);
+    }
+
+    #[test]
+    fn swap_charges_fee_on_input() {
+        let res = swap(AMP, 30, 1_000_000_000, 1_000_000_000, 1_000_000, 0).unwrap();
+        assert_eq!(res.fee, 3_000);
+        let no_fee = swap(AMP, 0, 1_000_000_000, 1_000_000_000, 1_000_000, 0).unwrap();
+        assert!(res.withdraw < no_fee.withdraw);
+    }
+
+    #[test]
+    fn swap_enforces_min_out() {
+        let res = swap(AMP, 0, 1_000_000, 1_000_000, 1_000, 1_000);
+        assert!(matches!(res, Err(CurveError::SlippageLimitExceeded)));
+    }
+
+    #[test]
+    fn swap_exact_out_enforces_max_in() {
+        let res = swap_exact_out(AMP, 30, 1_000_000, 1_000_000, 1_000, 1_000);
+        assert!(matches!(res, Err(CurveError::SlippageLimitExceeded)));
+    }
+
+    #[test]
+    fn swap_exact_out_cannot_drain_pool() {
+        let res = swap_exact_out(AMP, 30, 1_000_000, 1_000_000, 1_000_000, u64::MAX);
+        assert!(matches!(res, Err(CurveError::InsufficientBalance)));
+    }
+
+    #[test]
+    fn rejects_invalid_fee() {
+        let res = swap(AMP, 10_000, 1_000_000, 1_000_000, 1_000, 0);
+        assert!(matches!(res, Err(CurveError::InvalidFeeAmount)));
+    }
+
+    #[test]
+    fn spot_price_of_balanced_pool_is_one() {
+        let price = spot_price(AMP, 1_000_000_000, 1_000_000_000).unwrap();
+        assert!(price.abs_diff(1 << 64) <= 1 << 20);
+    }
+
+    #[test]
+    fn spot_price_matches_a_small_swap() {
+        let (x, y) = (1_000_000_000_000u64, 3_000_000_000_000u64);
+        let price = spot_price(AMP, x, y).unwrap();
+        assert!(price > 1 << 64);
+
+        let res = swap(AMP, 0, x, y, 1_000_000, 0).unwrap();
+        let swap_price = ((res.withdraw as u128) << 64) / res.deposit as u128;
+        assert!(price.abs_diff(swap_price) < price / 10_000);
+    }
+
+    fn reserve() -> impl Strategy<Value = u64> {
+        1_000u64..1_000_000_000_000_000
+    }
+
+    proptest! {
+        #[test]
+        fn d_is_symmetric(amp in MIN_AMP..=MAX_AMP, x in reserve(), y in reserve()) {
+            prop_assert_eq!(compute_d(amp, x, y).unwrap(), compute_d(amp, y, x).unwrap());
+        }
+
+        #[test]
+        fn d_is_bounded_by_sum(amp in MIN_AMP..=MAX_AMP, x in reserve(), y in reserve()) {
+            let d = compute_d(amp, x, y).unwrap();
+            prop_assert!(d <= x as u128 + y as u128 + 1);
+        }
+
+        #[test]
+        fn swap_never_decreases_d(
+            amp in MIN_AMP..=MAX_AMP,
+            fee in 0u16..1_000,
+            x in reserve(),
+            y in reserve(),
+            amount in 1u64..1_000_000_000_000,
+        ) {
+            let d = compute_d(amp, x, y).unwrap();
+            let res = swap(amp, fee, x, y, amount, 0).unwrap();
+            prop_assert!(res.withdraw < y);
+
+            let d_after = compute_d(amp, x + res.deposit - res.fee, y - res.withdraw).unwrap();
+            prop_assert!(d_after + 1 >= d);
+        }
+
+        #[test]
+        fn swap_exact_out_never_decreases_d(
+            amp in MIN_AMP..=MAX_AMP,
+            fee in 0u16..1_000,
+            x in reserve(),
+            y in reserve(),
+            share in 1u64..1_000,
+        ) {
+            let amount_out = (y as u128 * share as u128 / 1_000) as u64;
+            prop_assume!(amount_out > 0);
+
+            let d = compute_d(amp, x, y).unwrap();
+            let res = swap_exact_out(amp, fee, x, y, amount_out, u64::MAX).unwrap();
+            prop_assert_eq!(res.withdraw, amount_out);
+
+            let d_after = compute_d(amp, x + res.deposit - res.fee, y - res.withdraw).unwrap();
+            prop_assert!(d_after + 1 >= d);
+        }
+
+        #[test]
+        fn swap_exact_out_input_buys_at_least_the_output(
+            amp in MIN_AMP..=MAX_AMP,
+            fee in 0u16..1_000,
+            x in reserve(),
+            y in reserve(),
+            share in 1u64..500,
+        ) {
+            let amount_out = (y as u128 * share as u128 / 1_000) as u64;
+            prop_assume!(amount_out > 0);
+
+            let exact_out = swap_exact_out(amp, fee, x, y, amount_out, u64::MAX).unwrap();
+            let exact_in = swap(amp, fee, x, y, exact_out.deposit, 0).unwrap();
+            prop_assert!(exact_in.withdraw + 1 >= amount_out);
+        }
+
+        #[test]
+        fn larger_input_gives_larger_output(
+            amp in MIN_AMP..=MAX_AMP,
+            x in reserve(),
+            y in reserve(),
+            amount in 1u64..1_000_000_000_000,
+        ) {
+            let small = swap(amp, 30, x, y, amount, 0).unwrap();
+            let large = swap(amp, 30, x, y, amount.saturating_mul(2), 0).unwrap();
+            prop_assert!(large.withdraw >= small.withdraw);
+        }
+    }
+}
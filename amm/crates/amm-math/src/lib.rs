@@ -0,0 +1,10 @@
+// Curve math of the amm program, shared with off-chain code so that quotes match
+// what the program does, down to fees and rounding.
+#![cfg_attr(not(test), no_std)]
+
+pub mod curve;
+pub mod math;
+pub mod quote;
+
+pub use curve::{Curve, CurveError, SwapResult};
+pub use quote::*;
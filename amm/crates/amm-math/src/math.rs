@@ -0,0 +1,209 @@
+// Fixed-point helpers for intermediate values that don't fit in a u128.
+
+const MASK: u128 = u64::MAX as u128;
+
+// Full 256-bit product of `a` and `b` as (high, low) halves.
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a0, a1) = (a & MASK, a >> 64);
+    let (b0, b1) = (b & MASK, b >> 64);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let lo = (p00 & MASK) | ((mid & MASK) << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+
+    (hi, lo)
+}
+
+// Returns floor(a * b / c) and the remainder, or None if c is zero or the
+// quotient doesn't fit in a u128.
+pub fn mul_div_rem(a: u128, b: u128, c: u128) -> Option<(u128, u128)> {
+    if c == 0 {
+        return None;
+    }
+
+    let (hi, lo) = full_mul(a, b);
+    if hi == 0 {
+        return Some((lo / c, lo % c));
+    }
+    if hi >= c {
+        return None;
+    }
+
+    // Long division of the 256-bit product, one bit at a time.
+    let mut rem = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+
+    Some((quotient, rem))
+}
+
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    mul_div_rem(a, b, c).map(|(quotient, _)| quotient)
+}
+
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    let (quotient, rem) = mul_div_rem(a, b, c)?;
+    match rem > 0 {
+        true => quotient.checked_add(1),
+        false => Some(quotient),
+    }
+}
+
+// Returns (a * b) >> 64, or None if the result doesn't fit in a u128.
+pub fn mul_shr_64(a: u128, b: u128) -> Option<u128> {
+    let (hi, lo) = full_mul(a, b);
+    if hi >> 64 != 0 {
+        return None;
+    }
+    Some((hi << 64) | (lo >> 64))
+}
+
+// Returns (a * b) >> 128, which always fits in a u128.
+pub fn mul_shr_128(a: u128, b: u128) -> u128 {
+    full_mul(a, b).0
+}
+
+// Returns floor(sqrt(a * b)), for products that don't fit in a u128.
+pub fn sqrt_mul(a: u128, b: u128) -> u128 {
+    let (hi, lo) = full_mul(a, b);
+    let bits = match hi {
+        0 => 128 - lo.leading_zeros(),
+        _ => 256 - hi.leading_zeros(),
+    };
+    if bits == 0 {
+        return 0;
+    }
+
+    // Newton's method from a power of two at or above the root. It stays at or
+    // above the root, so a * b / x fits, and stops once it no longer decreases.
+    let mut x = match bits.div_ceil(2) {
+        128 => u128::MAX,
+        half => 1 << half,
+    };
+    loop {
+        let quotient = mul_div(a, b, x).unwrap_or(0);
+        // floor((x + quotient) / 2) without overflowing.
+        let next = x / 2 + quotient / 2 + (x & quotient & 1);
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+// Returns floor(sqrt(n)).
+pub fn sqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method from an initial guess that is at least the root.
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_small_values() {
+        assert_eq!(mul_div(6, 7, 4), Some(10));
+        assert_eq!(mul_div_ceil(6, 7, 4), Some(11));
+        assert_eq!(mul_div_ceil(6, 8, 4), Some(12));
+        assert_eq!(mul_div_rem(6, 7, 4), Some((10, 2)));
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+
+    #[test]
+    fn mul_div_wide_product() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(mul_div(u128::MAX, 2, 4), Some(u128::MAX / 2));
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 90), Some(1 << 110));
+        assert_eq!(mul_div(u128::MAX, u128::MAX, 1), None);
+    }
+
+    #[test]
+    fn mul_shr_wide_product() {
+        assert_eq!(mul_shr_64(1 << 64, 1 << 64), Some(1 << 64));
+        assert_eq!(mul_shr_64(u128::MAX, 1 << 64), Some(u128::MAX));
+        assert_eq!(mul_shr_64(u128::MAX, 1 << 65), None);
+        assert_eq!(mul_shr_128(u128::MAX, 1 << 64), (1 << 64) - 1);
+        assert_eq!(mul_shr_128(1 << 127, 4), 2);
+    }
+
+    #[test]
+    fn sqrt_mul_rounds_down() {
+        assert_eq!(sqrt_mul(0, 5), 0);
+        assert_eq!(sqrt_mul(1, 1), 1);
+        assert_eq!(sqrt_mul(2, 4), 2);
+        assert_eq!(sqrt_mul(3, 3), 3);
+        assert_eq!(sqrt_mul(3, 5), 3);
+        assert_eq!(sqrt_mul(1 << 100, 1 << 100), 1 << 100);
+        assert_eq!(sqrt_mul(u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(sqrt_mul(u128::MAX, 1), u64::MAX as u128);
+    }
+
+    #[test]
+    fn sqrt_mul_brackets_the_root() {
+        let values = [
+            2u128,
+            7,
+            1_000_003,
+            u64::MAX as u128,
+            1 << 90,
+            u128::MAX / 3,
+        ];
+        for a in values {
+            for b in values {
+                let root = sqrt_mul(a, b);
+                let (hi, lo) = full_mul(a, b);
+                assert!(full_mul(root, root) <= (hi, lo));
+                assert!(full_mul(root + 1, root + 1) > (hi, lo));
+            }
+        }
+    }
+
+    #[test]
+    fn mul_div_matches_u128_when_it_fits() {
+        let values = [0u128, 1, 3, 1_000_007, u64::MAX as u128, 1 << 70];
+        for a in values {
+            for b in values {
+                for c in [1u128, 7, 1 << 64, u128::MAX] {
+                    if let Some(product) = a.checked_mul(b) {
+                        assert_eq!(mul_div_rem(a, b, c), Some((product / c, product % c)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_rounds_down() {
+        assert_eq!(sqrt(0), 0);
+        assert_eq!(sqrt(1), 1);
+        assert_eq!(sqrt(8), 2);
+        assert_eq!(sqrt(9), 3);
+        assert_eq!(sqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX as u128);
+        assert_eq!(sqrt(u128::MAX), u64::MAX as u128);
+    }
+}
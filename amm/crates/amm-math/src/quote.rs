@@ -0,0 +1,485 @@
+// Quotes for every instruction of a Config pool. Amounts are what reaches or
+// leaves the vaults, so Token-2022 transfer fees, which the program charges on
+// top, are left to the caller.
+
+use crate::{
+    curve::{self, constant_product, Curve, CurveError, SwapResult},
+    math::{mul_div, mul_div_ceil, sqrt},
+};
+
+// LP tokens locked in the pool on the first deposit, which nobody can withdraw.
+// This keeps the supply from ever returning to zero, so the first depositor can't
+// inflate the price of a single LP token to round out later depositors.
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// Most splits a single-sided deposit into a stable swap pool tries.
+const MAX_SPLIT_EVALUATIONS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amounts {
+    pub x: u64,
+    pub y: u64,
+}
+
+// A pool as the program prices it. The reserves exclude uncollected protocol
+// fees, i.e. they are the vault balances less `protocol_fees_x` and `protocol_fees_y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pool {
+    pub curve: Curve,
+    pub fee: u16,
+    pub protocol_fee: u16,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub supply: u64,
+}
+
+// LP tokens minted to the first depositor of `x` and `y`, on top of the locked
+// `MINIMUM_LIQUIDITY`.
+pub fn quote_initial_deposit(x: u64, y: u64) -> Result<u64, CurveError> {
+    if x == 0 || y == 0 {
+        return Err(CurveError::ZeroBalance);
+    }
+
+    let liquidity = sqrt(x as u128 * y as u128) as u64;
+    if liquidity <= MINIMUM_LIQUIDITY {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    Ok(liquidity - MINIMUM_LIQUIDITY)
+}
+
+// Part of a swap fee that is set aside for the protocol.
+pub fn protocol_share(protocol_fee: u16, fee: u64) -> u64 {
+    (fee as u128 * protocol_fee as u128 / 10_000) as u64
+}
+
+impl Pool {
+    pub fn quote_swap(&self, is_x: bool, amount_in: u64) -> Result<SwapResult, CurveError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        curve::swap(self.curve, self.fee, reserve_in, reserve_out, amount_in, 0)
+    }
+
+    // Input needed for exactly `amount_out` of the output token.
+    pub fn quote_swap_exact_out(
+        &self,
+        is_x: bool,
+        amount_out: u64,
+    ) -> Result<SwapResult, CurveError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        curve::swap_exact_out(
+            self.curve,
+            self.fee,
+            reserve_in,
+            reserve_out,
+            amount_out,
+            u64::MAX,
+        )
+    }
+
+    // Amounts proportional to the reserves leave the price unchanged on every
+    // curve type, so LP shares are priced the same way. Deposits round up and
+    // withdrawals round down, in favour of the pool.
+    pub fn quote_deposit(&self, amount: u64) -> Result<Amounts, CurveError> {
+        if self.supply == 0 {
+            return Err(CurveError::ZeroBalance);
+        }
+
+        let share = |reserve: u64| {
+            mul_div_ceil(reserve as u128, amount as u128, self.supply as u128)
+                .and_then(|n| u64::try_from(n).ok())
+                .ok_or(CurveError::Overflow)
+        };
+
+        Ok(Amounts {
+            x: share(self.reserve_x)?,
+            y: share(self.reserve_y)?,
+        })
+    }
+
+    pub fn quote_withdraw(&self, amount: u64) -> Result<Amounts, CurveError> {
+        if self.supply == 0 {
+            return Err(CurveError::ZeroBalance);
+        }
+        if amount > self.supply {
+            return Err(CurveError::InsufficientBalance);
+        }
+
+        let share = |reserve: u64| {
+            mul_div(reserve as u128, amount as u128, self.supply as u128)
+                .ok_or(CurveError::Overflow)
+                .map(|n| n as u64)
+        };
+
+        Ok(Amounts {
+            x: share(self.reserve_x)?,
+            y: share(self.reserve_y)?,
+        })
+    }
+
+    // Split a single-sided deposit of `amount_in` into a swap to the other token
+    // and a balanced deposit of what is left, choosing the split that mints the
+    // most LP tokens. Returns the swap and the LP amount.
+    pub fn quote_deposit_single(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+    ) -> Result<(SwapResult, u64), CurveError> {
+        if self.supply == 0 {
+            return Err(CurveError::ZeroBalance);
+        }
+
+        // LP tokens minted for each side after swapping `swap_in`. The input side
+        // shrinks and the output side grows as more is swapped, so the best split
+        // is where they cross.
+        let lp_for = |swap_in: u64| -> Result<(SwapResult, u64, u64), CurveError> {
+            let res = self.quote_swap(is_x, swap_in)?;
+            let (reserve_in, reserve_out) = self.after_swap(is_x, &res)?.reserves(is_x);
+            if reserve_out == 0 {
+                return Err(CurveError::InsufficientBalance);
+            }
+
+            let lp_in = (amount_in - swap_in) as u128 * self.supply as u128 / reserve_in as u128;
+            let lp_out = res.withdraw as u128 * self.supply as u128 / reserve_out as u128;
+
+            Ok((
+                res,
+                u64::try_from(lp_in).map_err(|_| CurveError::Overflow)?,
+                u64::try_from(lp_out).map_err(|_| CurveError::Overflow)?,
+            ))
+        };
+
+        let (reserve_in, _) = self.reserves(is_x);
+        let estimate = constant_product::single_sided_split(self.fee, reserve_in, amount_in)?
+            .clamp(1, amount_in);
+
+        match self.curve {
+            // The estimate is exact up to rounding, which can favour the next amount.
+            Curve::ConstantProduct => {
+                let (res, lp_in, lp_out) = lp_for(estimate)?;
+                let (next, next_in, next_out) = lp_for((estimate + 1).min(amount_in))?;
+                match next_in.min(next_out) > lp_in.min(lp_out) {
+                    true => Ok((next, next_in.min(next_out))),
+                    false => Ok((res, lp_in.min(lp_out))),
+                }
+            }
+            Curve::StableSwap { .. } => {
+                best_split(amount_in, estimate, MAX_SPLIT_EVALUATIONS, lp_for)
+            }
+        }
+    }
+
+    // Burn `amount` LP tokens for only one token. The other side of the withdrawal
+    // is swapped back through the curve, paying the usual fee. Returns that swap,
+    // if there is one, and the total amount received.
+    pub fn quote_withdraw_single(
+        &self,
+        is_x: bool,
+        amount: u64,
+    ) -> Result<(Option<SwapResult>, u64), CurveError> {
+        let amounts = self.quote_withdraw(amount)?;
+        let (kept, swapped) = match is_x {
+            true => (amounts.x, amounts.y),
+            false => (amounts.y, amounts.x),
+        };
+
+        if swapped == 0 {
+            return Ok((None, kept));
+        }
+
+        let pool = Pool {
+            reserve_x: self.reserve_x - amounts.x,
+            reserve_y: self.reserve_y - amounts.y,
+            supply: self.supply - amount,
+            ..*self
+        };
+        let res = pool.quote_swap(!is_x, swapped)?;
+        let out = kept.checked_add(res.withdraw).ok_or(CurveError::Overflow)?;
+
+        Ok((Some(res), out))
+    }
+
+    // Marginal price of the input token in the output token, as Q64.64.
+    pub fn spot_price(&self, is_x: bool) -> Result<u128, CurveError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        curve::spot_price(self.curve, reserve_in, reserve_out)
+    }
+
+    // How much worse than the spot price the swap executes, in basis points. The
+    // LP fee isn't counted, so this is only the slippage along the curve.
+    pub fn price_impact(&self, is_x: bool, res: &SwapResult) -> Result<u64, CurveError> {
+        let amount_in = res.deposit - res.fee;
+        if amount_in == 0 {
+            return Err(CurveError::ZeroBalance);
+        }
+
+        let spot_price = self.spot_price(is_x)?;
+        let price = mul_div(res.withdraw as u128, 1 << 64, amount_in as u128)
+            .ok_or(CurveError::Overflow)?;
+        let ratio = mul_div(price, 10_000, spot_price).ok_or(CurveError::Overflow)?;
+
+        Ok(10_000u64.saturating_sub(ratio as u64))
+    }
+
+    // The pool after `res`, with the protocol share of the fee set aside.
+    pub fn after_swap(&self, is_x: bool, res: &SwapResult) -> Result<Pool, CurveError> {
+        let (reserve_in, reserve_out) = self.reserves(is_x);
+        let reserve_in = reserve_in
+            .checked_add(res.deposit - protocol_share(self.protocol_fee, res.fee))
+            .ok_or(CurveError::Overflow)?;
+        let reserve_out = reserve_out
+            .checked_sub(res.withdraw)
+            .ok_or(CurveError::InsufficientBalance)?;
+
+        let (reserve_x, reserve_y) = match is_x {
+            true => (reserve_in, reserve_out),
+            false => (reserve_out, reserve_in),
+        };
+
+        Ok(Pool {
+            reserve_x,
+            reserve_y,
+            ..*self
+        })
+    }
+
+    fn reserves(&self, is_x: bool) -> (u64, u64) {
+        match is_x {
+            true => (self.reserve_x, self.reserve_y),
+            false => (self.reserve_y, self.reserve_x),
+        }
+    }
+}
+
+// Searches for the swap amount of a single-sided deposit where `lp_for` crosses
+// over. `lp_for(s)` returns the swap and the LP tokens earned by the input left
+// after swapping `s` and by the swap output. The first shrinks and the second
+// grows with `s`, so the deposit mints the most where they cross.
+//
+// This is regula falsi with the Illinois modification, bracketing the crossing
+// between `estimate` and 1 or `amount_in`. It calls `lp_for` at most
+// `max_evaluations` times and returns the best split it saw, so converging
+// slowly costs the depositor a little but never breaks the deposit.
+fn best_split<T>(
+    amount_in: u64,
+    estimate: u64,
+    max_evaluations: usize,
+    mut lp_for: impl FnMut(u64) -> Result<(T, u64, u64), CurveError>,
+) -> Result<(T, u64), CurveError> {
+    let mut best: Option<(T, u64)> = None;
+    let mut evaluate = |swap_in: u64| -> Result<i128, CurveError> {
+        let (res, lp_in, lp_out) = lp_for(swap_in)?;
+        let lp = lp_in.min(lp_out);
+        if !matches!(&best, Some((_, best_lp)) if *best_lp >= lp) {
+            best = Some((res, lp));
+        }
+        Ok(lp_out as i128 - lp_in as i128)
+    };
+
+    // (swap_in, lp_out - lp_in) on either side of the crossing.
+    let mut low = (estimate, evaluate(estimate)?);
+    let mut high = low;
+    let mut evaluations = 1;
+    match low.1 < 0 {
+        true => high = (amount_in, evaluate(amount_in)?),
+        false => low = (1, evaluate(1)?),
+    }
+    evaluations += 1;
+
+    let mut last_low = None;
+    while evaluations < max_evaluations && low.1 < 0 && high.1 > 0 && high.0 - low.0 > 1 {
+        let step = (high.0 - low.0) as u128 * low.1.unsigned_abs() / (high.1 - low.1) as u128;
+        let swap_in = (low.0 + step as u64).clamp(low.0 + 1, high.0 - 1);
+        let gap = evaluate(swap_in)?;
+        evaluations += 1;
+
+        // Halve the side that stayed put twice in a row, so it can't stall.
+        let moved_low = gap < 0;
+        match moved_low {
+            true => low = (swap_in, gap),
+            false => high = (swap_in, gap),
+        }
+        if last_low == Some(moved_low) {
+            match moved_low {
+                true => high.1 /= 2,
+                false => low.1 /= 2,
+            }
+        }
+        last_low = Some(moved_low);
+    }
+
+    best.ok_or(CurveError::ZeroBalance)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn pool(curve: Curve) -> Pool {
+        Pool {
+            curve,
+            fee: 30,
+            protocol_fee: 1_000,
+            reserve_x: 1_000_000,
+            reserve_y: 1_000_000,
+            supply: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn initial_deposit_locks_minimum_liquidity() {
+        assert_eq!(quote_initial_deposit(4_000, 1_000).unwrap(), 1_000);
+        assert_eq!(
+            quote_initial_deposit(1_000, 1_000),
+            Err(CurveError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            quote_initial_deposit(0, 1_000),
+            Err(CurveError::ZeroBalance)
+        );
+    }
+
+    #[test]
+    fn deposit_rounds_up_and_withdraw_rounds_down() {
+        let pool = Pool {
+            reserve_x: 1_000,
+            reserve_y: 3_001,
+            supply: 3,
+            ..pool(Curve::ConstantProduct)
+        };
+        assert_eq!(pool.quote_deposit(1).unwrap(), Amounts { x: 334, y: 1_001 });
+        assert_eq!(
+            pool.quote_withdraw(1).unwrap(),
+            Amounts { x: 333, y: 1_000 }
+        );
+        assert_eq!(pool.quote_withdraw(4), Err(CurveError::InsufficientBalance));
+    }
+
+    #[test]
+    fn deposit_single_mints_close_to_a_balanced_deposit() {
+        for curve in [Curve::ConstantProduct, Curve::StableSwap { amp: 100 }] {
+            let (res, lp) = pool(curve).quote_deposit_single(true, 100_000).unwrap();
+            assert!(res.deposit < 100_000);
+            assert!(lp > 45_000 && lp < 50_000);
+        }
+    }
+
+    #[test]
+    fn withdraw_single_swaps_the_other_side() {
+        let pool = pool(Curve::ConstantProduct);
+        let (res, out) = pool.quote_withdraw_single(true, 100_000).unwrap();
+        let res = res.unwrap();
+        assert_eq!(res.deposit, 100_000);
+        assert_eq!(out, 100_000 + res.withdraw);
+        assert!(out < 200_000);
+    }
+
+    #[test]
+    fn after_swap_sets_aside_the_protocol_fee() {
+        let pool = pool(Curve::ConstantProduct);
+        let res = pool.quote_swap(false, 10_000).unwrap();
+        let after = pool.after_swap(false, &res).unwrap();
+        assert_eq!(after.reserve_x, 1_000_000 - res.withdraw);
+        assert_eq!(after.reserve_y, 1_000_000 + 10_000 - 3);
+    }
+
+    #[test]
+    fn price_impact_grows_with_size() {
+        let constant_product = pool(Curve::ConstantProduct);
+        let stable_swap = pool(Curve::StableSwap { amp: 100 });
+
+        let impact = |pool: Pool, amount: u64| {
+            let res = pool.quote_swap(true, amount).unwrap();
+            pool.price_impact(true, &res).unwrap()
+        };
+
+        // A tenth of the reserves moves a constant-product price by about 9%, and
+        // a balanced stable pool by much less.
+        assert!(impact(constant_product, 1_000) <= 20);
+        assert!(impact(constant_product, 100_000).abs_diff(900) <= 10);
+        assert!(impact(stable_swap, 1_000) <= 20);
+        assert!(impact(stable_swap, 100_000) < 100);
+    }
+
+    const AMOUNT_IN: u64 = 1_000_000;
+
+    // LP tokens for the leftover input and for the swap output of a split, with
+    // the output earning less the more is swapped, like on a curve.
+    fn lp(swap_in: u64) -> (u64, u64) {
+        let lp_out = swap_in * 10_000 / (10_000 + swap_in / 100);
+        ((AMOUNT_IN - swap_in) * 2, lp_out)
+    }
+
+    fn best_of_all() -> u64 {
+        (1..=AMOUNT_IN)
+            .map(|swap_in| {
+                let (lp_in, lp_out) = lp(swap_in);
+                lp_in.min(lp_out)
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn best_split_finds_the_crossing_within_its_budget() {
+        let best = best_of_all();
+        for estimate in [1, AMOUNT_IN / 10, AMOUNT_IN / 2, AMOUNT_IN] {
+            let evaluations = Cell::new(0);
+            let (_, found) = best_split(AMOUNT_IN, estimate, MAX_SPLIT_EVALUATIONS, |swap_in| {
+                evaluations.set(evaluations.get() + 1);
+                let (lp_in, lp_out) = lp(swap_in);
+                Ok(((), lp_in, lp_out))
+            })
+            .unwrap();
+
+            assert!(evaluations.get() <= MAX_SPLIT_EVALUATIONS);
+            assert_eq!(found, best);
+        }
+    }
+
+    fn curve() -> impl Strategy<Value = Curve> {
+        prop_oneof![
+            Just(Curve::ConstantProduct),
+            (1u64..10_000).prop_map(|amp| Curve::StableSwap { amp }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn deposit_then_withdraw_never_profits(
+            reserve_x in 1_000u64..1_000_000_000_000,
+            reserve_y in 1_000u64..1_000_000_000_000,
+            supply in 1_000u64..1_000_000_000_000,
+            amount in 1u64..1_000_000_000,
+        ) {
+            let pool = Pool { reserve_x, reserve_y, supply, ..pool(Curve::ConstantProduct) };
+            let deposit = pool.quote_deposit(amount).unwrap();
+            let pool = Pool {
+                reserve_x: reserve_x + deposit.x,
+                reserve_y: reserve_y + deposit.y,
+                supply: supply + amount,
+                ..pool
+            };
+            let withdraw = pool.quote_withdraw(amount).unwrap();
+            prop_assert!(withdraw.x <= deposit.x && withdraw.y <= deposit.y);
+        }
+
+        #[test]
+        fn exact_out_quote_inverts_swap_quote(
+            curve in curve(),
+            reserve_x in 1_000_000u64..1_000_000_000_000,
+            reserve_y in 1_000_000u64..1_000_000_000_000,
+            share in 1u64..500,
+        ) {
+            let pool = Pool { curve, reserve_x, reserve_y, ..pool(curve) };
+            let amount_out = (reserve_y as u128 * share as u128 / 1_000) as u64;
+
+            let exact_out = pool.quote_swap_exact_out(true, amount_out).unwrap();
+            let exact_in = pool.quote_swap(true, exact_out.deposit).unwrap();
+            prop_assert!(exact_in.withdraw + 1 >= amount_out);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+// A concentrated-liquidity pool at a price of one, with tick arrays on both
+// sides of it, and builders for the instructions tests run against it.
+
+use amm::{
+    accounts, instruction,
+    state::{ClPool, Position, TickArray},
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+    InstructionData,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+use crate::{pool::ata, ProcessResult, Svm};
+
+pub const TICK_SPACING: u16 = 10;
+
+pub struct TestClPool {
+    pub svm: Svm,
+    pub pool: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub user: Keypair,
+    pub user_x: Pubkey,
+    pub user_y: Pubkey,
+    // Start indexes of the tick arrays, in increasing order.
+    pub tick_arrays: Vec<i32>,
+}
+
+// Q64.64 square root of the price at `tick`, close enough to pick swap limits.
+pub fn sqrt_price_near_tick(tick: i32) -> u128 {
+    (1.0001f64.powf(tick as f64 / 2.0) * 2f64.powi(64)) as u128
+}
+
+impl TestClPool {
+    pub fn new(fee: u16) -> TestClPool {
+        let mut svm = Svm::new();
+        let user = Keypair::new();
+        svm.airdrop(&user.pubkey(), 1_000 * LAMPORTS_PER_SOL);
+
+        let (a, b) = (
+            svm.create_mint(&spl_token::ID, 6),
+            svm.create_mint(&spl_token::ID, 6),
+        );
+        let (mint_x, mint_y) = match a < b {
+            true => (a, b),
+            false => (b, a),
+        };
+
+        // Pools that share a bank (see `Svm`) need seeds of their own.
+        let seed = u64::from_le_bytes(mint_x.to_bytes()[..8].try_into().unwrap());
+        let pool = Pubkey::find_program_address(&[b"cl_pool", &seed.to_le_bytes()], &amm::ID).0;
+
+        let user_x = svm.create_ata(&user.pubkey(), &mint_x, &spl_token::ID);
+        let user_y = svm.create_ata(&user.pubkey(), &mint_y, &spl_token::ID);
+        svm.mint_to(&mint_x, &user_x, u64::MAX / 2);
+        svm.mint_to(&mint_y, &user_y, u64::MAX / 2);
+
+        let span = TickArray::span(TICK_SPACING);
+        let mut cl_pool = TestClPool {
+            svm,
+            pool,
+            mint_x,
+            mint_y,
+            vault_x: ata(&pool, &mint_x),
+            vault_y: ata(&pool, &mint_y),
+            user,
+            user_x,
+            user_y,
+            tick_arrays: vec![-span, 0],
+        };
+
+        let mut instructions = vec![Instruction {
+            program_id: amm::ID,
+            accounts: accounts::InitializeClPool {
+                signer: cl_pool.user.pubkey(),
+                mint_x,
+                mint_y,
+                vault_x: cl_pool.vault_x,
+                vault_y: cl_pool.vault_y,
+                pool,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeClPool {
+                seed,
+                fee,
+                tick_spacing: TICK_SPACING,
+                sqrt_price: 1 << 64,
+            }
+            .data(),
+        }];
+        for &start_tick_index in &cl_pool.tick_arrays {
+            instructions.push(Instruction {
+                program_id: amm::ID,
+                accounts: accounts::InitializeTickArray {
+                    signer: cl_pool.user.pubkey(),
+                    pool,
+                    tick_array: cl_pool.tick_array(start_tick_index),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: instruction::InitializeTickArray { start_tick_index }.data(),
+            });
+        }
+        cl_pool.process(&instructions).unwrap();
+
+        cl_pool
+    }
+
+    pub fn process(&mut self, instructions: &[Instruction]) -> ProcessResult {
+        self.svm.process(instructions, &[&self.user])
+    }
+
+    pub fn state(&self) -> ClPool {
+        self.svm.anchor_account(&self.pool)
+    }
+
+    pub fn tick_array(&self, start_tick_index: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"tick_array",
+                self.pool.as_ref(),
+                &start_tick_index.to_le_bytes(),
+            ],
+            &amm::ID,
+        )
+        .0
+    }
+
+    pub fn position(&self, tick_lower: i32, tick_upper: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"position",
+                self.pool.as_ref(),
+                self.user.pubkey().as_ref(),
+                &tick_lower.to_le_bytes(),
+                &tick_upper.to_le_bytes(),
+            ],
+            &amm::ID,
+        )
+        .0
+    }
+
+    pub fn position_state(&self, tick_lower: i32, tick_upper: i32) -> Position {
+        self.svm
+            .anchor_account(&self.position(tick_lower, tick_upper))
+    }
+
+    pub fn open_position_instruction(&self, tick_lower: i32, tick_upper: i32) -> Instruction {
+        Instruction {
+            program_id: amm::ID,
+            accounts: accounts::OpenPosition {
+                owner: self.user.pubkey(),
+                pool: self.pool,
+                position: self.position(tick_lower, tick_upper),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::OpenPosition {
+                tick_lower,
+                tick_upper,
+            }
+            .data(),
+        }
+    }
+
+    // Takes `IncreaseLiquidity`, `DecreaseLiquidity` or `CollectFees`.
+    pub fn modify_liquidity_instruction(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+        data: impl InstructionData,
+    ) -> Instruction {
+        let tick_array = |tick| self.tick_array(TickArray::start_index(tick, TICK_SPACING));
+
+        Instruction {
+            program_id: amm::ID,
+            accounts: accounts::ModifyLiquidity {
+                owner: self.user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                owner_x: self.user_x,
+                owner_y: self.user_y,
+                pool: self.pool,
+                position: self.position(tick_lower, tick_upper),
+                tick_array_lower: tick_array(tick_lower),
+                tick_array_upper: tick_array(tick_upper),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    // Swap `amount` of X (or Y) in, until the price reaches `sqrt_price_limit`
+    // if it is not zero. The tick arrays are passed from the one holding the
+    // current tick in the direction of the swap.
+    pub fn swap_instruction(&self, is_x: bool, amount: u64, sqrt_price_limit: u128) -> Instruction {
+        let current = TickArray::start_index(self.state().tick_current, TICK_SPACING);
+        let mut starts: Vec<i32> = self
+            .tick_arrays
+            .iter()
+            .copied()
+            .filter(|&start| match is_x {
+                true => start <= current,
+                false => start >= current,
+            })
+            .collect();
+        if is_x {
+            starts.reverse();
+        }
+
+        let mut accounts = accounts::ClSwap {
+            signer: self.user.pubkey(),
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_x: self.user_x,
+            user_y: self.user_y,
+            pool: self.pool,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(
+            starts
+                .into_iter()
+                .map(|start| AccountMeta::new(self.tick_array(start), false)),
+        );
+
+        Instruction {
+            program_id: amm::ID,
+            accounts,
+            data: instruction::ClSwap {
+                is_x,
+                amount,
+                min_out: 0,
+                sqrt_price_limit,
+                expiration: i64::MAX,
+            }
+            .data(),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// In-process runtime for the amm program's tests, on top of solana-program-test.
+// Under `cargo test` the program runs natively. Under `cargo test-sbf`, or with
+// SBF_OUT_DIR pointing at a build, the built amm.so runs instead. The token
+// programs always run from the binaries that ship with solana-program-test.
+
+use std::{cell::RefCell, collections::HashSet};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{entrypoint::ProgramResult, instruction::Instruction, program_pack::Pack},
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+            StateWithExtensions,
+        },
+        state::{Account as TokenAccount, Mint},
+    },
+};
+use solana_program_test::{
+    processor,
+    tokio::runtime::{Builder, Runtime},
+    BanksClient, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext,
+};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signature::{Keypair, Signature, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+pub mod cl_pool;
+pub mod pool;
+
+pub use solana_program_test::BanksTransactionResultWithMetadata;
+pub use solana_sdk::{account, signature, transaction};
+
+// What running a transaction returns: its logs and compute units, or why it failed.
+pub type ProcessResult = std::result::Result<BanksTransactionResultWithMetadata, TransactionError>;
+
+struct Bank {
+    runtime: Runtime,
+    context: ProgramTestContext,
+    processed: HashSet<Signature>,
+}
+
+thread_local! {
+    // solana-program-test leaks a few threads with every bank it starts, so a
+    // test that needs many of them, like a property test, reuses the bank its
+    // previous case dropped. Every test runs on a thread of its own.
+    static IDLE: RefCell<Option<Bank>> = const { RefCell::new(None) };
+}
+
+pub struct Svm(Option<Bank>);
+
+// Anchor's entrypoint wants the accounts to live as long as their data.
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    amm::entry(program_id, accounts, data)
+}
+
+impl Default for Svm {
+    fn default() -> Svm {
+        Svm::new()
+    }
+}
+
+impl Drop for Svm {
+    fn drop(&mut self) {
+        let bank = self.0.take();
+        IDLE.with(|idle| *idle.borrow_mut() = bank);
+    }
+}
+
+impl Svm {
+    pub fn new() -> Svm {
+        let bank = IDLE.with(|idle| idle.borrow_mut().take());
+        let bank = bank.unwrap_or_else(|| {
+            let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+            let program_test = ProgramTest::new("amm", amm::ID, processor!(process_instruction));
+            let context = runtime.block_on(program_test.start_with_context());
+
+            Bank {
+                runtime,
+                context,
+                processed: HashSet::new(),
+            }
+        });
+
+        let mut svm = Svm(Some(bank));
+        svm.set_clock(1_700_000_000);
+
+        svm
+    }
+
+    fn bank(&self) -> &Bank {
+        self.0.as_ref().unwrap()
+    }
+
+    fn bank_mut(&mut self) -> &mut Bank {
+        self.0.as_mut().unwrap()
+    }
+
+    // Pays for every transaction and is the authority of mints made by `create_mint`.
+    pub fn payer(&self) -> Pubkey {
+        self.bank().context.payer.pubkey()
+    }
+
+    fn banks_client(&self) -> BanksClient {
+        self.bank().context.banks_client.clone()
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<Account> {
+        let mut banks_client = self.banks_client();
+        self.bank()
+            .runtime
+            .block_on(banks_client.get_account(*key))
+            .unwrap()
+    }
+
+    pub fn set_account(&mut self, key: &Pubkey, account: &Account) {
+        self.bank_mut()
+            .context
+            .set_account(key, &AccountSharedData::from(account.clone()));
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        let mut account = self.account(key).unwrap_or(Account {
+            owner: system_program::ID,
+            ..Account::default()
+        });
+        account.lamports += lamports;
+        self.set_account(key, &account);
+    }
+
+    pub fn clock(&self) -> Clock {
+        let mut banks_client = self.banks_client();
+        self.bank()
+            .runtime
+            .block_on(banks_client.get_sysvar::<Clock>())
+            .unwrap()
+    }
+
+    pub fn set_clock(&mut self, unix_timestamp: i64) {
+        let clock = Clock {
+            unix_timestamp,
+            ..self.clock()
+        };
+        self.bank().context.set_sysvar(&clock);
+    }
+
+    // Runs `instructions` as one transaction paid for by `payer`, signed by it and
+    // `signers`. The program logs are in the result's metadata, and are also
+    // printed with RUST_LOG=solana_runtime::message_processor=debug.
+    pub fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> ProcessResult {
+        let mut banks_client = self.banks_client();
+        let bank = self.bank_mut();
+        let payer = bank.context.payer.insecure_clone();
+        let signers = [&[&payer], signers].concat();
+
+        // A bank kept idle for a while has an expired blockhash, and sending the
+        // same transaction twice under one blockhash would be rejected as a
+        // duplicate, so take the latest one and wait for the next if needed.
+        let mut blockhash = bank
+            .runtime
+            .block_on(banks_client.get_latest_blockhash())
+            .unwrap();
+        let mut transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &signers,
+            blockhash,
+        );
+        if bank.processed.contains(&transaction.signatures[0]) {
+            blockhash = bank
+                .runtime
+                .block_on(banks_client.get_new_latest_blockhash(&blockhash))
+                .unwrap();
+            transaction.sign(&signers, blockhash);
+        }
+        bank.processed.insert(transaction.signatures[0]);
+
+        let result = bank
+            .runtime
+            .block_on(banks_client.process_transaction_with_metadata(transaction))
+            .unwrap();
+
+        result.result.clone().map(|()| result)
+    }
+
+    // Token helpers.
+
+    pub fn create_mint(&mut self, token_program: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                Rent::default().minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                token_program,
+            ),
+            spl_token_2022::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).unwrap();
+
+        mint.pubkey()
+    }
+
+    // A Token-2022 mint that withholds `basis_points` of every transfer.
+    pub fn create_mint_with_transfer_fee(&mut self, decimals: u8, basis_points: u16) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                Rent::default().minimum_balance(space),
+                space as u64,
+                &spl_token_2022::ID,
+            ),
+            initialize_transfer_fee_config(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                None,
+                None,
+                basis_points,
+                u64::MAX,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+                &payer,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).unwrap();
+
+        mint.pubkey()
+    }
+
+    pub fn create_ata(&mut self, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        let instruction =
+            create_associated_token_account(&self.payer(), owner, mint, token_program);
+        self.process(&[instruction], &[]).unwrap();
+
+        get_associated_token_address_with_program_id(owner, mint, token_program)
+    }
+
+    pub fn mint_to(&mut self, mint: &Pubkey, to: &Pubkey, amount: u64) {
+        let token_program = self.account(mint).unwrap().owner;
+        let instruction = spl_token_2022::instruction::mint_to(
+            &token_program,
+            mint,
+            to,
+            &self.payer(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).unwrap();
+    }
+
+    pub fn token_balance(&self, account: &Pubkey) -> u64 {
+        let data = self.account(account).unwrap().data;
+        StateWithExtensions::<TokenAccount>::unpack(&data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    pub fn mint_supply(&self, mint: &Pubkey) -> u64 {
+        let data = self.account(mint).unwrap().data;
+        StateWithExtensions::<Mint>::unpack(&data)
+            .unwrap()
+            .base
+            .supply
+    }
+
+    pub fn anchor_account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let data = self.account(key).unwrap().data;
+        T::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+}
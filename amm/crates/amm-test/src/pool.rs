@@ -0,0 +1,273 @@
+// A constant product or stable swap pool with a first deposit, and builders for
+// the instructions tests run against it.
+
+use amm::{
+    accounts, instruction,
+    state::{Config, CurveType},
+};
+use amm_math::Pool;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+    InstructionData,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id, spl_associated_token_account,
+    },
+    token::spl_token,
+    token_2022::spl_token_2022,
+};
+
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+use crate::{ProcessResult, Svm};
+
+pub struct TestPool {
+    pub svm: Svm,
+    pub config: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub vault_lp: Pubkey,
+    pub token_program: Pubkey,
+    pub user: Keypair,
+    pub user_x: Pubkey,
+    pub user_y: Pubkey,
+    pub user_lp: Pubkey,
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token::ID)
+}
+
+impl TestPool {
+    pub fn new(curve: CurveType, fee: u16, protocol_fee: u16, x: u64, y: u64) -> TestPool {
+        TestPool::create(curve, fee, protocol_fee, x, y, None)
+    }
+
+    // A pool of two Token-2022 mints that withhold `transfer_fee` basis points of
+    // every transfer, the first deposit included.
+    pub fn with_transfer_fee(
+        curve: CurveType,
+        fee: u16,
+        protocol_fee: u16,
+        x: u64,
+        y: u64,
+        transfer_fee: u16,
+    ) -> TestPool {
+        TestPool::create(curve, fee, protocol_fee, x, y, Some(transfer_fee))
+    }
+
+    fn create(
+        curve: CurveType,
+        fee: u16,
+        protocol_fee: u16,
+        x: u64,
+        y: u64,
+        transfer_fee: Option<u16>,
+    ) -> TestPool {
+        let mut svm = Svm::new();
+        let user = Keypair::new();
+        svm.airdrop(&user.pubkey(), 1_000 * LAMPORTS_PER_SOL);
+
+        let (token_program, a, b) = match transfer_fee {
+            Some(basis_points) => (
+                spl_token_2022::ID,
+                svm.create_mint_with_transfer_fee(6, basis_points),
+                svm.create_mint_with_transfer_fee(6, basis_points),
+            ),
+            None => (
+                spl_token::ID,
+                svm.create_mint(&spl_token::ID, 6),
+                svm.create_mint(&spl_token::ID, 6),
+            ),
+        };
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            get_associated_token_address_with_program_id(owner, mint, &token_program)
+        };
+        let (mint_x, mint_y) = match a < b {
+            true => (a, b),
+            false => (b, a),
+        };
+
+        // Pools that share a bank (see `Svm`) need configs of their own.
+        let seed = u64::from_le_bytes(mint_x.to_bytes()[..8].try_into().unwrap());
+        let config = Pubkey::find_program_address(&[b"config", &seed.to_le_bytes()], &amm::ID).0;
+        let mint_lp = Pubkey::find_program_address(&[b"mint", config.as_ref()], &amm::ID).0;
+
+        let user_x = svm.create_ata(&user.pubkey(), &mint_x, &token_program);
+        let user_y = svm.create_ata(&user.pubkey(), &mint_y, &token_program);
+        svm.mint_to(&mint_x, &user_x, u64::MAX / 2);
+        svm.mint_to(&mint_y, &user_y, u64::MAX / 2);
+
+        let mut pool = TestPool {
+            svm,
+            config,
+            mint_x,
+            mint_y,
+            mint_lp,
+            vault_x: ata(&config, &mint_x),
+            vault_y: ata(&config, &mint_y),
+            vault_lp: ata(&config, &mint_lp),
+            token_program,
+            user_x,
+            user_y,
+            user_lp: ata(&user.pubkey(), &mint_lp),
+            user,
+        };
+
+        let initialize = Instruction {
+            program_id: amm::ID,
+            accounts: accounts::Initialize {
+                signer: pool.user.pubkey(),
+                mint_x,
+                mint_y,
+                vault_x: pool.vault_x,
+                vault_y: pool.vault_y,
+                mint_lp,
+                vault_lp: pool.vault_lp,
+                config,
+                registry: Pubkey::find_program_address(&[b"registry"], &amm::ID).0,
+                pair_index: Pubkey::find_program_address(
+                    &[
+                        b"pair",
+                        mint_x.as_ref(),
+                        mint_y.as_ref(),
+                        &curve.tag().to_le_bytes(),
+                    ],
+                    &amm::ID,
+                )
+                .0,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Initialize {
+                seed,
+                authority: Some(pool.user.pubkey()),
+                fee,
+                curve,
+            }
+            .data(),
+        };
+        let update_protocol_fee = Instruction {
+            program_id: amm::ID,
+            accounts: accounts::Update {
+                authority: pool.user.pubkey(),
+                config,
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateProtocolFee { protocol_fee }.data(),
+        };
+        pool.process(&[initialize, update_protocol_fee]).unwrap();
+
+        let deposit = pool.deposit_instruction(instruction::Deposit {
+            amount: 0,
+            max_x: x,
+            max_y: y,
+        });
+        pool.process(&[deposit]).unwrap();
+
+        pool
+    }
+
+    // The associated token account of `owner` under the pool's token program.
+    pub fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    pub fn process(&mut self, instructions: &[Instruction]) -> ProcessResult {
+        self.svm.process(instructions, &[&self.user])
+    }
+
+    // The pool as the program would price the next instruction.
+    pub fn quote_pool(&self) -> Pool {
+        let config: Config = self.svm.anchor_account(&self.config);
+        let reserve_x = self.svm.token_balance(&self.vault_x) - config.protocol_fees_x;
+        let reserve_y = self.svm.token_balance(&self.vault_y) - config.protocol_fees_y;
+        config.pool(reserve_x, reserve_y, self.svm.mint_supply(&self.mint_lp))
+    }
+
+    pub fn balances(&self) -> (u64, u64, u64) {
+        (
+            self.svm.token_balance(&self.user_x),
+            self.svm.token_balance(&self.user_y),
+            self.svm.token_balance(&self.user_lp),
+        )
+    }
+
+    pub fn swap_instruction(&self, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: amm::ID,
+            accounts: accounts::Swap {
+                signer: self.user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_x: self.user_x,
+                user_y: self.user_y,
+                config: self.config,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn deposit_instruction(&self, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: amm::ID,
+            accounts: accounts::Deposit {
+                signer: self.user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_x: self.user_x,
+                user_y: self.user_y,
+                mint_lp: self.mint_lp,
+                vault_lp: self.vault_lp,
+                user_lp: self.user_lp,
+                config: self.config,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn withdraw_instruction(&self, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: amm::ID,
+            accounts: accounts::Withdraw {
+                signer: self.user.pubkey(),
+                mint_x: self.mint_x,
+                mint_y: self.mint_y,
+                vault_x: self.vault_x,
+                vault_y: self.vault_y,
+                user_x: self.user_x,
+                user_y: self.user_y,
+                mint_lp: self.mint_lp,
+                user_lp: self.user_lp,
+                config: self.config,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: data.data(),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use amm_math::math::{mul_div, mul_div_ceil};
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+const Q64: u128 = 1 << 64;
+
+// Amount of X between two sqrt prices for `liquidity`: L * (b - a) / (a * b).
+pub fn amount_x_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let (lower, upper) = match sqrt_price_a < sqrt_price_b {
+        true => (sqrt_price_a, sqrt_price_b),
+        false => (sqrt_price_b, sqrt_price_a),
+    };
+    require!(lower > 0, AmmError::InvalidSqrtPrice);
+
+    // Keep the Q64 factor in the first division when it fits, so that only the
+    // last step rounds to a whole token amount.
+    let amount = match (round_up, liquidity.checked_mul(Q64)) {
+        (true, Some(numerator)) => {
+            mul_div_ceil(numerator, upper - lower, upper).map(|n| n.div_ceil(lower))
+        }
+        (false, Some(numerator)) => mul_div(numerator, upper - lower, upper).map(|n| n / lower),
+        (true, None) => {
+            mul_div_ceil(liquidity, upper - lower, upper).and_then(|n| mul_div_ceil(n, Q64, lower))
+        }
+        (false, None) => {
+            mul_div(liquidity, upper - lower, upper).and_then(|n| mul_div(n, Q64, lower))
+        }
+    };
+
+    Ok(amount.ok_or(AmmError::Overflow)?)
+}
+
+// Amount of Y between two sqrt prices for `liquidity`: L * (b - a).
+pub fn amount_y_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let diff = sqrt_price_a.abs_diff(sqrt_price_b);
+
+    let amount = match round_up {
+        true => mul_div_ceil(liquidity, diff, Q64),
+        false => mul_div(liquidity, diff, Q64),
+    };
+
+    Ok(amount.ok_or(AmmError::Overflow)?)
+}
+
+// Sqrt price after adding `amount` of the input token. Adding X moves the price
+// down and is rounded up, adding Y moves it up and is rounded down, so the pool
+// never gives out more than it should.
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    is_x: bool,
+) -> Result<u128> {
+    require!(liquidity > 0, AmmError::ZeroBalance);
+
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let next = match is_x {
+        // L * P / (L + amount * P)
+        true => {
+            let product = mul_div(amount as u128, sqrt_price, Q64).ok_or(AmmError::Overflow)?;
+            let denominator = liquidity.checked_add(product).ok_or(AmmError::Overflow)?;
+            mul_div_ceil(liquidity, sqrt_price, denominator).ok_or(AmmError::Overflow)?
+        }
+        // P + amount / L
+        false => {
+            let quotient = mul_div(amount as u128, Q64, liquidity).ok_or(AmmError::Overflow)?;
+            sqrt_price.checked_add(quotient).ok_or(AmmError::Overflow)?
+        }
+    };
+
+    Ok(next)
+}
+
+// Token amounts needed (or returned) to change a position's liquidity by `liquidity`,
+// depending on where the current price sits relative to the position's range.
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let (x, y) = if sqrt_price < sqrt_price_lower {
+        (
+            amount_x_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+            0,
+        )
+    } else if sqrt_price < sqrt_price_upper {
+        (
+            amount_x_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+            amount_y_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+        )
+    } else {
+        (
+            0,
+            amount_y_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+        )
+    };
+
+    Ok((
+        u64::try_from(x).map_err(|_| AmmError::Overflow)?,
+        u64::try_from(y).map_err(|_| AmmError::Overflow)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amount_deltas_at_price_one() {
+        // Between price 1 and price 4 (sqrt 1 and 2), L = 1000 holds 500 X or 1000 Y.
+        assert_eq!(amount_x_delta(Q64, 2 * Q64, 1_000, false).unwrap(), 500);
+        assert_eq!(amount_y_delta(Q64, 2 * Q64, 1_000, false).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn rounding_direction() {
+        assert_eq!(amount_x_delta(Q64, 3 * Q64, 1_000, false).unwrap(), 666);
+        assert_eq!(amount_x_delta(Q64, 3 * Q64, 1_000, true).unwrap(), 667);
+    }
+
+    #[test]
+    fn input_moves_price_the_right_way() {
+        let liquidity = 1_000_000_000;
+        let down = next_sqrt_price_from_input(Q64, liquidity, 1_000, true).unwrap();
+        let up = next_sqrt_price_from_input(Q64, liquidity, 1_000, false).unwrap();
+        assert!(down < Q64);
+        assert!(up > Q64);
+
+        // Swapping X in and then taking Y out across the same range is consistent.
+        let x_in = amount_x_delta(down, Q64, liquidity, true).unwrap();
+        assert!(x_in <= 1_000);
+    }
+
+    #[test]
+    fn amounts_depend_on_range() {
+        let (lower, upper) = (Q64, 2 * Q64);
+        assert_eq!(
+            amounts_for_liquidity(Q64 / 2, lower, upper, 1_000, true).unwrap(),
+            (500, 0)
+        );
+        assert_eq!(
+            amounts_for_liquidity(3 * Q64, lower, upper, 1_000, true).unwrap(),
+            (0, 1_000)
+        );
+        let (x, y) = amounts_for_liquidity(3 * Q64 / 2, lower, upper, 1_000, true).unwrap();
+        assert!(x > 0 && y > 0);
+    }
+}
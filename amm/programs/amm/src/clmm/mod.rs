@@ -0,0 +1,5 @@
+// Math for concentrated-liquidity pools, see `state::ClPool`.
+
+pub mod liquidity_math;
+pub mod swap_math;
+pub mod tick_math;
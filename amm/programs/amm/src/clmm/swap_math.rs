@@ -0,0 +1,103 @@
+use amm_math::math::mul_div_ceil;
+use anchor_lang::prelude::*;
+
+use super::liquidity_math::{amount_x_delta, amount_y_delta, next_sqrt_price_from_input};
+use crate::errors::AmmError;
+
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+// Swaps as much of `amount_remaining` as possible without moving the price past
+// `sqrt_price_target`, with the fee taken from the input.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee: u16,
+) -> Result<SwapStep> {
+    let is_x = sqrt_price_target <= sqrt_price;
+
+    let amount_remaining_less_fee =
+        (amount_remaining as u128 * (10_000 - fee) as u128 / 10_000) as u64;
+
+    let amount_in_max = match is_x {
+        true => amount_x_delta(sqrt_price_target, sqrt_price, liquidity, true)?,
+        false => amount_y_delta(sqrt_price, sqrt_price_target, liquidity, true)?,
+    };
+
+    let sqrt_price_next = match amount_remaining_less_fee as u128 >= amount_in_max {
+        true => sqrt_price_target,
+        false => {
+            next_sqrt_price_from_input(sqrt_price, liquidity, amount_remaining_less_fee, is_x)?
+        }
+    };
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let amount_in = match (reached_target, is_x) {
+        (true, _) => amount_in_max,
+        (false, true) => amount_x_delta(sqrt_price_next, sqrt_price, liquidity, true)?,
+        (false, false) => amount_y_delta(sqrt_price, sqrt_price_next, liquidity, true)?,
+    };
+    let amount_out = match is_x {
+        true => amount_y_delta(sqrt_price_next, sqrt_price, liquidity, false)?,
+        false => amount_x_delta(sqrt_price, sqrt_price_next, liquidity, false)?,
+    };
+
+    let amount_in = u64::try_from(amount_in).map_err(|_| AmmError::Overflow)?;
+    let amount_out = u64::try_from(amount_out).map_err(|_| AmmError::Overflow)?;
+
+    // If the target wasn't reached the whole remaining amount was used, and
+    // whatever isn't swapped is kept as the fee.
+    let fee_amount = match reached_target {
+        true => mul_div_ceil(amount_in as u128, fee as u128, (10_000 - fee) as u128)
+            .and_then(|n| u64::try_from(n).ok())
+            .ok_or(AmmError::Overflow)?,
+        false => amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(AmmError::Underflow)?,
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q64: u128 = 1 << 64;
+
+    #[test]
+    fn partial_step_uses_whole_amount() {
+        let step = compute_swap_step(Q64, Q64 / 2, 1_000_000_000, 1_000, 30).unwrap();
+        assert!(step.sqrt_price_next < Q64 && step.sqrt_price_next > Q64 / 2);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000);
+        assert!(step.amount_out < step.amount_in);
+    }
+
+    #[test]
+    fn full_step_stops_at_target() {
+        let step = compute_swap_step(Q64, 2 * Q64, 1_000, u64::MAX / 2, 30).unwrap();
+        assert_eq!(step.sqrt_price_next, 2 * Q64);
+        assert_eq!(step.amount_in, 1_000);
+        assert_eq!(step.amount_out, 500);
+        assert_eq!(step.fee_amount, 4);
+    }
+
+    #[test]
+    fn zero_liquidity_moves_price_for_free() {
+        let step = compute_swap_step(Q64, 2 * Q64, 0, 1_000, 30).unwrap();
+        assert_eq!(step.sqrt_price_next, 2 * Q64);
+        assert_eq!(step.amount_in, 0);
+        assert_eq!(step.amount_out, 0);
+    }
+}
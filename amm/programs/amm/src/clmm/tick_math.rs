@@ -0,0 +1,143 @@
+use amm_math::math::{mul_shr_128, mul_shr_64};
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+// Prices are stored as sqrt(price) in Q64.64 fixed point, where price is the
+// amount of Y per X and tick i corresponds to a price of 1.0001^i.
+
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_017;
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_963_822_778_343;
+
+// 2^128 / sqrt(1.0001)^(2^i)
+const NEGATIVE_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x09aa508b5b7a84e1c677de54f3e99bc8,
+    0x005d6af8dedb81196699c329225ee604,
+    0x00002216e584f5fa1ea926041bedfe97,
+];
+
+// 2^64 * sqrt(1.0001)^(2^i)
+const POSITIVE_FACTORS: [u128; 19] = [
+    0x1000346d6ff11672a,
+    0x100068db8bac710cb,
+    0x1000d1b9c68abe5f7,
+    0x1001a37e4a234cb08,
+    0x100347278ab0e92ad,
+    0x10068efb00a525480,
+    0x100d20a63b4173839,
+    0x101a4c11c742dd772,
+    0x1034c35c31f64cfa6,
+    0x106a34b78c8aaffbf,
+    0x10d72a6a46ccd8bce,
+    0x11b9a258e63928596,
+    0x13a2e2bda04f8379f,
+    0x181954be69e0da8fe,
+    0x244c2655d185a0290,
+    0x525816eeb9f935b1c,
+    0x1a7c8d00b551684ff4,
+    0x2bd893d0b2df7c97884,
+    0x78278e1e19e448cf8b95d,
+];
+
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), AmmError::InvalidTick);
+
+    let abs_tick = tick.unsigned_abs();
+
+    if tick >= 0 {
+        // Multiply up from 1.0 in Q64.64.
+        let mut ratio: u128 = 1 << 64;
+        for (i, factor) in POSITIVE_FACTORS.iter().enumerate() {
+            if abs_tick & (1 << i) != 0 {
+                ratio = mul_shr_64(ratio, *factor).ok_or(AmmError::Overflow)?;
+            }
+        }
+        Ok(ratio)
+    } else {
+        // Multiply down from 1.0 in Q0.128 and round up to Q64.64.
+        let mut ratio = u128::MAX;
+        for (i, factor) in NEGATIVE_FACTORS.iter().enumerate() {
+            if abs_tick & (1 << i) != 0 {
+                ratio = mul_shr_128(ratio, *factor);
+            }
+        }
+        Ok((ratio >> 64) + u128::from(ratio as u64 != 0))
+    }
+}
+
+// Greatest tick whose sqrt price is less than or equal to `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price),
+        AmmError::InvalidSqrtPrice
+    );
+
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        match sqrt_price_at_tick(mid)? <= sqrt_price {
+            true => low = mid,
+            false => high = mid - 1,
+        }
+    }
+
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_zero_is_price_one() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), 1 << 64);
+        assert_eq!(tick_at_sqrt_price(1 << 64).unwrap(), 0);
+    }
+
+    #[test]
+    fn bounds_match_constants() {
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE);
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn sqrt_price_is_monotonic() {
+        let mut previous = sqrt_price_at_tick(-5_000).unwrap();
+        for tick in -4_999..5_000 {
+            let current = sqrt_price_at_tick(tick).unwrap();
+            assert!(current > previous);
+            previous = current;
+        }
+    }
+
+    #[test]
+    fn tick_round_trips() {
+        for tick in [MIN_TICK + 1, -200_000, -1, 0, 1, 64, 200_000, MAX_TICK - 1] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            assert_eq!(tick_at_sqrt_price(sqrt_price + 1).unwrap(), tick);
+            assert_eq!(tick_at_sqrt_price(sqrt_price - 1).unwrap(), tick - 1);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use amm_math::CurveError;
+use anchor_lang::error_code;
+
+#[error_code]
+pub enum AmmError {
+    #[msg("The pool is locked")]
+    PoolLocked,
+
+    #[msg("The signer is not the pool authority")]
+    InvalidAuthority,
+
+    #[msg("The mints must be ordered with mint_x before mint_y")]
+    InvalidMintOrder,
+
+    #[msg("The fee must be less than 10000 basis points")]
+    InvalidFee,
+
+    #[msg("The protocol fee must not exceed 10000 basis points")]
+    InvalidProtocolFee,
+
+    #[msg("The amplification coefficient is out of range")]
+    InvalidAmplification,
+
+    #[msg("The transaction deadline has passed")]
+    Expired,
+
+    #[msg("The amount must be greater than zero")]
+    InvalidAmount,
+
+    #[msg("The slippage limit was exceeded")]
+    SlippageExceeded,
+
+    #[msg("The first deposit must mint more than the minimum liquidity")]
+    InsufficientLiquidity,
+
+    #[msg("The mint has a Token-2022 extension the pool doesn't support")]
+    UnsupportedMintExtension,
+
+    #[msg("The route accounts are invalid")]
+    InvalidRoute,
+
+    #[msg("A flash loan from this pool is outstanding")]
+    FlashLoanActive,
+
+    #[msg("The flash loan is not repaid later in the transaction")]
+    FlashLoanNotRepaid,
+
+    #[msg("There is no flash loan to repay")]
+    NoFlashLoan,
+
+    #[msg("The curve precision is invalid")]
+    InvalidPrecision,
+
+    #[msg("The curve calculation overflowed")]
+    Overflow,
+
+    #[msg("The curve calculation underflowed")]
+    Underflow,
+
+    #[msg("The curve fee amount is invalid")]
+    InvalidFeeAmount,
+
+    #[msg("The pool has insufficient balance")]
+    InsufficientBalance,
+
+    #[msg("The pool has zero balance")]
+    ZeroBalance,
+
+    #[msg("The tick is out of range or not aligned to the tick spacing")]
+    InvalidTick,
+
+    #[msg("The sqrt price is out of range")]
+    InvalidSqrtPrice,
+
+    #[msg("The tick spacing must be greater than zero")]
+    InvalidTickSpacing,
+
+    #[msg("The tick array does not match the pool or the tick")]
+    InvalidTickArray,
+
+    #[msg("The position still has liquidity or uncollected fees")]
+    PositionNotEmpty,
+
+    #[msg("The farm schedule is invalid")]
+    InvalidFarmSchedule,
+
+    #[msg("The order accounts are invalid")]
+    InvalidOrder,
+
+    #[msg("None of the orders could be filled")]
+    NoOrdersFilled,
+}
+
+impl From<CurveError> for AmmError {
+    fn from(error: CurveError) -> AmmError {
+        match error {
+            CurveError::InvalidPrecision => AmmError::InvalidPrecision,
+            CurveError::Overflow => AmmError::Overflow,
+            CurveError::Underflow => AmmError::Underflow,
+            CurveError::InvalidFeeAmount => AmmError::InvalidFeeAmount,
+            CurveError::InsufficientBalance => AmmError::InsufficientBalance,
+            CurveError::InsufficientLiquidity => AmmError::InsufficientLiquidity,
+            CurveError::ZeroBalance => AmmError::ZeroBalance,
+            CurveError::SlippageLimitExceeded => AmmError::SlippageExceeded,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Token-2022 mint extensions. Transfer fees are withheld from what the receiver
+// gets, so the curve has to work with net amounts, and transfer hooks need their
+// extra accounts forwarded to every transfer.
+
+use anchor_lang::{prelude::*, solana_program::program::invoke};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{instruction::harvest_withheld_tokens_to_mint, TransferFeeConfig},
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+        state::Mint as MintState,
+    },
+    token_interface::{Mint, TokenInterface},
+};
+
+use crate::errors::AmmError;
+
+// Extensions that don't get in the way of a pool holding the token. Anything else
+// is rejected, e.g. non-transferable and confidential mints, or a permanent
+// delegate that could move tokens out of the vaults.
+const SUPPORTED_EXTENSIONS: [ExtensionType; 10] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::TransferHook,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    for extension in extension_types(mint)? {
+        require!(
+            SUPPORTED_EXTENSIONS.contains(&extension),
+            AmmError::UnsupportedMintExtension
+        );
+    }
+
+    Ok(())
+}
+
+pub fn has_extension(mint: &AccountInfo, extension: ExtensionType) -> Result<bool> {
+    Ok(extension_types(mint)?.contains(&extension))
+}
+
+fn extension_types(mint: &AccountInfo) -> Result<Vec<ExtensionType>> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(vec![]);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    Ok(state.get_extension_types()?)
+}
+
+// Fee withheld when `amount` is transferred, i.e. the receiver gets `amount - fee`.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(AmmError::Overflow)?,
+        Err(_) => 0,
+    };
+
+    Ok(fee)
+}
+
+// Amount the sender has to transfer for the receiver to get exactly `amount`.
+pub fn amount_with_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) if amount > 0 => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(AmmError::Overflow)?,
+        _ => 0,
+    };
+
+    Ok(amount.checked_add(fee).ok_or(AmmError::Overflow)?)
+}
+
+// `transfer_checked` that also passes the accounts a transfer hook needs. These
+// are looked up in `extra_accounts`, usually the instruction's remaining accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        from,
+        mint.to_account_info(),
+        to,
+        authority,
+        extra_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+// Moves the transfer fees withheld in `account` to the mint, which Token-2022
+// requires before the account can be closed. Anyone can harvest them, but the
+// mint has to be writable.
+pub fn harvest_withheld_fees<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    if !has_extension(&mint.to_account_info(), ExtensionType::TransferFeeConfig)? {
+        return Ok(());
+    }
+
+    invoke(
+        &harvest_withheld_tokens_to_mint(token_program.key, &mint.key(), &[account.key])?,
+        &[mint.to_account_info(), account],
+    )?;
+
+    Ok(())
+}
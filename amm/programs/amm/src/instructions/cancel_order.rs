@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::harvest_withheld_fees,
+    state::{Config, LimitOrder},
+};
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // Writable for harvesting Token-2022 transfer fees withheld in the escrow.
+    #[account(
+        mut,
+        constraint=mint_in.key() == order.mints(&config).0 @ AmmError::InvalidOrder
+    )]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_in,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_in,
+        associated_token::authority=order,
+        associated_token::token_program=token_program
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    // Closing the order also refunds the tip.
+    #[account(
+        mut,
+        has_one=owner,
+        has_one=config,
+        seeds=[
+            b"order",
+            config.key().as_ref(),
+            owner.key().as_ref(),
+            order.id.to_le_bytes().as_ref(),
+        ],
+        bump=order.bump,
+        close=owner
+    )]
+    pub order: Box<Account<'info, LimitOrder>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelOrder<'info> {
+    // Works on locked pools too, so funds are never stuck in an order.
+    pub fn cancel_order(&mut self) -> Result<()> {
+        let config = self.config.key();
+        let owner = self.owner.key();
+        let id = self.order.id.to_le_bytes();
+        let seeds = &[
+            &b"order"[..],
+            config.as_ref(),
+            owner.as_ref(),
+            &id,
+            &[self.order.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let accounts = TransferChecked {
+            from: self.escrow.to_account_info(),
+            to: self.owner_in.to_account_info(),
+            mint: self.mint_in.to_account_info(),
+            authority: self.order.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, self.escrow.amount, self.mint_in.decimals)?;
+
+        harvest_withheld_fees(
+            &self.token_program,
+            &self.mint_in,
+            self.escrow.to_account_info(),
+        )?;
+
+        let accounts = CloseAccount {
+            account: self.escrow.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.order.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        close_account(cpi_context)
+    }
+}
@@ -0,0 +1,335 @@
+use std::cell::RefMut;
+
+use amm_math::math::mul_div;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    clmm::{
+        swap_math::compute_swap_step,
+        tick_math::{
+            sqrt_price_at_tick, tick_at_sqrt_price, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE,
+            MIN_TICK,
+        },
+    },
+    errors::AmmError,
+    state::{ClPool, Tick, TickArray},
+};
+
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer=signer,
+        associated_token::mint=mint_x,
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
+    )]
+    pub user_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer=signer,
+        associated_token::mint=mint_y,
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
+    )]
+    pub user_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump=pool.bump,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClSwap<'info> {
+    // Tick arrays are passed through remaining_accounts, starting with the one
+    // that holds the current tick and continuing in the direction of the swap.
+    pub fn cl_swap(
+        &mut self,
+        tick_arrays: &'info [AccountInfo<'info>],
+        is_x: bool,
+        amount: u64,
+        min_out: u64,
+        sqrt_price_limit: u128,
+        expiration: i64,
+    ) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(
+            Clock::get()?.unix_timestamp <= expiration,
+            AmmError::Expired
+        );
+
+        // Selling X moves the price down, selling Y moves it up. A limit of zero
+        // means no limit.
+        let sqrt_price_limit = match (sqrt_price_limit, is_x) {
+            (0, true) => MIN_SQRT_PRICE,
+            (0, false) => MAX_SQRT_PRICE,
+            (limit, _) => limit,
+        };
+        require!(
+            match is_x {
+                true =>
+                    sqrt_price_limit < self.pool.sqrt_price && sqrt_price_limit >= MIN_SQRT_PRICE,
+                false =>
+                    sqrt_price_limit > self.pool.sqrt_price && sqrt_price_limit <= MAX_SQRT_PRICE,
+            },
+            AmmError::InvalidSqrtPrice
+        );
+
+        let loaders = tick_arrays
+            .iter()
+            .map(AccountLoader::<TickArray>::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let mut arrays = loaders
+            .iter()
+            .map(|loader| loader.load_mut())
+            .collect::<Result<Vec<_>>>()?;
+        self.check_tick_arrays(&arrays, is_x)?;
+
+        let pool = &self.pool;
+        let tick_spacing = pool.tick_spacing;
+        let mut sqrt_price = pool.sqrt_price;
+        let mut tick_current = pool.tick_current;
+        let mut liquidity = pool.liquidity;
+        let (mut fee_growth_global_x, mut fee_growth_global_y) =
+            (pool.fee_growth_global_x, pool.fee_growth_global_y);
+
+        let mut amount_remaining = amount;
+        let mut amount_out: u64 = 0;
+
+        while amount_remaining > 0 && sqrt_price != sqrt_price_limit {
+            // Stop once the swap runs past the tick arrays that were passed in.
+            let Some((tick_next, initialized)) =
+                next_tick(&mut arrays, tick_current, tick_spacing, is_x)
+            else {
+                break;
+            };
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next_tick = sqrt_price_at_tick(tick_next)?;
+
+            let sqrt_price_target = match is_x {
+                true => sqrt_price_next_tick.max(sqrt_price_limit),
+                false => sqrt_price_next_tick.min(sqrt_price_limit),
+            };
+
+            let step = compute_swap_step(
+                sqrt_price,
+                sqrt_price_target,
+                liquidity,
+                amount_remaining,
+                pool.fee,
+            )?;
+
+            amount_remaining -= step.amount_in + step.fee_amount;
+            amount_out = amount_out
+                .checked_add(step.amount_out)
+                .ok_or(AmmError::Overflow)?;
+
+            if liquidity > 0 {
+                let fee_growth = mul_div(step.fee_amount as u128, 1 << 64, liquidity)
+                    .ok_or(AmmError::Overflow)?;
+                match is_x {
+                    true => fee_growth_global_x = fee_growth_global_x.wrapping_add(fee_growth),
+                    false => fee_growth_global_y = fee_growth_global_y.wrapping_add(fee_growth),
+                }
+            }
+
+            if step.sqrt_price_next == sqrt_price_next_tick {
+                if initialized {
+                    let liquidity_net = tick_mut(&mut arrays, tick_next, tick_spacing)?
+                        .cross(fee_growth_global_x, fee_growth_global_y);
+                    liquidity = match is_x {
+                        true => liquidity.checked_add_signed(-liquidity_net),
+                        false => liquidity.checked_add_signed(liquidity_net),
+                    }
+                    .ok_or(AmmError::Overflow)?;
+                }
+                tick_current = match is_x {
+                    true => tick_next - 1,
+                    false => tick_next,
+                };
+            } else if step.sqrt_price_next != sqrt_price {
+                tick_current = tick_at_sqrt_price(step.sqrt_price_next)?;
+            }
+
+            sqrt_price = step.sqrt_price_next;
+        }
+        drop(arrays);
+
+        let amount_in = amount - amount_remaining;
+        require!(amount_out >= min_out, AmmError::SlippageExceeded);
+
+        self.pool.sqrt_price = sqrt_price;
+        self.pool.tick_current = tick_current;
+        self.pool.liquidity = liquidity;
+        self.pool.fee_growth_global_x = fee_growth_global_x;
+        self.pool.fee_growth_global_y = fee_growth_global_y;
+
+        self.deposit_tokens(is_x, amount_in)?;
+        self.withdraw_tokens(is_x, amount_out)?;
+
+        Ok(())
+    }
+
+    fn check_tick_arrays(&self, arrays: &[RefMut<TickArray>], is_x: bool) -> Result<()> {
+        let tick_spacing = self.pool.tick_spacing;
+        let span = TickArray::span(tick_spacing);
+
+        require!(
+            arrays
+                .first()
+                .is_some_and(|array| array.contains(self.pool.tick_current, tick_spacing)),
+            AmmError::InvalidTickArray
+        );
+
+        for (i, array) in arrays.iter().enumerate() {
+            require_keys_eq!(array.pool, self.pool.key(), AmmError::InvalidTickArray);
+
+            if let Some(next) = arrays.get(i + 1) {
+                let expected = match is_x {
+                    true => array.start_tick_index - span,
+                    false => array.start_tick_index + span,
+                };
+                require!(
+                    next.start_tick_index == expected,
+                    AmmError::InvalidTickArray
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint) = match is_x {
+            true => (&self.user_x, &self.vault_x, &self.mint_x),
+            false => (&self.user_y, &self.vault_y, &self.mint_y),
+        };
+
+        let accounts = TransferChecked {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+
+    fn withdraw_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint) = match is_x {
+            true => (&self.vault_y, &self.user_y, &self.mint_y),
+            false => (&self.vault_x, &self.user_x, &self.mint_x),
+        };
+
+        let accounts = TransferChecked {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let seeds = &[
+            &b"cl_pool"[..],
+            &self.pool.seed.to_le_bytes(),
+            &[self.pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+}
+
+fn tick_mut<'a>(
+    arrays: &'a mut [RefMut<TickArray>],
+    tick_index: i32,
+    tick_spacing: u16,
+) -> Result<&'a mut Tick> {
+    arrays
+        .iter_mut()
+        .find(|array| array.contains(tick_index, tick_spacing))
+        .ok_or(AmmError::InvalidTickArray)?
+        .tick_mut(tick_index, tick_spacing)
+}
+
+// Next tick to swap towards and whether it is initialized. When no initialized
+// tick is left in the tick arrays, this is the last tick they cover.
+fn next_tick(
+    arrays: &mut [RefMut<TickArray>],
+    tick_current: i32,
+    tick_spacing: u16,
+    is_x: bool,
+) -> Option<(i32, bool)> {
+    let spacing = tick_spacing as i32;
+    let span = TickArray::span(tick_spacing);
+    let first = arrays.first()?.start_tick_index;
+    let last = arrays.last()?.start_tick_index;
+
+    let is_initialized = |arrays: &mut [RefMut<TickArray>], tick: i32| {
+        tick_mut(arrays, tick, tick_spacing).is_ok_and(|tick| tick.initialized)
+    };
+
+    match is_x {
+        // Moving down, the tick at the current price is still ahead of us.
+        true => {
+            let lowest = last;
+            let mut tick = tick_current.div_euclid(spacing) * spacing;
+            while tick >= lowest {
+                if is_initialized(arrays, tick) {
+                    return Some((tick, true));
+                }
+                tick -= spacing;
+            }
+            (tick_current >= lowest).then_some((lowest, false))
+        }
+        false => {
+            let highest = last + span - spacing;
+            let mut tick = (tick_current.div_euclid(spacing) + 1) * spacing;
+            while tick <= highest && tick >= first {
+                if is_initialized(arrays, tick) {
+                    return Some((tick, true));
+                }
+                tick += spacing;
+            }
+            (tick_current < highest).then_some((highest, false))
+        }
+    }
+}
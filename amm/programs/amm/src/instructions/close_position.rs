@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    state::{ClPool, Position},
+};
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds=[b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump=pool.bump,
+    )]
+    pub pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        has_one=owner,
+        has_one=pool,
+        seeds=[
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+        ],
+        bump=position.bump,
+        constraint=position.liquidity == 0
+            && position.fees_owed_x == 0
+            && position.fees_owed_y == 0 @ AmmError::PositionNotEmpty,
+        close=owner
+    )]
+    pub position: Account<'info, Position>,
+
+    pub system_program: Program<'info, System>,
+}
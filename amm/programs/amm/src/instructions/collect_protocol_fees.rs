@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{errors::AmmError, extensions::transfer, state::Config};
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer=authority,
+        associated_token::mint=mint_x,
+        associated_token::authority=authority,
+        associated_token::token_program=token_program
+    )]
+    pub authority_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer=authority,
+        associated_token::mint=mint_y,
+        associated_token::authority=authority,
+        associated_token::token_program=token_program
+    )]
+    pub authority_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+        constraint=config.authority == Some(authority.key()) @ AmmError::InvalidAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    pub fn collect_protocol_fees(&mut self, extra_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        self.config.check_no_flash_loan()?;
+
+        let (x, y) = (self.config.protocol_fees_x, self.config.protocol_fees_y);
+
+        if x > 0 {
+            self.withdraw_tokens(true, x, extra_accounts)?;
+        }
+        if y > 0 {
+            self.withdraw_tokens(false, y, extra_accounts)?;
+        }
+
+        self.config.protocol_fees_x = 0;
+        self.config.protocol_fees_y = 0;
+
+        Ok(())
+    }
+
+    fn withdraw_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (from, to, mint) = match is_x {
+            true => (&self.vault_x, &self.authority_x, &self.mint_x),
+            false => (&self.vault_y, &self.authority_y, &self.mint_y),
+        };
+
+        let seeds = &[
+            &b"config"[..],
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.config.to_account_info(),
+            extra_accounts,
+            amount,
+            signer_seeds,
+        )
+    }
+}
@@ -1,13 +1,15 @@
+use amm_math::{quote_initial_deposit, MINIMUM_LIQUIDITY};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
-    },
+    token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface},
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::state::Config;
+use crate::{
+    errors::AmmError,
+    extensions::{amount_with_transfer_fee, transfer, transfer_fee},
+    state::Config,
+};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -20,27 +22,31 @@ pub struct Deposit<'info> {
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_x: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_x: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_y: InterfaceAccount<'info, TokenAccount>,
 
@@ -51,15 +57,25 @@ pub struct Deposit<'info> {
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        mut,
+        associated_token::mint=mint_lp,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_lp: InterfaceAccount<'info, TokenAccount>,
+
     #[account(
         init_if_needed,
         payer=signer,
         associated_token::mint=mint_lp,
-        associated_token::authority=signer
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_lp: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds=[b"config", config.seed.to_le_bytes().as_ref()],
@@ -73,51 +89,137 @@ pub struct Deposit<'info> {
 }
 
 impl<'info> Deposit<'info> {
-    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
-        {
-            true => (max_x, max_y),
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        match self.mint_lp.supply == 0 && reserve_x == 0 && reserve_y == 0 {
+            // The first deposit sets the price, and mints sqrt(x * y) LP tokens
+            // of which `MINIMUM_LIQUIDITY` is locked. `amount` isn't used.
+            true => {
+                // Price the deposit by what reaches the vaults after any
+                // Token-2022 transfer fees.
+                let x = max_x - transfer_fee(&self.mint_x.to_account_info(), max_x)?;
+                let y = max_y - transfer_fee(&self.mint_y.to_account_info(), max_y)?;
+                let lp = quote_initial_deposit(x, y).map_err(AmmError::from)?;
+
+                self.deposit_tokens(true, max_x, extra_accounts)?;
+                self.deposit_tokens(false, max_y, extra_accounts)?;
+
+                self.mint_lp_tokens(true, MINIMUM_LIQUIDITY)?;
+                self.mint_lp_tokens(false, lp)?;
+            }
             false => {
-                let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
-                    self.mint_lp.supply,
-                    amount,
-                    6,
-                )
-                .unwrap();
-                (amounts.x, amounts.y)
+                require!(amount > 0, AmmError::InvalidAmount);
+
+                let amounts = self
+                    .config
+                    .pool(reserve_x, reserve_y, self.mint_lp.supply)
+                    .quote_deposit(amount)
+                    .map_err(AmmError::from)?;
+
+                // The vaults must receive the full amounts, so the user covers
+                // any Token-2022 transfer fees on top.
+                let x = amount_with_transfer_fee(&self.mint_x.to_account_info(), amounts.x)?;
+                let y = amount_with_transfer_fee(&self.mint_y.to_account_info(), amounts.y)?;
+                require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+
+                self.deposit_tokens(true, x, extra_accounts)?;
+                self.deposit_tokens(false, y, extra_accounts)?;
+
+                self.mint_lp_tokens(false, amount)?;
             }
+        }
+
+        self.update_oracle()
+    }
+
+    // Deposit only one token. Part of it is swapped through the curve, paying the
+    // usual fee, and the rest is deposited against the swap output.
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_lp: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        require!(self.mint_lp.supply > 0, AmmError::ZeroBalance);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let mint_in = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
         };
+        let received = amount_in - transfer_fee(&mint_in, amount_in)?;
+
+        let (res, lp) = self
+            .config
+            .pool(reserve_x, reserve_y, self.mint_lp.supply)
+            .quote_deposit_single(is_x, received)
+            .map_err(AmmError::from)?;
+        require!(lp > 0 && lp >= min_lp, AmmError::SlippageExceeded);
 
-        self.deposit_tokens(true, x)?;
-        self.deposit_tokens(false, y)?;
+        self.config.record_swap(is_x, res.deposit, res.fee)?;
 
-        self.mint_lp_tokens(amount)?;
+        // The swap output never leaves the vault, so only the input is moved.
+        self.deposit_tokens(is_x, amount_in, extra_accounts)?;
+
+        self.mint_lp_tokens(false, lp)?;
+
+        self.update_oracle()
+    }
 
-        Ok(())
+    // Record the reserves the instruction leaves for the price oracle.
+    fn update_oracle(&mut self) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.config
+            .update_oracle(self.vault_x.amount, self.vault_y.amount)
     }
 
-    fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    fn deposit_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let (from, to, mint) = match is_x {
             true => (&self.user_x, &self.vault_x, &self.mint_x),
             false => (&self.user_y, &self.vault_y, &self.mint_y),
         };
 
-        let accounts = TransferChecked {
-            from: from.to_account_info(),
-            to: to.to_account_info(),
-            mint: mint.to_account_info(),
-            authority: self.signer.to_account_info(),
-        };
-
-        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
-        transfer_checked(cpi_context, amount, mint.decimals)
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.signer.to_account_info(),
+            extra_accounts,
+            amount,
+            &[],
+        )
     }
 
-    fn mint_lp_tokens(&mut self, amount: u64) -> Result<()> {
+    fn mint_lp_tokens(&mut self, is_locked: bool, amount: u64) -> Result<()> {
+        let to = match is_locked {
+            true => &self.vault_lp,
+            false => &self.user_lp,
+        };
+
         let seeds = &[
             &b"config"[..],
             &self.config.seed.to_le_bytes(),
@@ -127,7 +229,7 @@ impl<'info> Deposit<'info> {
 
         let accounts = MintTo {
             mint: self.mint_lp.to_account_info(),
-            to: self.user_lp.to_account_info(),
+            to: to.to_account_info(),
             authority: self.config.to_account_info(),
         };
 
@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::{harvest_withheld_fees, transfer_fee},
+    state::{Config, LimitOrder},
+};
+
+// Accounts passed through remaining_accounts for every order, in this order:
+// order, escrow, owner, owner_out.
+pub const ORDER_ACCOUNTS: usize = 4;
+
+#[derive(Accounts)]
+pub struct FillOrders<'info> {
+    // Anyone can fill orders, and is paid their tips.
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    // Writable for harvesting Token-2022 transfer fees withheld in escrows.
+    #[account(mut)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FillOrders<'info> {
+    // Fills every order the pool can pay its `min_out` for, one after the other,
+    // and skips the rest. Fails if none could be filled.
+    pub fn fill_orders(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        let orders = remaining_accounts.chunks_exact(ORDER_ACCOUNTS);
+        require!(
+            orders.len() > 0 && orders.remainder().is_empty(),
+            AmmError::InvalidOrder
+        );
+
+        let mut filled = 0;
+        for order in orders {
+            if self.fill_order(order)? {
+                filled += 1;
+            }
+        }
+
+        require!(filled > 0, AmmError::NoOrdersFilled);
+
+        Ok(())
+    }
+
+    // Orders whose accounts don't check out are skipped like those below their
+    // limit, so that no order can fail the whole batch.
+    fn fill_order(&mut self, accounts: &'info [AccountInfo<'info>]) -> Result<bool> {
+        let Some((order, escrow, owner_out)) = self.order_accounts(accounts) else {
+            return Ok(false);
+        };
+        let owner = &accounts[2];
+        let (mint_in, mint_out) = self.mints(order.is_x);
+
+        // Earlier fills moved the vault balances.
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Same as a swap by the owner of the whole escrow, including anything
+        // sent to it since, and any Token-2022 transfer fees.
+        let amount = escrow.amount;
+        let amount_in = amount - transfer_fee(&mint_in.to_account_info(), amount)?;
+
+        let Ok(res) = self
+            .config
+            .swap(order.is_x, reserve_x, reserve_y, amount_in)
+        else {
+            return Ok(false);
+        };
+        let received = res.withdraw - transfer_fee(&mint_out.to_account_info(), res.withdraw)?;
+        if received < order.min_out {
+            return Ok(false);
+        }
+
+        self.config.record_swap(order.is_x, res.deposit, res.fee)?;
+
+        let config = self.config.key();
+        let id = order.id.to_le_bytes();
+        let seeds = &[
+            &b"order"[..],
+            config.as_ref(),
+            order.owner.as_ref(),
+            &id,
+            &[order.bump],
+        ];
+        let order_seeds = &[&seeds[..]];
+
+        // Pay the pool from the escrow.
+        let (vault_in, vault_out) = match order.is_x {
+            true => (&self.vault_x, &self.vault_y),
+            false => (&self.vault_y, &self.vault_x),
+        };
+
+        let cpi_accounts = TransferChecked {
+            from: escrow.to_account_info(),
+            to: vault_in.to_account_info(),
+            mint: mint_in.to_account_info(),
+            authority: order.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            order_seeds,
+        );
+        transfer_checked(cpi_context, amount, mint_in.decimals)?;
+
+        // Pay the owner from the pool.
+        let cpi_accounts = TransferChecked {
+            from: vault_out.to_account_info(),
+            to: owner_out.to_account_info(),
+            mint: mint_out.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seeds = &[
+            &b"config"[..],
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ];
+        let config_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            config_seeds,
+        );
+        transfer_checked(cpi_context, res.withdraw, mint_out.decimals)?;
+
+        // Record the reserves the fill leaves for the price oracle.
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.config
+            .update_oracle(self.vault_x.amount, self.vault_y.amount)?;
+
+        harvest_withheld_fees(&self.token_program, &mint_in, escrow.to_account_info())?;
+
+        let cpi_accounts = CloseAccount {
+            account: escrow.to_account_info(),
+            destination: owner.clone(),
+            authority: order.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            order_seeds,
+        );
+        close_account(cpi_context)?;
+
+        // The keeper gets the tip, the owner the rent.
+        order.sub_lamports(order.tip)?;
+        self.keeper.add_lamports(order.tip)?;
+        order.close(owner.clone())?;
+
+        Ok(true)
+    }
+
+    // The order, its escrow and the owner's account for what it buys, if they
+    // belong together and can all be written to.
+    #[allow(clippy::type_complexity)]
+    fn order_accounts(
+        &self,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Option<(
+        Account<'info, LimitOrder>,
+        InterfaceAccount<'info, TokenAccount>,
+        InterfaceAccount<'info, TokenAccount>,
+    )> {
+        let order = Account::<LimitOrder>::try_from(&accounts[0]).ok()?;
+        let escrow = InterfaceAccount::<TokenAccount>::try_from(&accounts[1]).ok()?;
+        let owner_out = InterfaceAccount::<TokenAccount>::try_from(&accounts[3]).ok()?;
+        let (mint_in, mint_out) = order.mints(&self.config);
+
+        let valid = accounts.iter().all(|account| account.is_writable)
+            && order.config == self.config.key()
+            && accounts[2].key() == order.owner
+            && escrow.key()
+                == get_associated_token_address_with_program_id(
+                    &order.key(),
+                    &mint_in,
+                    &self.token_program.key(),
+                )
+            && escrow.amount > 0
+            && !escrow.is_frozen()
+            && owner_out.owner == order.owner
+            && owner_out.mint == mint_out
+            && !owner_out.is_frozen();
+
+        valid.then_some((order, escrow, owner_out))
+    }
+
+    fn mints(
+        &self,
+        is_x: bool,
+    ) -> (
+        Box<InterfaceAccount<'info, Mint>>,
+        Box<InterfaceAccount<'info, Mint>>,
+    ) {
+        match is_x {
+            true => (self.mint_x.clone(), self.mint_y.clone()),
+            false => (self.mint_y.clone(), self.mint_x.clone()),
+        }
+    }
+}
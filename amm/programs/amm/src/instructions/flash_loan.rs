@@ -0,0 +1,247 @@
+use amm_math::math::mul_div_ceil;
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{
+        load_current_index_checked, load_instruction_at_checked,
+    },
+    Discriminator,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::{amount_with_transfer_fee, transfer},
+    instruction::FlashRepay,
+    state::Config,
+};
+
+// Position of `config` in the accounts of `FlashLoan`, used to match the repay
+// instruction to the pool that lent.
+const CONFIG_INDEX: usize = 7;
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer=signer,
+        associated_token::mint=mint_x,
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
+    )]
+    pub user_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer=signer,
+        associated_token::mint=mint_y,
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
+    )]
+    pub user_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: The instructions sysvar, checked by address.
+    #[account(address=anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FlashLoan<'info> {
+    pub fn flash_borrow(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        // Also makes sure no other flash loan from this pool is outstanding.
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let reserve = match is_x {
+            true => reserve_x,
+            false => reserve_y,
+        };
+        require!(amount <= reserve, AmmError::InsufficientBalance);
+
+        self.check_repay()?;
+
+        match is_x {
+            true => self.config.flash_loan_x = amount,
+            false => self.config.flash_loan_y = amount,
+        }
+
+        self.withdraw_tokens(is_x, amount, extra_accounts)
+    }
+
+    // Repays the outstanding loan plus the pool fee. The fee stays in the vault
+    // for LPs, except for the protocol share.
+    pub fn flash_repay(&mut self, extra_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Only one loan per pool can be outstanding at a time.
+        let (is_x, amount) = match self.config.flash_loan_x > 0 {
+            true => (true, self.config.flash_loan_x),
+            false => (false, self.config.flash_loan_y),
+        };
+        require!(amount > 0, AmmError::NoFlashLoan);
+
+        let fee = mul_div_ceil(amount as u128, self.config.fee as u128, 10_000)
+            .and_then(|fee| u64::try_from(fee).ok())
+            .ok_or(AmmError::Overflow)?;
+        let protocol_fee = self.config.protocol_share(fee);
+
+        let config: &mut Config = &mut self.config;
+        let (loan, fees, protocol_fees) = match is_x {
+            true => (
+                &mut config.flash_loan_x,
+                &mut config.fees_x,
+                &mut config.protocol_fees_x,
+            ),
+            false => (
+                &mut config.flash_loan_y,
+                &mut config.fees_y,
+                &mut config.protocol_fees_y,
+            ),
+        };
+
+        *loan = 0;
+        *fees = fees.checked_add(fee as u128).ok_or(AmmError::Overflow)?;
+        *protocol_fees = protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+
+        // The vault must get the loan and fee back in full, net of any Token-2022
+        // transfer fee.
+        let mint = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
+        };
+        let total = amount.checked_add(fee).ok_or(AmmError::Overflow)?;
+        let total = amount_with_transfer_fee(&mint, total)?;
+        self.deposit_tokens(is_x, total, extra_accounts)?;
+
+        self.update_oracle()
+    }
+
+    // The borrow must be a top-level instruction of this program, followed later
+    // in the transaction by a `flash_repay` for the same pool.
+    fn check_repay(&self) -> Result<()> {
+        let instructions = self.instructions.to_account_info();
+        let current = load_current_index_checked(&instructions)? as usize;
+
+        let borrow = load_instruction_at_checked(current, &instructions)?;
+        require_keys_eq!(borrow.program_id, crate::ID, AmmError::FlashLoanNotRepaid);
+
+        let mut index = current + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &instructions) {
+            let is_repay = ix.program_id == crate::ID
+                && ix.data.starts_with(&FlashRepay::DISCRIMINATOR)
+                && ix
+                    .accounts
+                    .get(CONFIG_INDEX)
+                    .is_some_and(|account| account.pubkey == self.config.key());
+            if is_repay {
+                return Ok(());
+            }
+            index += 1;
+        }
+
+        err!(AmmError::FlashLoanNotRepaid)
+    }
+
+    // Record the reserves the instruction leaves for the price oracle.
+    fn update_oracle(&mut self) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.config
+            .update_oracle(self.vault_x.amount, self.vault_y.amount)
+    }
+
+    fn deposit_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (from, to, mint) = match is_x {
+            true => (&self.user_x, &self.vault_x, &self.mint_x),
+            false => (&self.user_y, &self.vault_y, &self.mint_y),
+        };
+
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.signer.to_account_info(),
+            extra_accounts,
+            amount,
+            &[],
+        )
+    }
+
+    fn withdraw_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (from, to, mint) = match is_x {
+            true => (&self.vault_x, &self.user_x, &self.mint_x),
+            false => (&self.vault_y, &self.user_y, &self.mint_y),
+        };
+
+        let seeds = &[
+            &b"config"[..],
+            &self.config.seed.to_le_bytes(),
+            &[self.config.config_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.config.to_account_info(),
+            extra_accounts,
+            amount,
+            signer_seeds,
+        )
+    }
+}
@@ -1,17 +1,23 @@
+use amm_math::curve::stable_swap::{MAX_AMP, MIN_AMP};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::Config;
+use crate::{
+    errors::AmmError,
+    extensions::check_mint_extensions,
+    state::{Config, CurveType, PairIndex, PoolRegistry},
+};
 
 #[derive(Accounts)]
-#[instruction(seed: u64)]
+#[instruction(seed: u64, authority: Option<Pubkey>, fee: u16, curve: CurveType)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    // Mints must be passed in canonical order, see `initialize`.
     pub mint_x: InterfaceAccount<'info, Mint>,
     pub mint_y: InterfaceAccount<'info, Mint>,
 
@@ -19,14 +25,16 @@ pub struct Initialize<'info> {
         init,
         payer=signer,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_x: InterfaceAccount<'info, TokenAccount>,
     #[account(
         init,
         payer=signer,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
@@ -40,6 +48,16 @@ pub struct Initialize<'info> {
     )]
     pub mint_lp: InterfaceAccount<'info, Mint>,
 
+    // Holds the LP tokens locked by the first deposit.
+    #[account(
+        init,
+        payer=signer,
+        associated_token::mint=mint_lp,
+        associated_token::authority=config,
+        associated_token::token_program=token_program
+    )]
+    pub vault_lp: InterfaceAccount<'info, TokenAccount>,
+
     #[account(
         init,
         payer=signer,
@@ -49,6 +67,29 @@ pub struct Initialize<'info> {
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        init_if_needed,
+        payer=signer,
+        seeds=[b"registry"],
+        bump,
+        space=PoolRegistry::INIT_SPACE + 8
+    )]
+    pub registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+        init,
+        payer=signer,
+        seeds=[
+            b"pair",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+            curve.tag().to_le_bytes().as_ref(),
+        ],
+        bump,
+        space=PairIndex::INIT_SPACE + 8
+    )]
+    pub pair_index: Box<Account<'info, PairIndex>>,
+
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
@@ -60,19 +101,66 @@ impl<'info> Initialize<'info> {
         seed: u64,
         authority: Option<Pubkey>,
         fee: u16,
+        curve: CurveType,
         bumps: &InitializeBumps,
     ) -> Result<()> {
+        // Sorting the mints makes X/Y and Y/X the same pair.
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintOrder
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        check_mint_extensions(&self.mint_x.to_account_info())?;
+        check_mint_extensions(&self.mint_y.to_account_info())?;
+        if let CurveType::StableSwap { amp } = curve {
+            require!(
+                (MIN_AMP..=MAX_AMP).contains(&amp),
+                AmmError::InvalidAmplification
+            );
+        }
+
         self.config.set_inner(Config {
             seed,
             authority,
             mint_x: self.mint_x.key(),
             mint_y: self.mint_y.key(),
+            curve,
             fee,
+            protocol_fee: 0,
             config_bump: bumps.config,
             mint_lp_bump: bumps.mint_lp,
             locked: false,
+            protocol_fees_x: 0,
+            protocol_fees_y: 0,
+            volume_x: 0,
+            volume_y: 0,
+            fees_x: 0,
+            fees_y: 0,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update_ts: Clock::get()?.unix_timestamp,
+            last_reserve_x: 0,
+            last_reserve_y: 0,
+            flash_loan_x: 0,
+            flash_loan_y: 0,
         });
 
+        self.pair_index.set_inner(PairIndex {
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            curve,
+            config: self.config.key(),
+            index: self.registry.pool_count,
+            bump: bumps.pair_index,
+        });
+
+        self.registry.pool_count = self
+            .registry
+            .pool_count
+            .checked_add(1)
+            .ok_or(AmmError::Overflow)?;
+        self.registry.bump = bumps.registry;
+
         Ok(())
     }
 }
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::extension::ExtensionType,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    clmm::tick_math::{tick_at_sqrt_price, MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    errors::AmmError,
+    extensions::{check_mint_extensions, has_extension},
+    state::ClPool,
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer=signer,
+        associated_token::mint=mint_x,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer=signer,
+        associated_token::mint=mint_y,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer=signer,
+        seeds=[b"cl_pool", seed.to_le_bytes().as_ref()],
+        bump,
+        space=ClPool::INIT_SPACE + 8
+    )]
+    pub pool: Account<'info, ClPool>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeClPool<'info> {
+    pub fn initialize_cl_pool(
+        &mut self,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+        bumps: &InitializeClPoolBumps,
+    ) -> Result<()> {
+        // Sorted like the mints of other pools, though CL pools aren't in the
+        // registry or pair index.
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintOrder
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(tick_spacing > 0, AmmError::InvalidTickSpacing);
+
+        // Concentrated-liquidity instructions don't account for transfer fees or
+        // forward transfer hook accounts yet.
+        for mint in [&self.mint_x, &self.mint_y] {
+            let mint = mint.to_account_info();
+            check_mint_extensions(&mint)?;
+            require!(
+                !has_extension(&mint, ExtensionType::TransferFeeConfig)?
+                    && !has_extension(&mint, ExtensionType::TransferHook)?,
+                AmmError::UnsupportedMintExtension
+            );
+        }
+        require!(
+            (MIN_SQRT_PRICE..MAX_SQRT_PRICE).contains(&sqrt_price),
+            AmmError::InvalidSqrtPrice
+        );
+
+        self.pool.set_inner(ClPool {
+            seed,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick_current: tick_at_sqrt_price(sqrt_price)?,
+            liquidity: 0,
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            bump: bumps.pool,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::check_mint_extensions,
+    state::{Config, Farm},
+};
+
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds=[b"mint", config.key().as_ref()],
+        bump=config.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=reward_token_program)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer=authority,
+        associated_token::mint=mint_lp,
+        associated_token::authority=farm,
+        associated_token::token_program=token_program
+    )]
+    pub lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer=authority,
+        associated_token::mint=reward_mint,
+        associated_token::authority=farm,
+        associated_token::token_program=reward_token_program
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+        constraint=config.authority == Some(authority.key()) @ AmmError::InvalidAuthority
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        payer=authority,
+        seeds=[b"farm", config.key().as_ref()],
+        bump,
+        space=Farm::INIT_SPACE + 8
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeFarm<'info> {
+    pub fn initialize_farm(
+        &mut self,
+        reward_per_second: u64,
+        start_time: i64,
+        end_time: i64,
+        bumps: &InitializeFarmBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            start_time >= now && end_time > start_time,
+            AmmError::InvalidFarmSchedule
+        );
+        check_mint_extensions(&self.reward_mint.to_account_info())?;
+
+        self.farm.set_inner(Farm {
+            config: self.config.key(),
+            authority: self.authority.key(),
+            reward_mint: self.reward_mint.key(),
+            reward_per_second,
+            start_time,
+            end_time,
+            last_update_time: now,
+            reward_per_share: 0,
+            total_staked: 0,
+            bump: bumps.farm,
+        });
+
+        Ok(())
+    }
+}
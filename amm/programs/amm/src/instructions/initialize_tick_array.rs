@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    clmm::tick_math::{MAX_TICK, MIN_TICK},
+    errors::AmmError,
+    state::{ClPool, TickArray},
+};
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds=[b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump=pool.bump,
+    )]
+    pub pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer=signer,
+        seeds=[b"tick_array", pool.key().as_ref(), start_tick_index.to_le_bytes().as_ref()],
+        bump,
+        space=8 + std::mem::size_of::<TickArray>()
+    )]
+    pub tick_array: AccountLoader<'info, TickArray>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeTickArray<'info> {
+    pub fn initialize_tick_array(&mut self, start_tick_index: i32) -> Result<()> {
+        let tick_spacing = self.pool.tick_spacing;
+        require!(
+            start_tick_index == TickArray::start_index(start_tick_index, tick_spacing)
+                && start_tick_index + TickArray::span(tick_spacing) > MIN_TICK
+                && start_tick_index <= MAX_TICK,
+            AmmError::InvalidTickArray
+        );
+
+        let mut tick_array = self.tick_array.load_init()?;
+        tick_array.pool = self.pool.key();
+        tick_array.start_tick_index = start_tick_index;
+
+        Ok(())
+    }
+}
@@ -9,3 +9,54 @@ pub use swap::*;
 
 pub mod withdraw;
 pub use withdraw::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+pub mod observe;
+pub use observe::*;
+
+pub mod route_swap;
+pub use route_swap::*;
+
+pub mod flash_loan;
+pub use flash_loan::*;
+
+pub mod initialize_cl_pool;
+pub use initialize_cl_pool::*;
+
+pub mod initialize_tick_array;
+pub use initialize_tick_array::*;
+
+pub mod open_position;
+pub use open_position::*;
+
+pub mod modify_liquidity;
+pub use modify_liquidity::*;
+
+pub mod close_position;
+pub use close_position::*;
+
+pub mod cl_swap;
+pub use cl_swap::*;
+
+pub mod initialize_farm;
+pub use initialize_farm::*;
+
+pub mod stake;
+pub use stake::*;
+
+pub mod update_farm;
+pub use update_farm::*;
+
+pub mod place_order;
+pub use place_order::*;
+
+pub mod cancel_order;
+pub use cancel_order::*;
+
+pub mod fill_orders;
+pub use fill_orders::*;
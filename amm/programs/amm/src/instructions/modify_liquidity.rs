@@ -0,0 +1,271 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    clmm::{liquidity_math::amounts_for_liquidity, tick_math::sqrt_price_at_tick},
+    errors::AmmError,
+    state::{fee_growth_inside, ClPool, Position, TickArray},
+};
+
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: InterfaceAccount<'info, Mint>,
+    pub mint_y: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_x,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint=mint_y,
+        associated_token::authority=pool,
+        associated_token::token_program=token_program
+    )]
+    pub vault_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_x,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_x: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_y,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_y: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one=mint_x,
+        has_one=mint_y,
+        seeds=[b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump=pool.bump,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        mut,
+        has_one=owner,
+        has_one=pool,
+        seeds=[
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+        ],
+        bump=position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // Both may be the same account when the range fits in one tick array.
+    #[account(mut, has_one=pool)]
+    pub tick_array_lower: AccountLoader<'info, TickArray>,
+    #[account(mut, has_one=pool)]
+    pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    pub fn increase_liquidity(&mut self, liquidity: u128, max_x: u64, max_y: u64) -> Result<()> {
+        require!(liquidity > 0, AmmError::InvalidAmount);
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+
+        let (x, y) = self.modify(delta)?;
+        require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+
+        self.deposit_tokens(true, x)?;
+        self.deposit_tokens(false, y)?;
+
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(&mut self, liquidity: u128, min_x: u64, min_y: u64) -> Result<()> {
+        require!(
+            liquidity > 0 && liquidity <= self.position.liquidity,
+            AmmError::InvalidAmount
+        );
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+
+        let (x, y) = self.modify(-delta)?;
+        require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+
+        self.withdraw_tokens(true, x)?;
+        self.withdraw_tokens(false, y)?;
+
+        Ok(())
+    }
+
+    pub fn collect_fees(&mut self) -> Result<()> {
+        self.modify(0)?;
+
+        let (x, y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        self.withdraw_tokens(true, x)?;
+        self.withdraw_tokens(false, y)?;
+
+        Ok(())
+    }
+
+    // Update the position's ticks, fees and liquidity, and return the token
+    // amounts that correspond to `liquidity_delta`.
+    fn modify(&mut self, liquidity_delta: i128) -> Result<(u64, u64)> {
+        let tick_spacing = self.pool.tick_spacing;
+        let tick_current = self.pool.tick_current;
+        let (fee_growth_global_x, fee_growth_global_y) =
+            (self.pool.fee_growth_global_x, self.pool.fee_growth_global_y);
+        let (tick_lower, tick_upper) = (self.position.tick_lower, self.position.tick_upper);
+
+        // Each tick array is loaded on its own, so this also works when both
+        // accounts are the same.
+        let (lower, lower_emptied) = {
+            let mut tick_array = self.tick_array_lower.load_mut()?;
+            let tick = tick_array.tick_mut(tick_lower, tick_spacing)?;
+            let emptied = match liquidity_delta {
+                0 => false,
+                _ => tick.update(
+                    tick_lower,
+                    tick_current,
+                    liquidity_delta,
+                    fee_growth_global_x,
+                    fee_growth_global_y,
+                    false,
+                )?,
+            };
+            (*tick, emptied)
+        };
+        let (upper, upper_emptied) = {
+            let mut tick_array = self.tick_array_upper.load_mut()?;
+            let tick = tick_array.tick_mut(tick_upper, tick_spacing)?;
+            let emptied = match liquidity_delta {
+                0 => false,
+                _ => tick.update(
+                    tick_upper,
+                    tick_current,
+                    liquidity_delta,
+                    fee_growth_global_x,
+                    fee_growth_global_y,
+                    true,
+                )?,
+            };
+            (*tick, emptied)
+        };
+
+        let (fee_growth_inside_x, fee_growth_inside_y) = fee_growth_inside(
+            &lower,
+            &upper,
+            tick_lower,
+            tick_upper,
+            tick_current,
+            fee_growth_global_x,
+            fee_growth_global_y,
+        );
+        self.position
+            .update(liquidity_delta, fee_growth_inside_x, fee_growth_inside_y)?;
+
+        // Ticks left without liquidity are only cleared now, as their fee growth
+        // outside is part of the fee growth inside the position.
+        if lower_emptied {
+            self.tick_array_lower
+                .load_mut()?
+                .tick_mut(tick_lower, tick_spacing)?
+                .clear();
+        }
+        if upper_emptied {
+            self.tick_array_upper
+                .load_mut()?
+                .tick_mut(tick_upper, tick_spacing)?
+                .clear();
+        }
+
+        if tick_lower <= tick_current && tick_current < tick_upper {
+            self.pool.liquidity = self
+                .pool
+                .liquidity
+                .checked_add_signed(liquidity_delta)
+                .ok_or(AmmError::Overflow)?;
+        }
+
+        amounts_for_liquidity(
+            self.pool.sqrt_price,
+            sqrt_price_at_tick(tick_lower)?,
+            sqrt_price_at_tick(tick_upper)?,
+            liquidity_delta.unsigned_abs(),
+            liquidity_delta > 0,
+        )
+    }
+
+    fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint) = match is_x {
+            true => (&self.owner_x, &self.vault_x, &self.mint_x),
+            false => (&self.owner_y, &self.vault_y, &self.mint_y),
+        };
+
+        let accounts = TransferChecked {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+
+    fn withdraw_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint) = match is_x {
+            true => (&self.vault_x, &self.owner_x, &self.mint_x),
+            false => (&self.vault_y, &self.owner_y, &self.mint_y),
+        };
+
+        let accounts = TransferChecked {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let seeds = &[
+            &b"cl_pool"[..],
+            &self.pool.seed.to_le_bytes(),
+            &[self.pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, amount, mint.decimals)
+    }
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Config, Observation};
+
+// Prices from the reserves recorded by the last update rather than the vault
+// balances, which anyone can move by sending tokens to the vaults.
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> Observe<'info> {
+    pub fn observe(&self) -> Result<Observation> {
+        Ok(self.config.observe(Clock::get()?.unix_timestamp))
+    }
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    clmm::tick_math::{MAX_TICK, MIN_TICK},
+    errors::AmmError,
+    state::{ClPool, Position},
+};
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds=[b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump=pool.bump,
+    )]
+    pub pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space=Position::INIT_SPACE + 8
+    )]
+    pub position: Account<'info, Position>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenPosition<'info> {
+    pub fn open_position(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        bumps: &OpenPositionBumps,
+    ) -> Result<()> {
+        let tick_spacing = self.pool.tick_spacing as i32;
+        require!(
+            tick_lower < tick_upper
+                && tick_lower >= MIN_TICK
+                && tick_upper <= MAX_TICK
+                && tick_lower.rem_euclid(tick_spacing) == 0
+                && tick_upper.rem_euclid(tick_spacing) == 0,
+            AmmError::InvalidTick
+        );
+
+        self.position.set_inner(Position {
+            pool: self.pool.key(),
+            owner: self.owner.key(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_x_last: 0,
+            fee_growth_inside_y_last: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            bump: bumps.position,
+        });
+
+        Ok(())
+    }
+}
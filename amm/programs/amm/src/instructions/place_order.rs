@@ -0,0 +1,138 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::extension::ExtensionType,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::{has_extension, transfer_fee},
+    state::{Config, LimitOrder},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct PlaceOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_in,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_in: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Created here so that a fill never fails for lack of it.
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=mint_out,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer=owner,
+        associated_token::mint=mint_in,
+        associated_token::authority=order,
+        associated_token::token_program=token_program
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        payer=owner,
+        seeds=[
+            b"order",
+            config.key().as_ref(),
+            owner.key().as_ref(),
+            id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space=LimitOrder::INIT_SPACE + 8
+    )]
+    pub order: Box<Account<'info, LimitOrder>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceOrder<'info> {
+    pub fn place_order(
+        &mut self,
+        id: u64,
+        amount: u64,
+        min_out: u64,
+        tip: u64,
+        bumps: &PlaceOrderBumps,
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0 && min_out > 0, AmmError::InvalidAmount);
+
+        let is_x = match (self.mint_in.key(), self.mint_out.key()) {
+            (i, o) if i == self.config.mint_x && o == self.config.mint_y => true,
+            (i, o) if i == self.config.mint_y && o == self.config.mint_x => false,
+            _ => return err!(AmmError::InvalidOrder),
+        };
+
+        // Orders are filled through remaining_accounts, which leaves no room for
+        // the extra accounts of Token-2022 transfer hooks.
+        for mint in [&self.mint_in, &self.mint_out] {
+            require!(
+                !has_extension(&mint.to_account_info(), ExtensionType::TransferHook)?,
+                AmmError::UnsupportedMintExtension
+            );
+        }
+
+        // The order sells what reaches the escrow after any Token-2022 transfer fee.
+        let escrowed = amount - transfer_fee(&self.mint_in.to_account_info(), amount)?;
+        require!(escrowed > 0, AmmError::InvalidAmount);
+
+        self.order.set_inner(LimitOrder {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            id,
+            is_x,
+            amount: escrowed,
+            min_out,
+            tip,
+            bump: bumps.order,
+        });
+
+        let accounts = TransferChecked {
+            from: self.owner_in.to_account_info(),
+            to: self.escrow.to_account_info(),
+            mint: self.mint_in.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_context, amount, self.mint_in.decimals)?;
+
+        // The tip is held by the order account until it is filled or cancelled.
+        let accounts = Transfer {
+            from: self.owner.to_account_info(),
+            to: self.order.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.system_program.to_account_info(), accounts);
+        transfer(cpi_context, tip)
+    }
+}
@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_2022::spl_token_2022::extension::ExtensionType,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::{has_extension, transfer_fee},
+    state::Config,
+};
+
+// Accounts passed through remaining_accounts for every hop, in this order:
+// config, mint_in, mint_out, vault_in, vault_out, user_out.
+pub const HOP_ACCOUNTS: usize = 6;
+
+#[derive(Accounts)]
+pub struct RouteSwap<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    // The token account the first hop is paid from.
+    #[account(
+        mut,
+        token::authority=signer,
+        token::token_program=token_program
+    )]
+    pub user_in: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RouteSwap<'info> {
+    pub fn route_swap(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        amount_in: u64,
+        min_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        let hops = remaining_accounts.chunks_exact(HOP_ACCOUNTS);
+        require!(
+            hops.len() > 0 && hops.remainder().is_empty(),
+            AmmError::InvalidRoute
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= expiration,
+            AmmError::Expired
+        );
+
+        let mut from = self.user_in.to_account_info();
+        let mut amount = amount_in;
+
+        for hop in hops {
+            amount = self.swap_hop(hop, &from, amount)?;
+            // The next hop is paid from what this one sent to the user.
+            from = hop[5].clone();
+        }
+
+        require!(amount >= min_out, AmmError::SlippageExceeded);
+
+        Ok(())
+    }
+
+    fn swap_hop(
+        &self,
+        hop: &'info [AccountInfo<'info>],
+        from: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<u64> {
+        require!(hop[0].is_writable, AmmError::InvalidRoute);
+        let mut config = Account::<Config>::try_from(&hop[0])?;
+        let mint_in = InterfaceAccount::<Mint>::try_from(&hop[1])?;
+        let mint_out = InterfaceAccount::<Mint>::try_from(&hop[2])?;
+        let mut vault_in = InterfaceAccount::<TokenAccount>::try_from(&hop[3])?;
+        let mut vault_out = InterfaceAccount::<TokenAccount>::try_from(&hop[4])?;
+        let user_out = InterfaceAccount::<TokenAccount>::try_from(&hop[5])?;
+
+        let config_key = Pubkey::create_program_address(
+            &[
+                b"config",
+                config.seed.to_le_bytes().as_ref(),
+                &[config.config_bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| AmmError::InvalidRoute)?;
+        require_keys_eq!(config.key(), config_key, AmmError::InvalidRoute);
+        require!(!config.locked, AmmError::PoolLocked);
+
+        let is_x = match (mint_in.key(), mint_out.key()) {
+            (i, o) if i == config.mint_x && o == config.mint_y => true,
+            (i, o) if i == config.mint_y && o == config.mint_x => false,
+            _ => return err!(AmmError::InvalidRoute),
+        };
+
+        let token_program = self.token_program.key();
+        require_keys_eq!(
+            vault_in.key(),
+            get_associated_token_address_with_program_id(
+                &config.key(),
+                &mint_in.key(),
+                &token_program
+            ),
+            AmmError::InvalidRoute
+        );
+        require_keys_eq!(
+            vault_out.key(),
+            get_associated_token_address_with_program_id(
+                &config.key(),
+                &mint_out.key(),
+                &token_program
+            ),
+            AmmError::InvalidRoute
+        );
+        require_keys_eq!(user_out.mint, mint_out.key(), AmmError::InvalidRoute);
+        require_keys_eq!(user_out.owner, self.signer.key(), AmmError::InvalidRoute);
+
+        // Remaining accounts are taken by the hops, so there is no room for the
+        // extra accounts of Token-2022 transfer hooks.
+        for mint in [&mint_in, &mint_out] {
+            require!(
+                !has_extension(&mint.to_account_info(), ExtensionType::TransferHook)?,
+                AmmError::UnsupportedMintExtension
+            );
+        }
+
+        let (reserve_x, reserve_y) = match is_x {
+            true => config.reserves(vault_in.amount, vault_out.amount)?,
+            false => config.reserves(vault_out.amount, vault_in.amount)?,
+        };
+
+        // Only what reaches the vault after any Token-2022 transfer fee is swapped.
+        let amount_in = amount - transfer_fee(&mint_in.to_account_info(), amount)?;
+
+        // Slippage is only enforced once, on the output of the last hop.
+        let res = config.swap(is_x, reserve_x, reserve_y, amount_in)?;
+
+        config.record_swap(is_x, res.deposit, res.fee)?;
+
+        // Pay the pool from the user.
+        let accounts = TransferChecked {
+            from: from.clone(),
+            to: vault_in.to_account_info(),
+            mint: mint_in.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_context, amount, mint_in.decimals)?;
+
+        // Pay the user from the pool.
+        let accounts = TransferChecked {
+            from: vault_out.to_account_info(),
+            to: user_out.to_account_info(),
+            mint: mint_out.to_account_info(),
+            authority: config.to_account_info(),
+        };
+
+        let seeds = &[
+            &b"config"[..],
+            &config.seed.to_le_bytes(),
+            &[config.config_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, res.withdraw, mint_out.decimals)?;
+
+        // Record the reserves the hop leaves for the price oracle. The pool is
+        // written back before the next hop, which may swap through it again.
+        vault_in.reload()?;
+        vault_out.reload()?;
+        match is_x {
+            true => config.update_oracle(vault_in.amount, vault_out.amount)?,
+            false => config.update_oracle(vault_out.amount, vault_in.amount)?,
+        }
+        config.exit(&crate::ID)?;
+
+        let received = res.withdraw - transfer_fee(&mint_out.to_account_info(), res.withdraw)?;
+        Ok(received)
+    }
+}
@@ -0,0 +1,171 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::AmmError,
+    extensions::transfer,
+    state::{Config, Farm, StakePosition},
+};
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds=[b"mint", config.key().as_ref()],
+        bump=config.mint_lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program=reward_token_program)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_lp,
+        associated_token::authority=farm,
+        associated_token::token_program=token_program
+    )]
+    pub lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=reward_mint,
+        associated_token::authority=farm,
+        associated_token::token_program=reward_token_program
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint=mint_lp,
+        associated_token::authority=owner,
+        associated_token::token_program=token_program
+    )]
+    pub owner_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer=owner,
+        associated_token::mint=reward_mint,
+        associated_token::authority=owner,
+        associated_token::token_program=reward_token_program
+    )]
+    pub owner_reward: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        has_one=config,
+        has_one=reward_mint,
+        seeds=[b"farm", config.key().as_ref()],
+        bump=farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(
+        init_if_needed,
+        payer=owner,
+        seeds=[b"stake", farm.key().as_ref(), owner.key().as_ref()],
+        bump,
+        space=StakePosition::INIT_SPACE + 8
+    )]
+    pub stake_position: Box<Account<'info, StakePosition>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Stake<'info> {
+    pub fn stake(&mut self, amount: u64, bumps: &StakeBumps) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        let delta = i64::try_from(amount).map_err(|_| AmmError::Overflow)?;
+
+        if self.stake_position.farm == Pubkey::default() {
+            self.stake_position.set_inner(StakePosition {
+                farm: self.farm.key(),
+                owner: self.owner.key(),
+                amount: 0,
+                reward_debt: 0,
+                pending_rewards: 0,
+                bump: bumps.stake_position,
+            });
+        }
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.stake_position.update(&mut self.farm, delta)?;
+
+        let accounts = TransferChecked {
+            from: self.owner_lp.to_account_info(),
+            to: self.lp_vault.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_context, amount, self.mint_lp.decimals)
+    }
+
+    pub fn unstake(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        let delta = i64::try_from(amount).map_err(|_| AmmError::Overflow)?;
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.stake_position.update(&mut self.farm, -delta)?;
+
+        let accounts = TransferChecked {
+            from: self.lp_vault.to_account_info(),
+            to: self.owner_lp.to_account_info(),
+            mint: self.mint_lp.to_account_info(),
+            authority: self.farm.to_account_info(),
+        };
+
+        let config = self.config.key();
+        let seeds = &[&b"farm"[..], config.as_ref(), &[self.farm.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+        transfer_checked(cpi_context, amount, self.mint_lp.decimals)
+    }
+
+    // Pays out pending rewards, as far as the reward vault covers them. Whatever
+    // it can't cover stays pending until the farm is topped up.
+    pub fn harvest(&mut self, extra_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.stake_position.update(&mut self.farm, 0)?;
+
+        let amount = self
+            .stake_position
+            .pending_rewards
+            .min(self.reward_vault.amount);
+        require!(amount > 0, AmmError::InvalidAmount);
+        self.stake_position.pending_rewards -= amount;
+
+        let config = self.config.key();
+        let seeds = &[&b"farm"[..], config.as_ref(), &[self.farm.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        transfer(
+            &self.reward_token_program,
+            self.reward_vault.to_account_info(),
+            &self.reward_mint,
+            self.owner_reward.to_account_info(),
+            self.farm.to_account_info(),
+            extra_accounts,
+            amount,
+            signer_seeds,
+        )
+    }
+}
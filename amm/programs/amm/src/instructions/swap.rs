@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::{ConstantProduct, LiquidityPair};
 
-use crate::state::Config;
+use crate::{
+    errors::AmmError,
+    extensions::{amount_with_transfer_fee, transfer, transfer_fee},
+    state::Config,
+};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
@@ -18,31 +21,36 @@ pub struct Swap<'info> {
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_x: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_x: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_y: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds=[b"config", config.seed.to_le_bytes().as_ref()],
@@ -56,59 +64,132 @@ pub struct Swap<'info> {
 }
 
 impl<'info> Swap<'info> {
-    pub fn swap(&mut self, is_x: bool, amount: u64, min: u64) -> Result<()> {
-        let mut curve = ConstantProduct::init(
-            self.vault_x.amount,
-            self.vault_y.amount,
-            self.vault_x.amount,
-            self.config.fee,
-            None,
-        )
-        .unwrap();
+    pub fn swap(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0, AmmError::InvalidAmount);
+        self.check_expiration(expiration)?;
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // With Token-2022 transfer fees the vault receives less than the user
+        // sends, and the user receives less than the vault sends.
+        let (mint_in, mint_out) = self.mints(is_x);
+        let amount_in = amount - transfer_fee(&mint_in, amount)?;
+
+        let res = self.config.swap(is_x, reserve_x, reserve_y, amount_in)?;
+        let received = res.withdraw - transfer_fee(&mint_out, res.withdraw)?;
+        require!(received >= min, AmmError::SlippageExceeded);
+
+        self.config.record_swap(is_x, res.deposit, res.fee)?;
+
+        self.deposit_tokens(is_x, amount, extra_accounts)?;
+        self.withdraw_tokens(!is_x, res.withdraw, extra_accounts)?;
+
+        self.update_oracle()
+    }
 
-        let p = match is_x {
-            true => LiquidityPair::X,
-            false => LiquidityPair::Y,
-        };
+    pub fn swap_exact_out(
+        &mut self,
+        is_x: bool,
+        amount_out: u64,
+        max_in: u64,
+        expiration: i64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount_out > 0, AmmError::InvalidAmount);
+        self.check_expiration(expiration)?;
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        // Gross up both sides for Token-2022 transfer fees, so the user receives
+        // exactly `amount_out` and `max_in` bounds what they actually send.
+        let (mint_in, mint_out) = self.mints(is_x);
+        let amount_out = amount_with_transfer_fee(&mint_out, amount_out)?;
+
+        let res = self
+            .config
+            .swap_exact_out(is_x, reserve_x, reserve_y, amount_out)?;
+        let amount_in = amount_with_transfer_fee(&mint_in, res.deposit)?;
+        require!(amount_in <= max_in, AmmError::SlippageExceeded);
+
+        self.config.record_swap(is_x, res.deposit, res.fee)?;
+
+        self.deposit_tokens(is_x, amount_in, extra_accounts)?;
+        self.withdraw_tokens(!is_x, res.withdraw, extra_accounts)?;
+
+        self.update_oracle()
+    }
 
-        let res = curve.swap(p, amount, min).unwrap();
+    // Record the reserves the instruction leaves for the price oracle.
+    fn update_oracle(&mut self) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.config
+            .update_oracle(self.vault_x.amount, self.vault_y.amount)
+    }
 
-        self.deposit_tokens(is_x, res.deposit)?;
-        self.withdraw_tokens(is_x, res.withdraw)?;
+    fn mints(&self, is_x: bool) -> (AccountInfo<'info>, AccountInfo<'info>) {
+        match is_x {
+            true => (self.mint_x.to_account_info(), self.mint_y.to_account_info()),
+            false => (self.mint_y.to_account_info(), self.mint_x.to_account_info()),
+        }
+    }
+
+    fn check_expiration(&self, expiration: i64) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp <= expiration,
+            AmmError::Expired
+        );
 
         Ok(())
     }
 
-    fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    fn deposit_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let (from, to, mint) = match is_x {
             true => (&self.user_x, &self.vault_x, &self.mint_x),
             false => (&self.user_y, &self.vault_y, &self.mint_y),
         };
 
-        let accounts = TransferChecked {
-            from: from.to_account_info(),
-            to: to.to_account_info(),
-            mint: mint.to_account_info(),
-            authority: self.signer.to_account_info(),
-        };
-
-        let cpi_context = CpiContext::new(self.token_program.to_account_info(), accounts);
-        transfer_checked(cpi_context, amount, mint.decimals)
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.signer.to_account_info(),
+            extra_accounts,
+            amount,
+            &[],
+        )
     }
 
-    fn withdraw_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    fn withdraw_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let (from, to, mint) = match is_x {
             true => (&self.vault_x, &self.user_x, &self.mint_x),
             false => (&self.vault_y, &self.user_y, &self.mint_y),
         };
 
-        let accounts = TransferChecked {
-            from: from.to_account_info(),
-            to: to.to_account_info(),
-            mint: mint.to_account_info(),
-            authority: self.config.to_account_info(),
-        };
-
         let seeds = &[
             &b"config"[..],
             &self.config.seed.to_le_bytes(),
@@ -116,11 +197,15 @@ impl<'info> Swap<'info> {
         ];
         let signer_seeds = &[&seeds[..]];
 
-        let cpi_context = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            accounts,
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.config.to_account_info(),
+            extra_accounts,
+            amount,
             signer_seeds,
-        );
-        transfer_checked(cpi_context, amount, mint.decimals)
+        )
     }
 }
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::AmmError, state::Config};
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"config", config.seed.to_le_bytes().as_ref()],
+        bump=config.config_bump,
+        constraint=config.authority == Some(authority.key()) @ AmmError::InvalidAuthority
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> Update<'info> {
+    pub fn lock(&mut self) -> Result<()> {
+        self.config.locked = true;
+
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> Result<()> {
+        self.config.locked = false;
+
+        Ok(())
+    }
+
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        require!(fee < 10_000, AmmError::InvalidFee);
+        self.config.fee = fee;
+
+        Ok(())
+    }
+
+    pub fn update_protocol_fee(&mut self, protocol_fee: u16) -> Result<()> {
+        require!(protocol_fee <= 10_000, AmmError::InvalidProtocolFee);
+        self.config.protocol_fee = protocol_fee;
+
+        Ok(())
+    }
+
+    // Once renounced, the pool can never be locked, unlocked or updated again.
+    pub fn renounce_authority(&mut self) -> Result<()> {
+        self.config.authority = None;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{errors::AmmError, extensions::transfer, state::Farm};
+
+#[derive(Accounts)]
+pub struct UpdateFarm<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mint::token_program=reward_token_program)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint=reward_mint,
+        associated_token::authority=farm,
+        associated_token::token_program=reward_token_program
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=reward_mint,
+        associated_token::authority=authority,
+        associated_token::token_program=reward_token_program
+    )]
+    pub authority_reward: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one=authority @ AmmError::InvalidAuthority,
+        has_one=reward_mint,
+        seeds=[b"farm", farm.config.as_ref()],
+        bump=farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateFarm<'info> {
+    pub fn top_up_rewards(
+        &mut self,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        transfer(
+            &self.reward_token_program,
+            self.authority_reward.to_account_info(),
+            &self.reward_mint,
+            self.reward_vault.to_account_info(),
+            self.authority.to_account_info(),
+            extra_accounts,
+            amount,
+            &[],
+        )
+    }
+
+    pub fn extend_farm(&mut self, end_time: i64, reward_per_second: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            end_time >= self.farm.end_time && end_time > now,
+            AmmError::InvalidFarmSchedule
+        );
+
+        self.farm.extend(now, end_time, reward_per_second)
+    }
+}
@@ -1,13 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{
-        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
-    },
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::ConstantProduct;
 
-use crate::state::Config;
+use crate::{
+    errors::AmmError,
+    extensions::{transfer, transfer_fee},
+    state::Config,
+};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -20,27 +21,31 @@ pub struct Withdraw<'info> {
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_x: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=config,
+        associated_token::token_program=token_program
     )]
     pub vault_y: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_x,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_x: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint=mint_y,
-        associated_token::authority=config
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_y: InterfaceAccount<'info, TokenAccount>,
 
@@ -54,11 +59,13 @@ pub struct Withdraw<'info> {
     #[account(
         mut,
         associated_token::mint=mint_lp,
-        associated_token::authority=signer
+        associated_token::authority=signer,
+        associated_token::token_program=token_program
     )]
     pub user_lp: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
+        mut,
         has_one=mint_x,
         has_one=mint_y,
         seeds=[b"config", config.seed.to_le_bytes().as_ref()],
@@ -72,46 +79,103 @@ pub struct Withdraw<'info> {
 }
 
 impl<'info> Withdraw<'info> {
-    pub fn withdraw(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
-        {
-            true => (max_x, max_y),
-            false => {
-                let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
-                    self.mint_lp.supply,
-                    amount,
-                    6,
-                )
-                .unwrap();
-                (amounts.x, amounts.y)
-            }
+    pub fn withdraw(
+        &mut self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let amounts = self
+            .config
+            .pool(reserve_x, reserve_y, self.mint_lp.supply)
+            .quote_withdraw(amount)
+            .map_err(AmmError::from)?;
+        let (x, y) = (amounts.x, amounts.y);
+
+        // Like `min_out`, the minimums apply to what the user receives after any
+        // transfer fee.
+        let received_x = x - transfer_fee(&self.mint_x.to_account_info(), x)?;
+        let received_y = y - transfer_fee(&self.mint_y.to_account_info(), y)?;
+        require!(
+            received_x >= min_x && received_y >= min_y,
+            AmmError::SlippageExceeded
+        );
+
+        self.withdraw_tokens(true, x, extra_accounts)?;
+        self.withdraw_tokens(false, y, extra_accounts)?;
+
+        self.burn_lp_tokens(amount)?;
+
+        self.update_oracle()
+    }
+
+    // Withdraw only one token. The other side of the withdrawal is swapped back
+    // through the curve, paying the usual fee.
+    pub fn withdraw_single(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min_out: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        let (res, out) = self
+            .config
+            .pool(reserve_x, reserve_y, self.mint_lp.supply)
+            .quote_withdraw_single(is_x, amount)
+            .map_err(AmmError::from)?;
+        if let Some(res) = res {
+            self.config.record_swap(!is_x, res.deposit, res.fee)?;
+        }
+
+        // `min_out` applies to what the user receives after any transfer fee.
+        let mint_out = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
         };
+        let received = out - transfer_fee(&mint_out, out)?;
+        require!(received >= min_out, AmmError::SlippageExceeded);
 
-        self.withdraw_tokens(true, x)?;
-        self.withdraw_tokens(false, y)?;
+        self.withdraw_tokens(is_x, out, extra_accounts)?;
 
         self.burn_lp_tokens(amount)?;
 
-        Ok(())
+        self.update_oracle()
     }
 
-    fn withdraw_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
+    // Record the reserves the instruction leaves for the price oracle.
+    fn update_oracle(&mut self) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.config
+            .update_oracle(self.vault_x.amount, self.vault_y.amount)
+    }
+
+    fn withdraw_tokens(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        extra_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let (from, to, mint) = match is_x {
             true => (&self.vault_x, &self.user_x, &self.mint_x),
             false => (&self.vault_y, &self.user_y, &self.mint_y),
         };
 
-        let accounts = TransferChecked {
-            from: from.to_account_info(),
-            to: to.to_account_info(),
-            mint: mint.to_account_info(),
-            authority: self.config.to_account_info(),
-        };
-
         let seeds = &[
             &b"config"[..],
             &self.config.seed.to_le_bytes(),
@@ -119,12 +183,16 @@ impl<'info> Withdraw<'info> {
         ];
         let signer_seeds = &[&seeds[..]];
 
-        let cpi_context = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            accounts,
+        transfer(
+            &self.token_program,
+            from.to_account_info(),
+            mint,
+            to.to_account_info(),
+            self.config.to_account_info(),
+            extra_accounts,
+            amount,
             signer_seeds,
-        );
-        transfer_checked(cpi_context, amount, mint.decimals)
+        )
     }
 
     fn burn_lp_tokens(&mut self, amount: u64) -> Result<()> {
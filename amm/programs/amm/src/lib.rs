@@ -2,10 +2,14 @@ use anchor_lang::prelude::*;
 
 declare_id!("79pHagCGJvjR9snFr5YAYitGpMun6abyzD8jmoCCBrTQ");
 
+mod clmm;
+mod errors;
+mod extensions;
 mod instructions;
-mod state;
+pub mod state;
 
 use instructions::*;
+use state::{CurveType, Observation};
 
 #[program]
 pub mod amm {
@@ -16,19 +20,266 @@ pub mod amm {
         seed: u64,
         authority: Option<Pubkey>,
         fee: u16,
+        curve: CurveType,
     ) -> Result<()> {
-        ctx.accounts.initialize(seed, authority, fee, &ctx.bumps)
+        ctx.accounts
+            .initialize(seed, authority, fee, curve, &ctx.bumps)
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y)
+    // Instructions that move pool tokens take the extra accounts of Token-2022
+    // transfer hooks, for either mint, in remaining_accounts.
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .deposit(amount, max_x, max_y, ctx.remaining_accounts)
+    }
+
+    pub fn deposit_single<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        is_x: bool,
+        amount_in: u64,
+        min_lp: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .deposit_single(is_x, amount_in, min_lp, ctx.remaining_accounts)
+    }
+
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap(is_x, amount, min, expiration, ctx.remaining_accounts)
+    }
+
+    pub fn swap_exact_out<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        is_x: bool,
+        amount_out: u64,
+        max_in: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap_exact_out(is_x, amount_out, max_in, expiration, ctx.remaining_accounts)
+    }
+
+    // Swaps through every pool in remaining_accounts in order, see `HOP_ACCOUNTS`.
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+        amount_in: u64,
+        min_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .route_swap(ctx.remaining_accounts, amount_in, min_out, expiration)
+    }
+
+    // Must be followed by `flash_repay` for the same pool in the same transaction.
+    pub fn flash_borrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLoan<'info>>,
+        is_x: bool,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .flash_borrow(is_x, amount, ctx.remaining_accounts)
+    }
+
+    pub fn flash_repay<'info>(ctx: Context<'_, '_, '_, 'info, FlashLoan<'info>>) -> Result<()> {
+        ctx.accounts.flash_repay(ctx.remaining_accounts)
+    }
+
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw(amount, min_x, min_y, ctx.remaining_accounts)
+    }
+
+    // `is_x` selects the token that is received.
+    pub fn withdraw_single<'info>(
+        ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw_single(is_x, lp_amount, min_out, ctx.remaining_accounts)
+    }
+
+    // Returns the price accumulators at the current time. Other programs can call
+    // this through CPI and read the return data to compute a TWAP.
+    pub fn observe(ctx: Context<Observe>) -> Result<Observation> {
+        ctx.accounts.observe()
+    }
+
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.lock()
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.unlock()
+    }
+
+    pub fn update_fee(ctx: Context<Update>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)
+    }
+
+    pub fn update_protocol_fee(ctx: Context<Update>, protocol_fee: u16) -> Result<()> {
+        ctx.accounts.update_protocol_fee(protocol_fee)
+    }
+
+    pub fn collect_protocol_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, CollectProtocolFees<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.collect_protocol_fees(ctx.remaining_accounts)
+    }
+
+    pub fn renounce_authority(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.renounce_authority()
+    }
+
+    // Concentrated-liquidity pools are seeded by `seed` alone. They are not
+    // counted in the registry nor given a pair index, which cover the pools of
+    // `initialize` only.
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_cl_pool(seed, fee, tick_spacing, sqrt_price, &ctx.bumps)
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        ctx.accounts.initialize_tick_array(start_tick_index)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .open_position(tick_lower, tick_upper, &ctx.bumps)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+    ) -> Result<()> {
+        ctx.accounts.increase_liquidity(liquidity, max_x, max_y)
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<()> {
+        ctx.accounts.decrease_liquidity(liquidity, min_x, min_y)
+    }
+
+    pub fn collect_fees(ctx: Context<ModifyLiquidity>) -> Result<()> {
+        ctx.accounts.collect_fees()
+    }
+
+    // The position must be empty, see `ClosePosition`.
+    pub fn close_position(_ctx: Context<ClosePosition>) -> Result<()> {
+        Ok(())
+    }
+
+    // Tick arrays are passed in remaining_accounts, starting with the one holding
+    // the current tick and following the direction of the swap.
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        is_x: bool,
+        amount: u64,
+        min_out: u64,
+        sqrt_price_limit: u128,
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts.cl_swap(
+            ctx.remaining_accounts,
+            is_x,
+            amount,
+            min_out,
+            sqrt_price_limit,
+            expiration,
+        )
+    }
+
+    pub fn initialize_farm(
+        ctx: Context<InitializeFarm>,
+        reward_per_second: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_farm(reward_per_second, start_time, end_time, &ctx.bumps)
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        ctx.accounts.stake(amount, &ctx.bumps)
+    }
+
+    pub fn unstake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        ctx.accounts.unstake(amount)
+    }
+
+    pub fn harvest<'info>(ctx: Context<'_, '_, '_, 'info, Stake<'info>>) -> Result<()> {
+        ctx.accounts.harvest(ctx.remaining_accounts)
+    }
+
+    pub fn top_up_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateFarm<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.top_up_rewards(amount, ctx.remaining_accounts)
+    }
+
+    pub fn extend_farm(
+        ctx: Context<UpdateFarm>,
+        end_time: i64,
+        reward_per_second: u64,
+    ) -> Result<()> {
+        ctx.accounts.extend_farm(end_time, reward_per_second)
+    }
+
+    // Sells `amount` of `mint_in` for at least `min_out` of the other pool mint.
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        id: u64,
+        amount: u64,
+        min_out: u64,
+        tip: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .place_order(id, amount, min_out, tip, &ctx.bumps)
     }
 
-    pub fn swap(ctx: Context<Swap>, is_x: bool, amount: u64, min: u64) -> Result<()> {
-        ctx.accounts.swap(is_x, amount, min)
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        ctx.accounts.cancel_order()
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.withdraw(amount, max_x, max_y)
+    // Permissionless crank over the orders in remaining_accounts, see `ORDER_ACCOUNTS`.
+    pub fn fill_orders<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+        ctx.accounts.fill_orders(ctx.remaining_accounts)
     }
 }
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+// A concentrated-liquidity pool. Liquidity is provided through `Position`s over a
+// tick range instead of fungible LP tokens, see `TickArray` for per-tick state.
+#[account]
+#[derive(InitSpace)]
+pub struct ClPool {
+    pub seed: u64,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub fee: u16,
+    pub tick_spacing: u16,
+    pub sqrt_price: u128, // Q64.64 sqrt of the price of X in Y.
+    pub tick_current: i32,
+    pub liquidity: u128, // Liquidity of the positions in range at the current tick.
+    pub fee_growth_global_x: u128, // Q64.64 fees earned per unit of liquidity.
+    pub fee_growth_global_y: u128,
+    pub bump: u8,
+}
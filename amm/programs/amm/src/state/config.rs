@@ -1,5 +1,8 @@
+use amm_math::{protocol_share, Curve, Pool, SwapResult};
 use anchor_lang::prelude::*;
 
+use crate::errors::AmmError;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
@@ -7,8 +10,203 @@ pub struct Config {
     pub authority: Option<Pubkey>,
     pub mint_x: Pubkey,
     pub mint_y: Pubkey,
+    pub curve: CurveType,
     pub fee: u16,
+    pub protocol_fee: u16, // Share of `fee` in basis points that goes to the protocol.
     pub config_bump: u8,
     pub mint_lp_bump: u8,
     pub locked: bool,
+    pub protocol_fees_x: u64, // Held in vault_x but owed to the protocol.
+    pub protocol_fees_y: u64, // Held in vault_y but owed to the protocol.
+    pub volume_x: u128,
+    pub volume_y: u128,
+    pub fees_x: u128,
+    pub fees_y: u128,
+    pub price_x_cumulative: u128, // Sum of Q64.64 price of X in Y times seconds elapsed.
+    pub price_y_cumulative: u128, // Sum of Q64.64 price of Y in X times seconds elapsed.
+    pub last_update_ts: i64,
+    pub last_reserve_x: u64, // Reserves as of `last_update_ts`, priced until the next update.
+    pub last_reserve_y: u64,
+    pub flash_loan_x: u64, // Lent out by flash_borrow, owed back by flash_repay.
+    pub flash_loan_y: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap { amp: u64 },
+}
+
+impl CurveType {
+    // Identifies the curve regardless of its parameters, used in `PairIndex` seeds.
+    pub fn tag(&self) -> u8 {
+        match self {
+            CurveType::ConstantProduct => 0,
+            CurveType::StableSwap { .. } => 1,
+        }
+    }
+}
+
+impl From<CurveType> for Curve {
+    fn from(curve: CurveType) -> Curve {
+        match curve {
+            CurveType::ConstantProduct => Curve::ConstantProduct,
+            CurveType::StableSwap { amp } => Curve::StableSwap { amp },
+        }
+    }
+}
+
+// Snapshot of the price accumulators. A TWAP over a window is the wrapping
+// difference of two snapshots divided by the seconds between them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Observation {
+    pub price_x_cumulative: u128,
+    pub price_y_cumulative: u128,
+    pub timestamp: i64,
+}
+
+impl Config {
+    // Vault balances available to the curve, i.e. excluding uncollected protocol fees.
+    pub fn reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        self.check_no_flash_loan()?;
+
+        let x = vault_x
+            .checked_sub(self.protocol_fees_x)
+            .ok_or(AmmError::Underflow)?;
+        let y = vault_y
+            .checked_sub(self.protocol_fees_y)
+            .ok_or(AmmError::Underflow)?;
+
+        Ok((x, y))
+    }
+
+    // The pool as `amm_math` prices it, given the reserves and LP supply.
+    pub fn pool(&self, reserve_x: u64, reserve_y: u64, supply: u64) -> Pool {
+        Pool {
+            curve: self.curve.into(),
+            fee: self.fee,
+            protocol_fee: self.protocol_fee,
+            reserve_x,
+            reserve_y,
+            supply,
+        }
+    }
+
+    pub fn swap(
+        &self,
+        is_x: bool,
+        reserve_x: u64,
+        reserve_y: u64,
+        amount_in: u64,
+    ) -> Result<SwapResult> {
+        let res = self
+            .pool(reserve_x, reserve_y, 0)
+            .quote_swap(is_x, amount_in)
+            .map_err(AmmError::from)?;
+
+        Ok(res)
+    }
+
+    pub fn swap_exact_out(
+        &self,
+        is_x: bool,
+        reserve_x: u64,
+        reserve_y: u64,
+        amount_out: u64,
+    ) -> Result<SwapResult> {
+        let res = self
+            .pool(reserve_x, reserve_y, 0)
+            .quote_swap_exact_out(is_x, amount_out)
+            .map_err(AmmError::from)?;
+
+        Ok(res)
+    }
+
+    // Vault balances don't reflect the pool while a flash loan is outstanding, so
+    // anything that prices against them has to wait for the repayment.
+    pub fn check_no_flash_loan(&self) -> Result<()> {
+        require!(
+            self.flash_loan_x == 0 && self.flash_loan_y == 0,
+            AmmError::FlashLoanActive
+        );
+
+        Ok(())
+    }
+
+    // Accumulators as they would be at `now`, if the reserves recorded by the
+    // last update held until then. They are expected to overflow and wrap, like
+    // Uniswap v2.
+    pub fn observe(&self, now: i64) -> Observation {
+        let elapsed = now.saturating_sub(self.last_update_ts);
+        let (reserve_x, reserve_y) = (self.last_reserve_x, self.last_reserve_y);
+        let (mut price_x_cumulative, mut price_y_cumulative) =
+            (self.price_x_cumulative, self.price_y_cumulative);
+
+        if elapsed > 0 && reserve_x > 0 && reserve_y > 0 {
+            let price_x = ((reserve_y as u128) << 64) / reserve_x as u128;
+            let price_y = ((reserve_x as u128) << 64) / reserve_y as u128;
+
+            price_x_cumulative =
+                price_x_cumulative.wrapping_add(price_x.wrapping_mul(elapsed as u128));
+            price_y_cumulative =
+                price_y_cumulative.wrapping_add(price_y.wrapping_mul(elapsed as u128));
+        }
+
+        Observation {
+            price_x_cumulative,
+            price_y_cumulative,
+            timestamp: now,
+        }
+    }
+
+    // Must be called with the vault balances an instruction leaves behind. The
+    // time since the last update is priced at the reserves recorded then, and
+    // these become the reserves for the next update. Tokens sent straight to a
+    // vault are only priced once an update records them, and only for as long
+    // as they stay.
+    pub fn update_oracle(&mut self, vault_x: u64, vault_y: u64) -> Result<()> {
+        let observation = self.observe(Clock::get()?.unix_timestamp);
+        let (reserve_x, reserve_y) = self.reserves(vault_x, vault_y)?;
+
+        self.price_x_cumulative = observation.price_x_cumulative;
+        self.price_y_cumulative = observation.price_y_cumulative;
+        self.last_update_ts = observation.timestamp;
+        self.last_reserve_x = reserve_x;
+        self.last_reserve_y = reserve_y;
+
+        Ok(())
+    }
+
+    // Track volume and fees of a swap, and set aside the protocol share of the fee.
+    pub fn record_swap(&mut self, is_x: bool, amount_in: u64, fee: u64) -> Result<()> {
+        let protocol_fee = self.protocol_share(fee);
+
+        let (volume, fees, protocol_fees) = match is_x {
+            true => (
+                &mut self.volume_x,
+                &mut self.fees_x,
+                &mut self.protocol_fees_x,
+            ),
+            false => (
+                &mut self.volume_y,
+                &mut self.fees_y,
+                &mut self.protocol_fees_y,
+            ),
+        };
+
+        *volume = volume
+            .checked_add(amount_in as u128)
+            .ok_or(AmmError::Overflow)?;
+        *fees = fees.checked_add(fee as u128).ok_or(AmmError::Overflow)?;
+        *protocol_fees = protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    // Part of a swap fee that is set aside for the protocol.
+    pub fn protocol_share(&self, fee: u64) -> u64 {
+        protocol_share(self.protocol_fee, fee)
+    }
 }
@@ -1,2 +1,20 @@
 pub mod config;
 pub use config::*;
+
+pub mod cl_pool;
+pub use cl_pool::*;
+
+pub mod tick_array;
+pub use tick_array::*;
+
+pub mod position;
+pub use position::*;
+
+pub mod registry;
+pub use registry::*;
+
+pub mod farm;
+pub use farm::*;
+
+pub mod order;
+pub use order::*;
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+// Sells `amount` of one side of the pool for at least `min_out` of the other,
+// i.e. at a limit price of `min_out / amount`. The tokens are escrowed in the
+// order's associated token account until it is filled or cancelled.
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub config: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+    pub is_x: bool, // Whether X is sold for Y.
+    pub amount: u64,
+    pub min_out: u64,
+    pub tip: u64, // Lamports on top of rent paid to whoever fills the order.
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    // Mints the order sells and buys.
+    pub fn mints(&self, config: &Config) -> (Pubkey, Pubkey) {
+        match self.is_x {
+            true => (config.mint_x, config.mint_y),
+            false => (config.mint_y, config.mint_x),
+        }
+    }
+}
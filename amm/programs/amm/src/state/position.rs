@@ -0,0 +1,62 @@
+use amm_math::math::mul_div;
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_x_last: u128,
+    pub fee_growth_inside_y_last: u128,
+    pub fees_owed_x: u64,
+    pub fees_owed_y: u64,
+    pub bump: u8,
+}
+
+impl Position {
+    // Credit fees earned since the last update, then apply the liquidity change.
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_x: u128,
+        fee_growth_inside_y: u128,
+    ) -> Result<()> {
+        let owed_x = mul_div(
+            fee_growth_inside_x.wrapping_sub(self.fee_growth_inside_x_last),
+            self.liquidity,
+            1 << 64,
+        )
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or(AmmError::Overflow)?;
+        let owed_y = mul_div(
+            fee_growth_inside_y.wrapping_sub(self.fee_growth_inside_y_last),
+            self.liquidity,
+            1 << 64,
+        )
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or(AmmError::Overflow)?;
+
+        self.fees_owed_x = self
+            .fees_owed_x
+            .checked_add(owed_x)
+            .ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self
+            .fees_owed_y
+            .checked_add(owed_y)
+            .ok_or(AmmError::Overflow)?;
+        self.fee_growth_inside_x_last = fee_growth_inside_x;
+        self.fee_growth_inside_y_last = fee_growth_inside_y;
+
+        self.liquidity = self
+            .liquidity
+            .checked_add_signed(liquidity_delta)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+}
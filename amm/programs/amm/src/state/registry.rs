@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::CurveType;
+
+// Global count of the pools created through `initialize`. Concentrated-liquidity
+// pools are not counted.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolRegistry {
+    pub pool_count: u64,
+    pub bump: u8,
+}
+
+// The canonical pool for a mint pair and curve type. Seeded by the sorted mints
+// and `CurveType::tag`, so there is at most one per pair and curve type, and it can
+// be derived from the mints alone.
+#[account]
+#[derive(InitSpace)]
+pub struct PairIndex {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub curve: CurveType,
+    pub config: Pubkey,
+    pub index: u64, // Position of the pool in the registry, in creation order.
+    pub bump: u8,
+}
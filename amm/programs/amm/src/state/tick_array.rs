@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+pub const TICK_ARRAY_SIZE: usize = 64;
+
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Default)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128, // Added to the pool liquidity when the price crosses the tick upwards.
+    pub liquidity_gross: u128, // Total liquidity of the positions that use this tick.
+    pub fee_growth_outside_x: u128, // Fee growth on the other side of the tick from the current price.
+    pub fee_growth_outside_y: u128,
+}
+
+impl Tick {
+    // Returns whether the tick is left without liquidity. It is not cleared here,
+    // since fees for the position still need its fee growth, see `clear`.
+    pub fn update(
+        &mut self,
+        tick_index: i32,
+        tick_current: i32,
+        liquidity_delta: i128,
+        fee_growth_global_x: u128,
+        fee_growth_global_y: u128,
+        upper: bool,
+    ) -> Result<bool> {
+        let liquidity_gross = self
+            .liquidity_gross
+            .checked_add_signed(liquidity_delta)
+            .ok_or(AmmError::Overflow)?;
+
+        if self.liquidity_gross == 0 {
+            // By convention, all fee growth before a tick is initialized happened below it.
+            if tick_index <= tick_current {
+                self.fee_growth_outside_x = fee_growth_global_x;
+                self.fee_growth_outside_y = fee_growth_global_y;
+            }
+            self.initialized = true;
+        }
+
+        let liquidity_net = match upper {
+            true => self.liquidity_net.checked_sub(liquidity_delta),
+            false => self.liquidity_net.checked_add(liquidity_delta),
+        };
+        self.liquidity_net = liquidity_net.ok_or(AmmError::Overflow)?;
+        self.liquidity_gross = liquidity_gross;
+
+        Ok(liquidity_gross == 0)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Tick::default();
+    }
+
+    // Flip the fee growth to the other side of the tick and return the liquidity
+    // that comes into range when crossing it upwards.
+    pub fn cross(&mut self, fee_growth_global_x: u128, fee_growth_global_y: u128) -> i128 {
+        self.fee_growth_outside_x = fee_growth_global_x.wrapping_sub(self.fee_growth_outside_x);
+        self.fee_growth_outside_y = fee_growth_global_y.wrapping_sub(self.fee_growth_outside_y);
+
+        self.liquidity_net
+    }
+}
+
+// Fee growth per unit of liquidity between two ticks.
+pub fn fee_growth_inside(
+    lower: &Tick,
+    upper: &Tick,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_x: u128,
+    fee_growth_global_y: u128,
+) -> (u128, u128) {
+    let (below_x, below_y) = match tick_current >= tick_lower {
+        true => (lower.fee_growth_outside_x, lower.fee_growth_outside_y),
+        false => (
+            fee_growth_global_x.wrapping_sub(lower.fee_growth_outside_x),
+            fee_growth_global_y.wrapping_sub(lower.fee_growth_outside_y),
+        ),
+    };
+    let (above_x, above_y) = match tick_current < tick_upper {
+        true => (upper.fee_growth_outside_x, upper.fee_growth_outside_y),
+        false => (
+            fee_growth_global_x.wrapping_sub(upper.fee_growth_outside_x),
+            fee_growth_global_y.wrapping_sub(upper.fee_growth_outside_y),
+        ),
+    };
+
+    (
+        fee_growth_global_x
+            .wrapping_sub(below_x)
+            .wrapping_sub(above_x),
+        fee_growth_global_y
+            .wrapping_sub(below_y)
+            .wrapping_sub(above_y),
+    )
+}
+
+// A fixed-size run of ticks, `tick_spacing` apart, starting at `start_tick_index`.
+#[account(zero_copy(unsafe))]
+#[repr(C, packed)]
+pub struct TickArray {
+    pub pool: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+}
+
+impl TickArray {
+    pub fn span(tick_spacing: u16) -> i32 {
+        tick_spacing as i32 * TICK_ARRAY_SIZE as i32
+    }
+
+    // Start index of the tick array that holds `tick_index`.
+    pub fn start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+        let span = Self::span(tick_spacing);
+        tick_index.div_euclid(span) * span
+    }
+
+    pub fn contains(&self, tick_index: i32, tick_spacing: u16) -> bool {
+        let start = self.start_tick_index;
+        tick_index >= start && tick_index < start + Self::span(tick_spacing)
+    }
+
+    pub fn tick_mut(&mut self, tick_index: i32, tick_spacing: u16) -> Result<&mut Tick> {
+        require!(
+            self.contains(tick_index, tick_spacing)
+                && tick_index.rem_euclid(tick_spacing as i32) == 0,
+            AmmError::InvalidTickArray
+        );
+
+        let offset = (tick_index - self.start_tick_index) / tick_spacing as i32;
+        Ok(&mut self.ticks[offset as usize])
+    }
+}
@@ -0,0 +1,133 @@
+use amm::{accounts, instruction};
+use amm_test::{
+    cl_pool::{sqrt_price_near_tick, TestClPool, TICK_SPACING},
+    pool::ata,
+    signature::Signer,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+    InstructionData,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token};
+
+const LIQUIDITY: u128 = 1_000_000_000_000;
+
+fn provide(pool: &mut TestClPool, tick_lower: i32, tick_upper: i32) {
+    let open = pool.open_position_instruction(tick_lower, tick_upper);
+    let increase = pool.modify_liquidity_instruction(
+        tick_lower,
+        tick_upper,
+        instruction::IncreaseLiquidity {
+            liquidity: LIQUIDITY,
+            max_x: u64::MAX,
+            max_y: u64::MAX,
+        },
+    );
+    pool.process(&[open, increase]).unwrap();
+}
+
+// Swap both ways within the current tick array, to earn fees in range.
+fn trade(pool: &mut TestClPool) {
+    let sell_x = pool.swap_instruction(true, 100_000_000, 0);
+    pool.process(&[sell_x]).unwrap();
+    let sell_y = pool.swap_instruction(false, 100_000_000, 0);
+    pool.process(&[sell_y]).unwrap();
+}
+
+// Collect the fees of a position, then withdraw all of its liquidity, which
+// must not earn it anything more. Returns the fees collected.
+fn collect_and_withdraw(pool: &mut TestClPool, tick_lower: i32, tick_upper: i32) -> (u64, u64) {
+    let before = (
+        pool.svm.token_balance(&pool.user_x),
+        pool.svm.token_balance(&pool.user_y),
+    );
+    let collect =
+        pool.modify_liquidity_instruction(tick_lower, tick_upper, instruction::CollectFees {});
+    pool.process(&[collect]).unwrap();
+    let fees = (
+        pool.svm.token_balance(&pool.user_x) - before.0,
+        pool.svm.token_balance(&pool.user_y) - before.1,
+    );
+
+    let decrease = pool.modify_liquidity_instruction(
+        tick_lower,
+        tick_upper,
+        instruction::DecreaseLiquidity {
+            liquidity: LIQUIDITY,
+            min_x: 0,
+            min_y: 0,
+        },
+    );
+    pool.process(&[decrease]).unwrap();
+
+    let position = pool.position_state(tick_lower, tick_upper);
+    assert_eq!(position.liquidity, 0);
+    assert_eq!((position.fees_owed_x, position.fees_owed_y), (0, 0));
+
+    fees
+}
+
+#[test]
+fn withdrawing_all_of_a_range_around_the_price_credits_no_extra_fees() {
+    let mut pool = TestClPool::new(30);
+    provide(&mut pool, -600, 600);
+
+    // Fees earned before the position opens aren't its own.
+    trade(&mut pool);
+    provide(&mut pool, -100, 100);
+    trade(&mut pool);
+
+    let (x, y) = collect_and_withdraw(&mut pool, -100, 100);
+    assert!(x > 0 && y > 0);
+}
+
+#[test]
+fn withdrawing_all_of_a_range_away_from_the_price_credits_no_extra_fees() {
+    let mut pool = TestClPool::new(30);
+    provide(&mut pool, -600, 600);
+    provide(&mut pool, 100, 200);
+
+    // Move the price up through the range and back down below it, so both of
+    // its ticks are crossed twice.
+    let up = pool.swap_instruction(false, u64::MAX / 4, sqrt_price_near_tick(250));
+    pool.process(&[up]).unwrap();
+    let down = pool.swap_instruction(true, u64::MAX / 4, sqrt_price_near_tick(-50));
+    pool.process(&[down]).unwrap();
+    assert!(pool.state().tick_current < 100);
+
+    let (x, y) = collect_and_withdraw(&mut pool, 100, 200);
+    assert!(x > 0 && y > 0);
+}
+
+#[test]
+fn cl_pools_take_their_mints_sorted() {
+    let mut pool = TestClPool::new(30);
+    let seed = pool.state().seed + 1;
+    let other = Pubkey::find_program_address(&[b"cl_pool", &seed.to_le_bytes()], &amm::ID).0;
+
+    let initialize = Instruction {
+        program_id: amm::ID,
+        accounts: accounts::InitializeClPool {
+            signer: pool.user.pubkey(),
+            mint_x: pool.mint_y,
+            mint_y: pool.mint_x,
+            vault_x: ata(&other, &pool.mint_y),
+            vault_y: ata(&other, &pool.mint_x),
+            pool: other,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeClPool {
+            seed,
+            fee: 30,
+            tick_spacing: TICK_SPACING,
+            sqrt_price: 1 << 64,
+        }
+        .data(),
+    };
+    assert!(pool.process(&[initialize]).is_err());
+    assert!(pool.svm.account(&other).is_none());
+}
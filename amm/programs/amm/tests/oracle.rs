@@ -0,0 +1,102 @@
+// The cumulative prices of a pool, and what tokens sent straight to its vaults
+// do to them.
+
+use amm::{
+    accounts, instruction,
+    state::{Config, CurveType, Observation},
+};
+use amm_test::{pool::TestPool, signature::Signer};
+use anchor_lang::{
+    prelude::*, solana_program::instruction::Instruction, AnchorDeserialize, InstructionData,
+};
+use anchor_spl::token::spl_token;
+
+const RESERVE_X: u64 = 1_000_000_000;
+const RESERVE_Y: u64 = 2_000_000_000;
+const ELAPSED: i64 = 1_000;
+
+// Q64.64 prices of X in Y and of Y in X at the initial reserves.
+const PRICE_X: u128 = 2 << 64;
+const PRICE_Y: u128 = 1 << 63;
+
+fn config(pool: &TestPool) -> Config {
+    pool.svm.anchor_account(&pool.config)
+}
+
+fn donate_x(pool: &TestPool, amount: u64) -> Instruction {
+    spl_token::instruction::transfer(
+        &spl_token::ID,
+        &pool.user_x,
+        &pool.vault_x,
+        &pool.user.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap()
+}
+
+fn observe(pool: &mut TestPool) -> Observation {
+    let observe = Instruction {
+        program_id: amm::ID,
+        accounts: accounts::Observe {
+            config: pool.config,
+        }
+        .to_account_metas(None),
+        data: instruction::Observe {}.data(),
+    };
+    let result = pool.svm.process(&[observe], &[]).unwrap();
+    let data = result.metadata.unwrap().return_data.unwrap().data;
+    Observation::deserialize(&mut data.as_slice()).unwrap()
+}
+
+#[test]
+fn donations_do_not_move_the_cumulative_price() {
+    let mut pool = TestPool::new(CurveType::ConstantProduct, 30, 0, RESERVE_X, RESERVE_Y);
+    let before = config(&pool);
+    pool.svm.set_clock(before.last_update_ts + ELAPSED);
+
+    // Tripling the X reserve right before a swap updates the accumulators
+    // doesn't reprice the time since the last update.
+    let donate = donate_x(&pool, 2 * RESERVE_X);
+    let swap = pool.swap_instruction(instruction::Swap {
+        is_x: false,
+        amount: 1_000,
+        min: 0,
+        expiration: i64::MAX,
+    });
+    pool.process(&[donate, swap]).unwrap();
+
+    let after = config(&pool);
+    assert_eq!(
+        after.price_x_cumulative - before.price_x_cumulative,
+        PRICE_X * ELAPSED as u128
+    );
+    assert_eq!(
+        after.price_y_cumulative - before.price_y_cumulative,
+        PRICE_Y * ELAPSED as u128
+    );
+    // From here on the donation is part of the reserves.
+    assert_eq!(after.last_reserve_x, pool.svm.token_balance(&pool.vault_x));
+    assert!(after.last_reserve_x > 2 * RESERVE_X);
+}
+
+#[test]
+fn observe_prices_from_the_recorded_reserves() {
+    let mut pool = TestPool::new(CurveType::ConstantProduct, 30, 0, RESERVE_X, RESERVE_Y);
+    let before = config(&pool);
+    pool.svm.set_clock(before.last_update_ts + ELAPSED);
+
+    let donate = donate_x(&pool, 2 * RESERVE_X);
+    pool.process(&[donate]).unwrap();
+    let observation = observe(&mut pool);
+
+    assert_eq!(
+        observation.price_x_cumulative - before.price_x_cumulative,
+        PRICE_X * ELAPSED as u128
+    );
+    assert_eq!(
+        observation.price_y_cumulative - before.price_y_cumulative,
+        PRICE_Y * ELAPSED as u128
+    );
+    assert_eq!(observation.timestamp, before.last_update_ts + ELAPSED);
+}
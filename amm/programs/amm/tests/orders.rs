@@ -0,0 +1,294 @@
+// Limit orders placed against a pool, filled by a keeper or cancelled.
+
+use amm::{accounts, instruction, state::CurveType};
+use amm_test::{
+    pool::TestPool,
+    signature::{Keypair, Signer},
+    ProcessResult,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, system_program},
+    InstructionData,
+};
+use anchor_spl::{associated_token::spl_associated_token_account, token::spl_token};
+
+const TIP: u64 = 10_000;
+
+struct Order {
+    order: Pubkey,
+    escrow: Pubkey,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+}
+
+fn setup() -> (TestPool, Keypair) {
+    with_keeper(TestPool::new(
+        CurveType::ConstantProduct,
+        30,
+        0,
+        1_000_000_000,
+        1_000_000_000,
+    ))
+}
+
+fn with_keeper(mut pool: TestPool) -> (TestPool, Keypair) {
+    let keeper = Keypair::new();
+    pool.svm.airdrop(&keeper.pubkey(), LAMPORTS_PER_SOL);
+    (pool, keeper)
+}
+
+fn place_order(pool: &mut TestPool, id: u64, is_x: bool, amount: u64, min_out: u64) -> Order {
+    let (mint_in, mint_out) = match is_x {
+        true => (pool.mint_x, pool.mint_y),
+        false => (pool.mint_y, pool.mint_x),
+    };
+    let order = Pubkey::find_program_address(
+        &[
+            b"order",
+            pool.config.as_ref(),
+            pool.user.pubkey().as_ref(),
+            &id.to_le_bytes(),
+        ],
+        &amm::ID,
+    )
+    .0;
+    let escrow = pool.ata(&order, &mint_in);
+
+    let place_order = Instruction {
+        program_id: amm::ID,
+        accounts: accounts::PlaceOrder {
+            owner: pool.user.pubkey(),
+            mint_in,
+            mint_out,
+            owner_in: pool.ata(&pool.user.pubkey(), &mint_in),
+            owner_out: pool.ata(&pool.user.pubkey(), &mint_out),
+            escrow,
+            config: pool.config,
+            order,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::PlaceOrder {
+            id,
+            amount,
+            min_out,
+            tip: TIP,
+        }
+        .data(),
+    };
+    pool.process(&[place_order]).unwrap();
+
+    Order {
+        order,
+        escrow,
+        mint_in,
+        mint_out,
+    }
+}
+
+fn fill_orders(pool: &mut TestPool, keeper: &Keypair, orders: &[&Order]) -> ProcessResult {
+    let mut accounts = accounts::FillOrders {
+        keeper: keeper.pubkey(),
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        config: pool.config,
+        token_program: pool.token_program,
+    }
+    .to_account_metas(None);
+    for order in orders {
+        accounts.extend([
+            AccountMeta::new(order.order, false),
+            AccountMeta::new(order.escrow, false),
+            AccountMeta::new(pool.user.pubkey(), false),
+            AccountMeta::new(pool.ata(&pool.user.pubkey(), &order.mint_out), false),
+        ]);
+    }
+
+    let fill_orders = Instruction {
+        program_id: amm::ID,
+        accounts,
+        data: instruction::FillOrders {}.data(),
+    };
+    pool.svm.process(&[fill_orders], &[keeper])
+}
+
+fn cancel_order(pool: &mut TestPool, order: &Order) -> ProcessResult {
+    let cancel_order = Instruction {
+        program_id: amm::ID,
+        accounts: accounts::CancelOrder {
+            owner: pool.user.pubkey(),
+            mint_in: order.mint_in,
+            owner_in: pool.ata(&pool.user.pubkey(), &order.mint_in),
+            escrow: order.escrow,
+            config: pool.config,
+            order: order.order,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: pool.token_program,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CancelOrder {}.data(),
+    };
+    pool.process(&[cancel_order])
+}
+
+// Moves the price of X up by buying it with Y.
+fn buy_x(pool: &mut TestPool, amount: u64) {
+    let swap = pool.swap_instruction(instruction::Swap {
+        is_x: false,
+        amount,
+        min: 0,
+        expiration: i64::MAX,
+    });
+    pool.process(&[swap]).unwrap();
+}
+
+#[test]
+fn order_fills_once_the_price_crosses() {
+    let (mut pool, keeper) = setup();
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_100_000);
+    assert_eq!(pool.svm.token_balance(&order.escrow), 1_000_000);
+
+    assert!(fill_orders(&mut pool, &keeper, &[&order]).is_err());
+
+    buy_x(&mut pool, 200_000_000);
+    let quote = pool.quote_pool().quote_swap(true, 1_000_000).unwrap();
+    assert!(quote.withdraw >= 1_100_000);
+
+    let (x, y, lp) = pool.balances();
+    let keeper_lamports = pool.svm.lamports(&keeper.pubkey());
+    fill_orders(&mut pool, &keeper, &[&order]).unwrap();
+
+    assert_eq!(pool.balances(), (x, y + quote.withdraw, lp));
+    assert_eq!(pool.svm.lamports(&keeper.pubkey()), keeper_lamports + TIP);
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+    assert_eq!(pool.svm.lamports(&order.escrow), 0);
+}
+
+#[test]
+fn fill_orders_skips_orders_below_their_limit() {
+    let (mut pool, keeper) = setup();
+    let low = place_order(&mut pool, 0, true, 1_000_000, 1_000_000);
+    let high = place_order(&mut pool, 1, true, 1_000_000, 2_000_000);
+
+    buy_x(&mut pool, 200_000_000);
+    fill_orders(&mut pool, &keeper, &[&low, &high]).unwrap();
+
+    assert_eq!(pool.svm.lamports(&low.order), 0);
+    assert_eq!(pool.svm.token_balance(&high.escrow), 1_000_000);
+}
+
+#[test]
+fn cancel_order_refunds_tokens_and_tip() {
+    let (mut pool, _) = setup();
+    let (x, y, lp) = pool.balances();
+    let lamports = pool.svm.lamports(&pool.user.pubkey());
+
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_100_000);
+    assert_eq!(pool.balances(), (x - 1_000_000, y, lp));
+
+    cancel_order(&mut pool, &order).unwrap();
+
+    assert_eq!(pool.balances(), (x, y, lp));
+    assert_eq!(pool.svm.lamports(&pool.user.pubkey()), lamports);
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+}
+
+#[test]
+fn fill_orders_sells_tokens_sent_to_the_escrow() {
+    let (mut pool, keeper) = setup();
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_000_000);
+
+    // Anyone can send tokens to the escrow, which would keep it from closing
+    // if only the order amount was sold.
+    let donate = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &pool.user_x,
+        &order.escrow,
+        &pool.user.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    pool.process(&[donate]).unwrap();
+
+    buy_x(&mut pool, 200_000_000);
+    let quote = pool.quote_pool().quote_swap(true, 1_000_001).unwrap();
+    let (x, y, lp) = pool.balances();
+    fill_orders(&mut pool, &keeper, &[&order]).unwrap();
+
+    assert_eq!(pool.balances(), (x, y + quote.withdraw, lp));
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+    assert_eq!(pool.svm.lamports(&order.escrow), 0);
+}
+
+#[test]
+fn fill_orders_skips_orders_it_already_filled() {
+    let (mut pool, keeper) = setup();
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_000_000);
+
+    buy_x(&mut pool, 200_000_000);
+    let keeper_lamports = pool.svm.lamports(&keeper.pubkey());
+    fill_orders(&mut pool, &keeper, &[&order, &order]).unwrap();
+
+    assert_eq!(pool.svm.lamports(&keeper.pubkey()), keeper_lamports + TIP);
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+}
+
+// 1%, rounded up like Token-2022 does.
+fn transfer_fee(amount: u64) -> u64 {
+    (amount * 100).div_ceil(10_000)
+}
+
+fn setup_with_transfer_fee() -> (TestPool, Keypair) {
+    with_keeper(TestPool::with_transfer_fee(
+        CurveType::ConstantProduct,
+        30,
+        0,
+        1_000_000_000,
+        1_000_000_000,
+        100,
+    ))
+}
+
+#[test]
+fn orders_of_transfer_fee_mints_fill() {
+    let (mut pool, keeper) = setup_with_transfer_fee();
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_000_000);
+    let escrowed = 1_000_000 - transfer_fee(1_000_000);
+    assert_eq!(pool.svm.token_balance(&order.escrow), escrowed);
+
+    buy_x(&mut pool, 200_000_000);
+    let quote = pool
+        .quote_pool()
+        .quote_swap(true, escrowed - transfer_fee(escrowed))
+        .unwrap();
+    let (x, y, lp) = pool.balances();
+    fill_orders(&mut pool, &keeper, &[&order]).unwrap();
+
+    // The fees withheld in the escrow were harvested for it to close.
+    let received = quote.withdraw - transfer_fee(quote.withdraw);
+    assert_eq!(pool.balances(), (x, y + received, lp));
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+    assert_eq!(pool.svm.lamports(&order.escrow), 0);
+}
+
+#[test]
+fn orders_of_transfer_fee_mints_cancel() {
+    let (mut pool, _) = setup_with_transfer_fee();
+    let (x, y, lp) = pool.balances();
+
+    let order = place_order(&mut pool, 0, true, 1_000_000, 1_100_000);
+    cancel_order(&mut pool, &order).unwrap();
+
+    let escrowed = 1_000_000 - transfer_fee(1_000_000);
+    let refund = escrowed - transfer_fee(escrowed);
+    assert_eq!(pool.balances(), (x - 1_000_000 + refund, y, lp));
+    assert_eq!(pool.svm.lamports(&order.order), 0);
+    assert_eq!(pool.svm.lamports(&order.escrow), 0);
+}
@@ -0,0 +1,223 @@
+// Differential tests: every `amm_math` quote must match what the program's
+// instructions actually move, to the token.
+
+use amm::{instruction, state::CurveType};
+use amm_test::pool::TestPool;
+use proptest::prelude::*;
+
+fn curve() -> impl Strategy<Value = CurveType> {
+    prop_oneof![
+        Just(CurveType::ConstantProduct),
+        (1u64..10_000).prop_map(|amp| CurveType::StableSwap { amp }),
+    ]
+}
+
+fn reserve() -> impl Strategy<Value = u64> {
+    1_000_000u64..1_000_000_000_000
+}
+
+// Assert that `actual` moved exactly `expected` in (positive) or out (negative)
+// of the user's accounts.
+fn assert_moved(before: (u64, u64, u64), after: (u64, u64, u64), expected: (i128, i128, i128)) {
+    let delta = (
+        after.0 as i128 - before.0 as i128,
+        after.1 as i128 - before.1 as i128,
+        after.2 as i128 - before.2 as i128,
+    );
+    assert_eq!(delta, expected);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn swap_matches_quote(
+        curve in curve(),
+        fee in 0u16..1_000,
+        protocol_fee in 0u16..=10_000,
+        x in reserve(),
+        y in reserve(),
+        is_x: bool,
+        share in 1u64..1_000,
+    ) {
+        let mut pool = TestPool::new(curve, fee, protocol_fee, x, y);
+        let quote_pool = pool.quote_pool();
+        let amount = (x.min(y) as u128 * share as u128 / 1_000).max(1) as u64;
+        let quote = quote_pool.quote_swap(is_x, amount).unwrap();
+        prop_assume!(quote.withdraw > 0);
+
+        // Asking for one more than the quote must fail.
+        let too_much = pool.swap_instruction(instruction::Swap {
+            is_x,
+            amount,
+            min: quote.withdraw + 1,
+            expiration: i64::MAX,
+        });
+        prop_assert!(pool.process(&[too_much]).is_err());
+
+        let before = pool.balances();
+        let swap = pool.swap_instruction(instruction::Swap {
+            is_x,
+            amount,
+            min: quote.withdraw,
+            expiration: i64::MAX,
+        });
+        pool.process(&[swap]).unwrap();
+
+        let (amount_in, amount_out) = (quote.deposit as i128, quote.withdraw as i128);
+        let expected = match is_x {
+            true => (-amount_in, amount_out, 0),
+            false => (amount_out, -amount_in, 0),
+        };
+        assert_moved(before, pool.balances(), expected);
+        prop_assert_eq!(pool.quote_pool(), quote_pool.after_swap(is_x, &quote).unwrap());
+    }
+
+    #[test]
+    fn swap_exact_out_matches_quote(
+        curve in curve(),
+        fee in 0u16..1_000,
+        protocol_fee in 0u16..=10_000,
+        x in reserve(),
+        y in reserve(),
+        is_x: bool,
+        share in 1u64..900,
+    ) {
+        let mut pool = TestPool::new(curve, fee, protocol_fee, x, y);
+        let quote_pool = pool.quote_pool();
+        let reserve_out = match is_x {
+            true => y,
+            false => x,
+        };
+        let amount_out = (reserve_out as u128 * share as u128 / 1_000).max(1) as u64;
+        let quote = quote_pool.quote_swap_exact_out(is_x, amount_out).unwrap();
+
+        let too_little = pool.swap_instruction(instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_in: quote.deposit - 1,
+            expiration: i64::MAX,
+        });
+        prop_assert!(pool.process(&[too_little]).is_err());
+
+        let before = pool.balances();
+        let swap = pool.swap_instruction(instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_in: quote.deposit,
+            expiration: i64::MAX,
+        });
+        pool.process(&[swap]).unwrap();
+
+        let (amount_in, amount_out) = (quote.deposit as i128, quote.withdraw as i128);
+        let expected = match is_x {
+            true => (-amount_in, amount_out, 0),
+            false => (amount_out, -amount_in, 0),
+        };
+        assert_moved(before, pool.balances(), expected);
+        prop_assert_eq!(pool.quote_pool(), quote_pool.after_swap(is_x, &quote).unwrap());
+    }
+
+    #[test]
+    fn deposit_and_withdraw_match_quotes(
+        curve in curve(),
+        x in reserve(),
+        y in reserve(),
+        share in 1u64..1_000,
+    ) {
+        let mut pool = TestPool::new(curve, 30, 0, x, y);
+        let amount = pool.quote_pool().supply / 1_000 * share;
+        prop_assume!(amount > 0);
+
+        let quote = pool.quote_pool().quote_deposit(amount).unwrap();
+        let before = pool.balances();
+        let deposit = pool.deposit_instruction(instruction::Deposit {
+            amount,
+            max_x: quote.x,
+            max_y: quote.y,
+        });
+        pool.process(&[deposit]).unwrap();
+        assert_moved(
+            before,
+            pool.balances(),
+            (-(quote.x as i128), -(quote.y as i128), amount as i128),
+        );
+
+        let quote = pool.quote_pool().quote_withdraw(amount).unwrap();
+        let before = pool.balances();
+        let withdraw = pool.withdraw_instruction(instruction::Withdraw {
+            amount,
+            min_x: quote.x,
+            min_y: quote.y,
+        });
+        pool.process(&[withdraw]).unwrap();
+        assert_moved(
+            before,
+            pool.balances(),
+            (quote.x as i128, quote.y as i128, -(amount as i128)),
+        );
+    }
+
+    #[test]
+    fn single_sided_deposit_and_withdraw_match_quotes(
+        curve in curve(),
+        fee in 0u16..1_000,
+        protocol_fee in 0u16..=10_000,
+        x in reserve(),
+        y in reserve(),
+        is_x: bool,
+        share in 1u64..500,
+    ) {
+        let mut pool = TestPool::new(curve, fee, protocol_fee, x, y);
+        let amount_in = (x.min(y) as u128 * share as u128 / 1_000).max(1) as u64;
+
+        let (_, lp) = pool.quote_pool().quote_deposit_single(is_x, amount_in).unwrap();
+        prop_assume!(lp > 0);
+
+        let too_much = pool.deposit_instruction(instruction::DepositSingle {
+            is_x,
+            amount_in,
+            min_lp: lp + 1,
+        });
+        prop_assert!(pool.process(&[too_much]).is_err());
+
+        let before = pool.balances();
+        let deposit = pool.deposit_instruction(instruction::DepositSingle {
+            is_x,
+            amount_in,
+            min_lp: lp,
+        });
+        pool.process(&[deposit]).unwrap();
+        let expected = match is_x {
+            true => (-(amount_in as i128), 0, lp as i128),
+            false => (0, -(amount_in as i128), lp as i128),
+        };
+        assert_moved(before, pool.balances(), expected);
+
+        let (_, out) = pool.quote_pool().quote_withdraw_single(is_x, lp).unwrap();
+        let before = pool.balances();
+        let withdraw = pool.withdraw_instruction(instruction::WithdrawSingle {
+            is_x,
+            lp_amount: lp,
+            min_out: out,
+        });
+        pool.process(&[withdraw]).unwrap();
+        let expected = match is_x {
+            true => (out as i128, 0, -(lp as i128)),
+            false => (0, out as i128, -(lp as i128)),
+        };
+        assert_moved(before, pool.balances(), expected);
+    }
+}
+
+#[test]
+fn initial_deposit_matches_quote() {
+    let pool = TestPool::new(CurveType::ConstantProduct, 30, 0, 4_000_000, 1_000_000);
+    let lp = amm_math::quote_initial_deposit(4_000_000, 1_000_000).unwrap();
+
+    assert_eq!(pool.svm.token_balance(&pool.user_lp), lp);
+    assert_eq!(
+        pool.svm.token_balance(&pool.vault_lp),
+        amm_math::MINIMUM_LIQUIDITY
+    );
+}
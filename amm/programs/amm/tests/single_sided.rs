@@ -0,0 +1,140 @@
+use amm::{instruction, state::CurveType};
+use amm_test::pool::TestPool;
+
+const RESERVE: u64 = 1_000_000_000;
+
+const CURVES: [CurveType; 2] = [
+    CurveType::ConstantProduct,
+    CurveType::StableSwap { amp: 100 },
+];
+
+fn supply(pool: &TestPool) -> u64 {
+    pool.svm.mint_supply(&pool.mint_lp)
+}
+
+fn deposit_single(pool: &mut TestPool, is_x: bool, amount_in: u64, min_lp: u64) -> bool {
+    let deposit = pool.deposit_instruction(instruction::DepositSingle {
+        is_x,
+        amount_in,
+        min_lp,
+    });
+    pool.process(&[deposit]).is_ok()
+}
+
+#[test]
+fn deposit_single_mints_about_half_the_value_of_the_input() {
+    for curve in CURVES {
+        for is_x in [true, false] {
+            let amount_in = RESERVE / 100;
+
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, RESERVE);
+            let supply_before = supply(&pool);
+            let before = pool.balances();
+            assert!(deposit_single(&mut pool, is_x, amount_in, 0));
+            let after = pool.balances();
+
+            // Only the input token leaves the user.
+            let (spent, kept) = match is_x {
+                true => (before.0 - after.0, before.1 - after.1),
+                false => (before.1 - after.1, before.0 - after.0),
+            };
+            assert_eq!((spent, kept), (amount_in, 0));
+
+            // The input is worth a share of a balanced pool of twice its size,
+            // less the fee on the part that is swapped.
+            let lp = after.2 - before.2;
+            let fair = (supply_before as u128 * amount_in as u128 / (2 * RESERVE) as u128) as u64;
+            assert!(lp <= fair);
+            assert!(lp >= fair - fair / 100);
+
+            // Asking for one more LP token must fail.
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, RESERVE);
+            assert!(!deposit_single(&mut pool, is_x, amount_in, lp + 1));
+        }
+    }
+}
+
+#[test]
+fn deposit_single_into_an_imbalanced_pool() {
+    for curve in CURVES {
+        for is_x in [true, false] {
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, RESERVE * 20);
+            let before = pool.balances();
+            assert!(deposit_single(&mut pool, is_x, RESERVE / 10, 0));
+            assert!(pool.balances().2 > before.2);
+        }
+    }
+}
+
+#[test]
+fn withdraw_single_pays_out_one_token() {
+    for curve in CURVES {
+        for is_x in [true, false] {
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, RESERVE);
+            let lp_amount = pool.balances().2 / 100;
+            let supply_before = supply(&pool);
+            let before = pool.balances();
+
+            let withdraw = pool.withdraw_instruction(instruction::WithdrawSingle {
+                is_x,
+                lp_amount,
+                min_out: 0,
+            });
+            pool.process(&[withdraw]).unwrap();
+            let after = pool.balances();
+
+            let (received, other) = match is_x {
+                true => (after.0 - before.0, after.1 - before.1),
+                false => (after.1 - before.1, after.0 - before.0),
+            };
+            assert_eq!(other, 0);
+            assert_eq!(before.2 - after.2, lp_amount);
+
+            // Both sides of the share, less the fee on swapping one of them.
+            let fair = (2 * RESERVE as u128 * lp_amount as u128 / supply_before as u128) as u64;
+            assert!(received <= fair);
+            assert!(received >= fair - fair / 100);
+        }
+    }
+}
+
+#[test]
+fn single_sided_round_trip_does_not_profit() {
+    for curve in CURVES {
+        for is_x in [true, false] {
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, RESERVE * 3);
+            let before = pool.balances();
+            assert!(deposit_single(&mut pool, is_x, RESERVE / 7, 0));
+            let lp_amount = pool.balances().2 - before.2;
+
+            let withdraw = pool.withdraw_instruction(instruction::WithdrawSingle {
+                is_x,
+                lp_amount,
+                min_out: 0,
+            });
+            pool.process(&[withdraw]).unwrap();
+
+            let after = pool.balances();
+            assert!(after.0 <= before.0 && after.1 <= before.1);
+        }
+    }
+}
+
+#[test]
+#[ignore = "compute units are only metered for the built program: run with cargo test-sbf -- --ignored"]
+fn deposit_single_fits_in_the_default_compute_budget() {
+    for curve in CURVES {
+        for (y, amount_in) in [(RESERVE, RESERVE / 100), (RESERVE * 50, RESERVE * 3)] {
+            let mut pool = TestPool::new(curve, 30, 0, RESERVE, y);
+            let deposit = pool.deposit_instruction(instruction::DepositSingle {
+                is_x: true,
+                amount_in,
+                min_lp: 0,
+            });
+            let result = pool.process(&[deposit]).unwrap();
+
+            let consumed = result.metadata.unwrap().compute_units_consumed;
+            assert!(consumed < 200_000, "{consumed} compute units");
+        }
+    }
+}
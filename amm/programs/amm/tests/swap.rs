@@ -0,0 +1,85 @@
+use amm::{instruction, state::CurveType};
+use amm_test::pool::TestPool;
+
+const RESERVE_X: u64 = 1_000_000_000;
+const RESERVE_Y: u64 = 2_000_000_000;
+
+// Every curve, swapping either way.
+fn cases() -> impl Iterator<Item = (CurveType, bool)> {
+    [
+        CurveType::ConstantProduct,
+        CurveType::StableSwap { amp: 100 },
+    ]
+    .into_iter()
+    .flat_map(|curve| [(curve, true), (curve, false)])
+}
+
+// The user's and the vaults' balances of X and Y.
+fn balances(pool: &TestPool) -> [i128; 4] {
+    [pool.user_x, pool.user_y, pool.vault_x, pool.vault_y]
+        .map(|account| pool.svm.token_balance(&account) as i128)
+}
+
+// How much of the input and output token left the user and reached them,
+// checking that the vaults moved the other way.
+fn swapped(is_x: bool, before: [i128; 4], after: [i128; 4]) -> (u64, u64) {
+    let delta: Vec<i128> = after.iter().zip(before).map(|(a, b)| a - b).collect();
+    assert_eq!(delta[0], -delta[2]);
+    assert_eq!(delta[1], -delta[3]);
+
+    let (amount_in, amount_out) = match is_x {
+        true => (-delta[0], delta[1]),
+        false => (-delta[1], delta[0]),
+    };
+    (amount_in as u64, amount_out as u64)
+}
+
+#[test]
+fn swap_pays_out_the_other_token() {
+    for (curve, is_x) in cases() {
+        let mut pool = TestPool::new(curve, 30, 0, RESERVE_X, RESERVE_Y);
+        let before = balances(&pool);
+        let swap = pool.swap_instruction(instruction::Swap {
+            is_x,
+            amount: 1_000_000,
+            min: 1,
+            expiration: i64::MAX,
+        });
+        pool.process(&[swap]).unwrap();
+
+        let (amount_in, amount_out) = swapped(is_x, before, balances(&pool));
+        assert_eq!(amount_in, 1_000_000);
+        assert!(amount_out > 0);
+    }
+}
+
+#[test]
+fn swap_exact_out_pays_out_the_requested_amount() {
+    for (curve, is_x) in cases() {
+        let amount_out = 1_000_000;
+
+        let mut pool = TestPool::new(curve, 30, 0, RESERVE_X, RESERVE_Y);
+        let before = balances(&pool);
+        let swap = pool.swap_instruction(instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_in: u64::MAX,
+            expiration: i64::MAX,
+        });
+        pool.process(&[swap]).unwrap();
+
+        let (amount_in, received) = swapped(is_x, before, balances(&pool));
+        assert!(amount_in > 0);
+        assert!(received >= amount_out);
+
+        // The same swap capped one below what it cost must fail.
+        let mut pool = TestPool::new(curve, 30, 0, RESERVE_X, RESERVE_Y);
+        let too_little = pool.swap_instruction(instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_in: amount_in - 1,
+            expiration: i64::MAX,
+        });
+        assert!(pool.process(&[too_little]).is_err());
+    }
+}
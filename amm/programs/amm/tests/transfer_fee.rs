@@ -0,0 +1,43 @@
+// Pools of Token-2022 mints that charge a transfer fee, whose minimums apply
+// to what the user receives.
+
+use amm::{instruction, state::CurveType};
+use amm_test::pool::TestPool;
+
+const RESERVE: u64 = 1_000_000_000;
+
+// 1%, rounded up like Token-2022 does.
+fn transfer_fee(amount: u64) -> u64 {
+    (amount * 100).div_ceil(10_000)
+}
+
+#[test]
+fn withdraw_minimums_are_net_of_the_transfer_fee() {
+    let mut pool =
+        TestPool::with_transfer_fee(CurveType::ConstantProduct, 30, 0, RESERVE, RESERVE, 100);
+    let amount = pool.balances().2 / 10;
+    let quote = pool.quote_pool().quote_withdraw(amount).unwrap();
+    let (x, y) = (
+        quote.x - transfer_fee(quote.x),
+        quote.y - transfer_fee(quote.y),
+    );
+
+    let gross = pool.withdraw_instruction(instruction::Withdraw {
+        amount,
+        min_x: quote.x,
+        min_y: y,
+    });
+    assert!(pool.process(&[gross]).is_err());
+
+    let before = pool.balances();
+    let net = pool.withdraw_instruction(instruction::Withdraw {
+        amount,
+        min_x: x,
+        min_y: y,
+    });
+    pool.process(&[net]).unwrap();
+    assert_eq!(
+        pool.balances(),
+        (before.0 + x, before.1 + y, before.2 - amount)
+    );
+}
@@ -0,0 +1,100 @@
+// Stands in for the Bubblegum program, whose binary the tests don't have, along
+// with the account compression program it keeps its trees in. It transfers
+// compressed NFTs: it checks the proof of the leaf against the root like the
+// real program and replaces the leaf with one of the new owner. It keeps the
+// concurrent Merkle tree itself, as the whole data of a tree account it owns,
+// without the header or canopy of account compression, and logs nothing to the
+// noop program. Its other instructions fail.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{account_info::next_account_info, entrypoint::ProgramResult},
+};
+use mpl_bubblegum::{
+    instructions::TransferInstructionArgs,
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    types::LeafSchema,
+    utils::get_asset_id,
+};
+use spl_concurrent_merkle_tree::concurrent_merkle_tree::ConcurrentMerkleTree;
+
+pub const MAX_DEPTH: usize = 5;
+pub const MAX_BUFFER_SIZE: usize = 8;
+
+pub type Tree = ConcurrentMerkleTree<MAX_DEPTH, MAX_BUFFER_SIZE>;
+
+const TRANSFER: [u8; 8] = [163, 52, 200, 231, 140, 3, 69, 186];
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match data.strip_prefix(&TRANSFER) {
+        Some(args) => transfer(
+            program_id,
+            accounts,
+            &TransferInstructionArgs::try_from_slice(args)?,
+        ),
+        None => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// The owner or delegate of the leaf has to sign, and the new owner becomes its
+// delegate as well.
+fn transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: &TransferInstructionArgs,
+) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let tree_config = next_account_info(accounts)?;
+    let leaf_owner = next_account_info(accounts)?;
+    let leaf_delegate = next_account_info(accounts)?;
+    let new_leaf_owner = next_account_info(accounts)?;
+    let merkle_tree = next_account_info(accounts)?;
+    let log_wrapper = next_account_info(accounts)?;
+    let compression_program = next_account_info(accounts)?;
+    let _system_program = next_account_info(accounts)?;
+    let proof = accounts.map(|node| node.key.to_bytes()).collect::<Vec<_>>();
+    if !leaf_owner.is_signer && !leaf_delegate.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *tree_config.key != Pubkey::find_program_address(&[merkle_tree.key.as_ref()], program_id).0
+        || *log_wrapper.key != SPL_NOOP_ID
+        || *compression_program.key != SPL_ACCOUNT_COMPRESSION_ID
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if merkle_tree.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let leaf = |owner: &Pubkey, delegate: &Pubkey| {
+        LeafSchema::V1 {
+            id: get_asset_id(merkle_tree.key, args.nonce),
+            owner: *owner,
+            delegate: *delegate,
+            nonce: args.nonce,
+            data_hash: args.data_hash,
+            creator_hash: args.creator_hash,
+        }
+        .hash()
+    };
+
+    // Account data isn't aligned for the tree, so it's copied out and back.
+    let mut data = merkle_tree.try_borrow_mut_data()?;
+    let mut tree: Tree =
+        bytemuck::try_pod_read_unaligned(&data).map_err(|_| ProgramError::InvalidAccountData)?;
+    tree.set_leaf(
+        args.root,
+        leaf(leaf_owner.key, leaf_delegate.key),
+        leaf(new_leaf_owner.key, new_leaf_owner.key),
+        &proof,
+        args.index,
+    )
+    .map_err(|_| ProgramError::InvalidArgument)?;
+    data.copy_from_slice(bytemuck::bytes_of(&tree));
+
+    Ok(())
+}
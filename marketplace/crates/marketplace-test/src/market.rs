@@ -0,0 +1,1160 @@
+// A marketplace with NFTs in a verified collection, and builders for the
+// instructions tests run against it.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::Instruction, keccak, program_option::COption, program_pack::Pack,
+        system_program, sysvar,
+    },
+    InstructionData,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id, spl_associated_token_account,
+    },
+    metadata::mpl_token_metadata::{
+        self,
+        accounts::{MasterEdition, Metadata, TokenRecord},
+        types::{Collection, Key, TokenStandard},
+    },
+    token::spl_token,
+};
+use marketplace::{
+    accounts,
+    cnft::{CompressedNft, LeafCreator},
+    instruction,
+    state::{Listing, OfferTarget, RoyaltyPolicy},
+};
+use mpl_bubblegum::{
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    types::{
+        Collection as BubblegumCollection, Creator as BubblegumCreator, MetadataArgs,
+        TokenProgramVersion,
+    },
+};
+use solana_sdk::{
+    account::Account,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+use crate::{
+    bubblegum::Tree,
+    tree::{merkle_tree, TestTree},
+    ProcessResult, Svm,
+};
+
+pub struct TestMarketplace {
+    pub svm: Svm,
+    pub admin: Keypair,
+    pub name: String,
+    pub marketplace: Pubkey,
+    pub treasury: Pubkey,
+    pub rewards_mint: Pubkey,
+    pub collection_mint: Pubkey,
+}
+
+// A mint of supply one with its metadata and master edition.
+pub struct Nft {
+    pub mint: Pubkey,
+    pub metadata: Pubkey,
+    pub master_edition: Pubkey,
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token::ID)
+}
+
+// The Token Metadata record of a programmable NFT in `token_account`.
+pub fn token_record(nft: &Nft, token_account: &Pubkey) -> Pubkey {
+    TokenRecord::find_pda(&nft.mint, token_account).0
+}
+
+// Instructions that pay royalties take the creator accounts last.
+fn with_creators(instruction: &mut Instruction, creators: &[Pubkey]) {
+    instruction.accounts.extend(
+        creators
+            .iter()
+            .map(|creator| AccountMeta::new(*creator, false)),
+    );
+}
+
+impl TestMarketplace {
+    pub fn new(fee: u16) -> TestMarketplace {
+        let mut svm = Svm::new();
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), 1_000 * LAMPORTS_PER_SOL);
+
+        // Marketplaces that share a bank (see `Svm`) need names of their own.
+        let name = format!("market-{}", &admin.pubkey().to_string()[..8]);
+        let marketplace =
+            Pubkey::find_program_address(&[b"marketplace", name.as_bytes()], &marketplace::ID).0;
+        let treasury =
+            Pubkey::find_program_address(&[b"treasury", marketplace.as_ref()], &marketplace::ID).0;
+        let rewards_mint =
+            Pubkey::find_program_address(&[b"rewards", marketplace.as_ref()], &marketplace::ID).0;
+        let collection_mint = svm.create_mint(&spl_token::ID, 0);
+
+        let mut market = TestMarketplace {
+            svm,
+            admin,
+            name,
+            marketplace,
+            treasury,
+            rewards_mint,
+            collection_mint,
+        };
+
+        let initialize = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::Initialize {
+                admin: market.admin.pubkey(),
+                marketplace,
+                treasury,
+                rewards_mint,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Initialize {
+                name: market.name.clone(),
+                fee,
+            }
+            .data(),
+        };
+        let admin = market.admin.insecure_clone();
+        market.process(&[initialize], &[&admin]).unwrap();
+
+        market
+    }
+
+    pub fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> ProcessResult {
+        self.svm.process(instructions, signers)
+    }
+
+    // A funded account to act as a maker or taker.
+    pub fn user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 100 * LAMPORTS_PER_SOL);
+        user
+    }
+
+    pub fn offer(&self, bidder: &Pubkey, target: &OfferTarget) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"offer",
+                self.marketplace.as_ref(),
+                bidder.as_ref(),
+                target.key().as_ref(),
+            ],
+            &marketplace::ID,
+        )
+        .0
+    }
+
+    pub fn listing(&self, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"listing", self.marketplace.as_ref(), mint.as_ref()],
+            &marketplace::ID,
+        )
+        .0
+    }
+
+    // Mint an NFT to `owner`, verified in the marketplace's collection.
+    pub fn mint_nft(&mut self, owner: &Pubkey) -> Nft {
+        let mint = self.svm.create_mint(&spl_token::ID, 0);
+        let owner_ata = self.svm.create_ata(owner, &mint, &spl_token::ID);
+        self.svm.mint_to(&mint, &owner_ata, 1);
+
+        let nft = Nft {
+            mint,
+            metadata: Metadata::find_pda(&mint).0,
+            master_edition: MasterEdition::find_pda(&mint).0,
+        };
+        let metadata = self.metadata(&nft);
+        self.set_metadata(&nft, &metadata);
+
+        let master_edition = MasterEdition {
+            key: Key::MasterEditionV2,
+            supply: 0,
+            max_supply: Some(0),
+        };
+        self.set_metadata_account(&nft.master_edition, master_edition.try_to_vec().unwrap());
+
+        // Creating the master edition hands it the mint and freeze authorities.
+        let mut account = self.svm.account(&mint).unwrap();
+        let mut state = spl_token::state::Mint::unpack(&account.data).unwrap();
+        state.mint_authority = COption::Some(nft.master_edition);
+        state.freeze_authority = COption::Some(nft.master_edition);
+        spl_token::state::Mint::pack(state, &mut account.data).unwrap();
+        self.svm.set_account(&mint, &account);
+
+        nft
+    }
+
+    // Mint a programmable NFT to `owner`. Like every pNFT, it stays frozen in its
+    // token account.
+    pub fn mint_pnft(&mut self, owner: &Pubkey) -> Nft {
+        let nft = self.mint_nft(owner);
+        let mut metadata = self.metadata(&nft);
+        metadata.token_standard = Some(TokenStandard::ProgrammableNonFungible);
+        self.set_metadata(&nft, &metadata);
+
+        let owner_ata = ata(owner, &nft.mint);
+        let mut account = self.svm.account(&owner_ata).unwrap();
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.state = spl_token::state::AccountState::Frozen;
+        spl_token::state::Account::pack(state, &mut account.data).unwrap();
+        self.svm.set_account(&owner_ata, &account);
+
+        nft
+    }
+
+    // The metadata `mint_nft` gives an NFT, to change and write back with
+    // `set_metadata`.
+    pub fn metadata(&self, nft: &Nft) -> Metadata {
+        Metadata {
+            key: Key::MetadataV1,
+            update_authority: self.admin.pubkey(),
+            mint: nft.mint,
+            name: "NFT".to_string(),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(TokenStandard::NonFungible),
+            collection: Some(Collection {
+                verified: true,
+                key: self.collection_mint,
+            }),
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        }
+    }
+
+    pub fn set_metadata(&mut self, nft: &Nft, metadata: &Metadata) {
+        self.set_metadata_account(&nft.metadata, metadata.try_to_vec().unwrap());
+    }
+
+    // Token Metadata accounts are written directly, as the program would leave them.
+    fn set_metadata_account(&mut self, key: &Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: mpl_token_metadata::ID,
+            ..Account::default()
+        };
+        self.svm.set_account(key, &account);
+    }
+
+    fn list_accounts(&self, maker: &Pubkey, nft: &Nft) -> accounts::List {
+        let listing = self.listing(&nft.mint);
+        accounts::List {
+            maker: *maker,
+            marketplace: self.marketplace,
+            maker_mint: nft.mint,
+            maker_ata: ata(maker, &nft.mint),
+            vault: ata(&listing, &nft.mint),
+            listing,
+            collection_mint: self.collection_mint,
+            metadata: nft.metadata,
+            master_edition: nft.master_edition,
+            maker_token_record: None,
+            vault_token_record: None,
+            authorization_rules: None,
+            authorization_rules_program: None,
+            sysvar_instructions: None,
+            metadata_program: mpl_token_metadata::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub fn list_instruction(&self, maker: &Pubkey, nft: &Nft, price: u64) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: self.list_accounts(maker, nft).to_account_metas(None),
+            data: instruction::List {
+                price,
+                expires_at: None,
+                payment_mint: None,
+            }
+            .data(),
+        }
+    }
+
+    // Lists a programmable NFT, with the token records of the maker and the
+    // vault.
+    pub fn list_pnft_instruction(&self, maker: &Pubkey, nft: &Nft, price: u64) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        Instruction {
+            accounts: accounts::List {
+                maker_token_record: Some(token_record(nft, &ata(maker, &nft.mint))),
+                vault_token_record: Some(token_record(nft, &ata(&listing, &nft.mint))),
+                sysvar_instructions: Some(sysvar::instructions::ID),
+                ..self.list_accounts(maker, nft)
+            }
+            .to_account_metas(None),
+            ..self.list_instruction(maker, nft, price)
+        }
+    }
+
+    fn delist_accounts(&self, maker: &Pubkey, nft: &Nft) -> accounts::Delist {
+        let listing = self.listing(&nft.mint);
+        accounts::Delist {
+            maker: *maker,
+            marketplace: self.marketplace,
+            maker_mint: nft.mint,
+            maker_ata: ata(maker, &nft.mint),
+            vault: Some(ata(&listing, &nft.mint)),
+            listing,
+            master_edition: None,
+            metadata_program: None,
+            metadata: None,
+            maker_token_record: None,
+            vault_token_record: None,
+            authorization_rules: None,
+            authorization_rules_program: None,
+            sysvar_instructions: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    pub fn delist_instruction(&self, maker: &Pubkey, nft: &Nft) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: self.delist_accounts(maker, nft).to_account_metas(None),
+            data: instruction::Delist {}.data(),
+        }
+    }
+
+    pub fn delist_pnft_instruction(&self, maker: &Pubkey, nft: &Nft) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        Instruction {
+            accounts: accounts::Delist {
+                master_edition: Some(nft.master_edition),
+                metadata_program: Some(mpl_token_metadata::ID),
+                metadata: Some(nft.metadata),
+                maker_token_record: Some(token_record(nft, &ata(maker, &nft.mint))),
+                vault_token_record: Some(token_record(nft, &ata(&listing, &nft.mint))),
+                sysvar_instructions: Some(sysvar::instructions::ID),
+                ..self.delist_accounts(maker, nft)
+            }
+            .to_account_metas(None),
+            ..self.delist_instruction(maker, nft)
+        }
+    }
+
+    // Prices the listing in `payment_mint` instead of SOL.
+    pub fn list_for_tokens_instruction(
+        &self,
+        maker: &Pubkey,
+        nft: &Nft,
+        price: u64,
+        payment_mint: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            data: instruction::List {
+                price,
+                expires_at: None,
+                payment_mint: Some(*payment_mint),
+            }
+            .data(),
+            ..self.list_instruction(maker, nft, price)
+        }
+    }
+
+    fn purchase_accounts(&self, taker: &Pubkey, maker: &Pubkey, nft: &Nft) -> accounts::Purchase {
+        let listing = self.listing(&nft.mint);
+        accounts::Purchase {
+            taker: *taker,
+            maker: *maker,
+            marketplace: self.marketplace,
+            maker_mint: nft.mint,
+            taker_ata: ata(taker, &nft.mint),
+            vault: Some(ata(&listing, &nft.mint)),
+            maker_ata: None,
+            listing,
+            treasury: self.treasury,
+            rewards_mint: self.rewards_mint,
+            rewards_ata: ata(taker, &self.rewards_mint),
+            metadata: nft.metadata,
+            master_edition: None,
+            vault_token_record: None,
+            taker_token_record: None,
+            authorization_rules: None,
+            authorization_rules_program: None,
+            sysvar_instructions: None,
+            payment_mint: None,
+            taker_payment_ata: None,
+            maker_payment_ata: None,
+            treasury_payment_ata: None,
+            payment_token_program: None,
+            metadata_program: mpl_token_metadata::ID,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+    }
+
+    // The NFT stays frozen in the maker's token account, with the listing as its
+    // delegate.
+    pub fn list_escrowless_instruction(
+        &self,
+        maker: &Pubkey,
+        nft: &Nft,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::ListEscrowless {
+                maker: *maker,
+                marketplace: self.marketplace,
+                maker_mint: nft.mint,
+                maker_ata: ata(maker, &nft.mint),
+                listing: self.listing(&nft.mint),
+                collection_mint: self.collection_mint,
+                metadata: nft.metadata,
+                master_edition: nft.master_edition,
+                metadata_program: mpl_token_metadata::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ListEscrowless {
+                price,
+                expires_at,
+                payment_mint: None,
+            }
+            .data(),
+        }
+    }
+
+    pub fn delist_escrowless_instruction(&self, maker: &Pubkey, nft: &Nft) -> Instruction {
+        Instruction {
+            accounts: accounts::Delist {
+                vault: None,
+                master_edition: Some(nft.master_edition),
+                metadata_program: Some(mpl_token_metadata::ID),
+                ..self.delist_accounts(maker, nft)
+            }
+            .to_account_metas(None),
+            ..self.delist_instruction(maker, nft)
+        }
+    }
+
+    pub fn expire_listing_instruction(
+        &self,
+        payer: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+        escrowless: bool,
+    ) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::ExpireListing {
+                payer: *payer,
+                maker: *maker,
+                marketplace: self.marketplace,
+                maker_mint: nft.mint,
+                maker_ata: ata(maker, &nft.mint),
+                vault: (!escrowless).then(|| ata(&listing, &nft.mint)),
+                listing,
+                master_edition: escrowless.then_some(nft.master_edition),
+                metadata_program: escrowless.then_some(mpl_token_metadata::ID),
+                metadata: None,
+                maker_token_record: None,
+                vault_token_record: None,
+                authorization_rules: None,
+                authorization_rules_program: None,
+                sysvar_instructions: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ExpireListing {}.data(),
+        }
+    }
+
+    pub fn expire_listing_pnft_instruction(
+        &self,
+        payer: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+    ) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        let maker_ata = ata(maker, &nft.mint);
+        let vault = ata(&listing, &nft.mint);
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::ExpireListing {
+                payer: *payer,
+                maker: *maker,
+                marketplace: self.marketplace,
+                maker_mint: nft.mint,
+                maker_ata,
+                vault: Some(vault),
+                listing,
+                master_edition: Some(nft.master_edition),
+                metadata_program: Some(mpl_token_metadata::ID),
+                metadata: Some(nft.metadata),
+                maker_token_record: Some(token_record(nft, &maker_ata)),
+                vault_token_record: Some(token_record(nft, &vault)),
+                authorization_rules: None,
+                authorization_rules_program: None,
+                sysvar_instructions: Some(sysvar::instructions::ID),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ExpireListing {}.data(),
+        }
+    }
+
+    // The price a taker's client would show for the listing of `mint`, and so
+    // the most its purchases pay. Without a price to buy at, the program reports
+    // why.
+    fn quote(&self, mint: &Pubkey) -> u64 {
+        let Some(account) = self.svm.account(&self.listing(mint)) else {
+            return u64::MAX;
+        };
+        let listing = Listing::try_deserialize(&mut account.data.as_slice()).unwrap();
+        listing
+            .current_price(self.svm.clock().unix_timestamp)
+            .unwrap_or(u64::MAX)
+    }
+
+    pub fn update_listing_instruction(
+        &self,
+        maker: &Pubkey,
+        mint: &Pubkey,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::UpdateListing {
+                maker: *maker,
+                marketplace: self.marketplace,
+                listing: self.listing(mint),
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateListing { price, expires_at }.data(),
+        }
+    }
+
+    // `creators` are the accounts of the verified creators the purchase pays
+    // royalties to, in the order of the metadata. It pays at most the current
+    // price of the listing.
+    pub fn purchase_instruction(
+        &self,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: self
+                .purchase_accounts(taker, maker, nft)
+                .to_account_metas(None),
+            data: instruction::Purchase {
+                max_price: self.quote(&nft.mint),
+            }
+            .data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    // Purchase of an escrowless listing, out of the maker's token account.
+    pub fn purchase_escrowless_instruction(
+        &self,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::Purchase {
+                vault: None,
+                maker_ata: Some(ata(maker, &nft.mint)),
+                master_edition: Some(nft.master_edition),
+                ..self.purchase_accounts(taker, maker, nft)
+            }
+            .to_account_metas(None),
+            data: instruction::Purchase {
+                max_price: self.quote(&nft.mint),
+            }
+            .data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    pub fn purchase_pnft_instruction(
+        &self,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::Purchase {
+                master_edition: Some(nft.master_edition),
+                vault_token_record: Some(token_record(nft, &ata(&listing, &nft.mint))),
+                taker_token_record: Some(token_record(nft, &ata(taker, &nft.mint))),
+                sysvar_instructions: Some(sysvar::instructions::ID),
+                ..self.purchase_accounts(taker, maker, nft)
+            }
+            .to_account_metas(None),
+            data: instruction::Purchase {
+                max_price: self.quote(&nft.mint),
+            }
+            .data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    // Purchase of a listing priced in `payment_mint`. The creators are followed
+    // by their token accounts for it.
+    pub fn purchase_with_tokens_instruction(
+        &self,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        nft: &Nft,
+        payment_mint: &Pubkey,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::Purchase {
+                payment_mint: Some(*payment_mint),
+                taker_payment_ata: Some(ata(taker, payment_mint)),
+                maker_payment_ata: Some(ata(maker, payment_mint)),
+                treasury_payment_ata: Some(ata(&self.treasury, payment_mint)),
+                payment_token_program: Some(spl_token::ID),
+                ..self.purchase_accounts(taker, maker, nft)
+            }
+            .to_account_metas(None),
+            data: instruction::Purchase {
+                max_price: self.quote(&nft.mint),
+            }
+            .data(),
+        };
+        let creator_atas: Vec<Pubkey> = creators
+            .iter()
+            .map(|creator| ata(creator, payment_mint))
+            .collect();
+        with_creators(&mut instruction, creators);
+        with_creators(&mut instruction, &creator_atas);
+        instruction
+    }
+
+    pub fn update_fee_instruction(&self, admin: &Pubkey, fee: u16) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::UpdateMarketplace {
+                admin: *admin,
+                marketplace: self.marketplace,
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateFee { fee }.data(),
+        }
+    }
+
+    pub fn withdraw_treasury_instruction(&self, admin: &Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::WithdrawTreasury {
+                admin: *admin,
+                marketplace: self.marketplace,
+                treasury: self.treasury,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::WithdrawTreasury { amount }.data(),
+        }
+    }
+
+    pub fn add_payment_mint_instruction(&self, admin: &Pubkey, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::UpdateMarketplace {
+                admin: *admin,
+                marketplace: self.marketplace,
+            }
+            .to_account_metas(None),
+            data: instruction::AddPaymentMint { mint: *mint }.data(),
+        }
+    }
+
+    pub fn remove_payment_mint_instruction(&self, admin: &Pubkey, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::UpdateMarketplace {
+                admin: *admin,
+                marketplace: self.marketplace,
+            }
+            .to_account_metas(None),
+            data: instruction::RemovePaymentMint { mint: *mint }.data(),
+        }
+    }
+
+    pub fn update_royalty_policy_instruction(
+        &self,
+        admin: &Pubkey,
+        royalty_policy: RoyaltyPolicy,
+    ) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::UpdateMarketplace {
+                admin: *admin,
+                marketplace: self.marketplace,
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateRoyaltyPolicy { royalty_policy }.data(),
+        }
+    }
+
+    pub fn make_offer_instruction(
+        &self,
+        bidder: &Pubkey,
+        target: OfferTarget,
+        price: u64,
+        expires_at: i64,
+    ) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::MakeOffer {
+                bidder: *bidder,
+                marketplace: self.marketplace,
+                offer: self.offer(bidder, &target),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::MakeOffer {
+                target,
+                price,
+                expires_at,
+            }
+            .data(),
+        }
+    }
+
+    pub fn cancel_offer_instruction(&self, bidder: &Pubkey, target: &OfferTarget) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::CancelOffer {
+                bidder: *bidder,
+                marketplace: self.marketplace,
+                offer: self.offer(bidder, target),
+            }
+            .to_account_metas(None),
+            data: instruction::CancelOffer {}.data(),
+        }
+    }
+
+    // The NFT comes out of the listing vault if `listed`, or else out of the
+    // seller's token account.
+    pub fn accept_offer_instruction(
+        &self,
+        seller: &Pubkey,
+        bidder: &Pubkey,
+        nft: &Nft,
+        target: &OfferTarget,
+        listed: bool,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::AcceptOffer {
+                seller: *seller,
+                bidder: *bidder,
+                marketplace: self.marketplace,
+                mint: nft.mint,
+                seller_ata: (!listed).then(|| ata(seller, &nft.mint)),
+                listing: listed.then_some(listing),
+                vault: listed.then(|| ata(&listing, &nft.mint)),
+                bidder_ata: ata(bidder, &nft.mint),
+                offer: self.offer(bidder, target),
+                treasury: self.treasury,
+                metadata: nft.metadata,
+                master_edition: None,
+                metadata_program: mpl_token_metadata::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AcceptOffer {}.data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    // Accepts the offer with the NFT of an escrowless listing, which is thawed
+    // and moved out of the seller's token account.
+    pub fn accept_offer_escrowless_instruction(
+        &self,
+        seller: &Pubkey,
+        bidder: &Pubkey,
+        nft: &Nft,
+        target: &OfferTarget,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::AcceptOffer {
+                seller: *seller,
+                bidder: *bidder,
+                marketplace: self.marketplace,
+                mint: nft.mint,
+                seller_ata: Some(ata(seller, &nft.mint)),
+                listing: Some(self.listing(&nft.mint)),
+                vault: None,
+                bidder_ata: ata(bidder, &nft.mint),
+                offer: self.offer(bidder, target),
+                treasury: self.treasury,
+                metadata: nft.metadata,
+                master_edition: Some(nft.master_edition),
+                metadata_program: mpl_token_metadata::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AcceptOffer {}.data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    pub fn list_english_auction_instruction(
+        &self,
+        maker: &Pubkey,
+        nft: &Nft,
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: i64,
+    ) -> Instruction {
+        Instruction {
+            data: instruction::ListEnglishAuction {
+                reserve_price,
+                min_increment,
+                end_time,
+                extension: 0,
+            }
+            .data(),
+            ..self.list_instruction(maker, nft, 0)
+        }
+    }
+
+    // `previous_bidder` is the highest bidder so far, if any, who is refunded.
+    pub fn bid_instruction(
+        &self,
+        bidder: &Pubkey,
+        nft: &Nft,
+        amount: u64,
+        previous_bidder: Option<Pubkey>,
+    ) -> Instruction {
+        Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::Bid {
+                bidder: *bidder,
+                marketplace: self.marketplace,
+                listing: self.listing(&nft.mint),
+                previous_bidder,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Bid { amount }.data(),
+        }
+    }
+
+    pub fn settle_auction_instruction(
+        &self,
+        payer: &Pubkey,
+        maker: &Pubkey,
+        winner: &Pubkey,
+        nft: &Nft,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let listing = self.listing(&nft.mint);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::SettleAuction {
+                payer: *payer,
+                maker: *maker,
+                winner: *winner,
+                marketplace: self.marketplace,
+                maker_mint: nft.mint,
+                winner_ata: ata(winner, &nft.mint),
+                vault: ata(&listing, &nft.mint),
+                listing,
+                treasury: self.treasury,
+                metadata: nft.metadata,
+                metadata_program: mpl_token_metadata::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::SettleAuction {}.data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction
+    }
+
+    // An empty tree of the Bubblegum double, as account compression would
+    // initialize it.
+    pub fn create_tree(&mut self) -> TestTree {
+        let data = bytemuck::bytes_of(merkle_tree(&[]).as_ref()).to_vec();
+
+        let tree = TestTree {
+            address: Pubkey::new_unique(),
+            leaves: vec![],
+            metadata: vec![],
+        };
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: mpl_bubblegum::ID,
+            ..Account::default()
+        };
+        self.svm.set_account(&tree.address, &account);
+
+        tree
+    }
+
+    // The root of the tree as the Bubblegum double has it.
+    pub fn tree_root(&self, tree: &TestTree) -> [u8; 32] {
+        let data = self.svm.account(&tree.address).unwrap().data;
+        bytemuck::pod_read_unaligned::<Tree>(&data).get_root()
+    }
+
+    // Mint a compressed NFT in the verified collection to `owner` as the next
+    // leaf of `tree`, with royalties of `seller_fee_basis_points` split between
+    // `creators` by share, all verified.
+    pub fn mint_cnft(
+        &mut self,
+        tree: &mut TestTree,
+        owner: &Pubkey,
+        seller_fee_basis_points: u16,
+        creators: &[(Pubkey, u8)],
+    ) -> CompressedNft {
+        let collection = BubblegumCollection {
+            verified: true,
+            key: self.collection_mint,
+        };
+        self.mint_cnft_in(
+            tree,
+            owner,
+            seller_fee_basis_points,
+            creators,
+            Some(collection),
+        )
+    }
+
+    // Like `mint_cnft`, in `collection` instead.
+    pub fn mint_cnft_in(
+        &mut self,
+        tree: &mut TestTree,
+        owner: &Pubkey,
+        seller_fee_basis_points: u16,
+        creators: &[(Pubkey, u8)],
+        collection: Option<BubblegumCollection>,
+    ) -> CompressedNft {
+        let index = tree.leaves.len() as u32;
+        let metadata = MetadataArgs {
+            name: format!("Compressed #{index}"),
+            symbol: "CNFT".to_string(),
+            uri: "https://example.com/cnft.json".to_string(),
+            seller_fee_basis_points,
+            primary_sale_happened: true,
+            is_mutable: false,
+            edition_nonce: None,
+            token_standard: None,
+            collection,
+            uses: None,
+            token_program_version: TokenProgramVersion::Original,
+            creators: creators
+                .iter()
+                .map(|&(address, share)| BubblegumCreator {
+                    address,
+                    verified: true,
+                    share,
+                })
+                .collect(),
+        }
+        .try_to_vec()
+        .unwrap();
+        let mut nft = CompressedNft {
+            root: [0; 32],
+            metadata_hash: keccak::hash(&metadata).to_bytes(),
+            seller_fee_basis_points,
+            creators: creators
+                .iter()
+                .map(|&(address, share)| LeafCreator {
+                    address,
+                    verified: true,
+                    share,
+                })
+                .collect(),
+            nonce: index as u64,
+            index,
+        };
+        let leaf = tree.leaf(&nft, owner);
+        tree.leaves.push(leaf);
+        tree.metadata.push(metadata);
+
+        let mut account = self.svm.account(&tree.address).unwrap();
+        let mut state = bytemuck::pod_read_unaligned::<Tree>(&account.data);
+        state.append(leaf).unwrap();
+        account.data = bytemuck::bytes_of(&state).to_vec();
+        self.svm.set_account(&tree.address, &account);
+
+        nft.root = tree.root();
+        nft
+    }
+
+    // `nft` with the current root of `tree`, and the proof of its leaf.
+    fn proven(tree: &TestTree, nft: &CompressedNft) -> (CompressedNft, Vec<AccountMeta>) {
+        let proof = tree
+            .proof(nft.index)
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false))
+            .collect();
+        let nft = CompressedNft {
+            root: tree.root(),
+            ..nft.clone()
+        };
+
+        (nft, proof)
+    }
+
+    pub fn list_compressed_instruction(
+        &self,
+        maker: &Pubkey,
+        tree: &TestTree,
+        nft: &CompressedNft,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Instruction {
+        let (nft, proof) = Self::proven(tree, nft);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::ListCompressed {
+                maker: *maker,
+                marketplace: self.marketplace,
+                merkle_tree: tree.address,
+                tree_config: tree.tree_config(),
+                listing: self.listing(&nft.asset_id(&tree.address)),
+                collection_mint: self.collection_mint,
+                bubblegum_program: mpl_bubblegum::ID,
+                log_wrapper: SPL_NOOP_ID,
+                compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ListCompressed {
+                metadata: tree.metadata[nft.index as usize].clone(),
+                nft,
+                price,
+                expires_at,
+            }
+            .data(),
+        };
+        instruction.accounts.extend(proof);
+        instruction
+    }
+
+    // Signed by `signer`, the maker or anyone once the listing has expired.
+    pub fn delist_compressed_instruction(
+        &self,
+        signer: &Pubkey,
+        maker: &Pubkey,
+        tree: &TestTree,
+        nft: &CompressedNft,
+    ) -> Instruction {
+        let (nft, proof) = Self::proven(tree, nft);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::DelistCompressed {
+                signer: *signer,
+                maker: *maker,
+                marketplace: self.marketplace,
+                merkle_tree: tree.address,
+                tree_config: tree.tree_config(),
+                listing: self.listing(&nft.asset_id(&tree.address)),
+                bubblegum_program: mpl_bubblegum::ID,
+                log_wrapper: SPL_NOOP_ID,
+                compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::DelistCompressed { nft }.data(),
+        };
+        instruction.accounts.extend(proof);
+        instruction
+    }
+
+    // Like `purchase_instruction`, with the proof of the leaf after the creators.
+    pub fn purchase_compressed_instruction(
+        &self,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        tree: &TestTree,
+        nft: &CompressedNft,
+        creators: &[Pubkey],
+    ) -> Instruction {
+        let (nft, proof) = Self::proven(tree, nft);
+        let mut instruction = Instruction {
+            program_id: marketplace::ID,
+            accounts: accounts::PurchaseCompressed {
+                taker: *taker,
+                maker: *maker,
+                marketplace: self.marketplace,
+                merkle_tree: tree.address,
+                tree_config: tree.tree_config(),
+                listing: self.listing(&nft.asset_id(&tree.address)),
+                treasury: self.treasury,
+                rewards_mint: self.rewards_mint,
+                rewards_ata: ata(taker, &self.rewards_mint),
+                bubblegum_program: mpl_bubblegum::ID,
+                log_wrapper: SPL_NOOP_ID,
+                compression_program: SPL_ACCOUNT_COMPRESSION_ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::PurchaseCompressed {
+                max_price: self.quote(&nft.asset_id(&tree.address)),
+                nft,
+            }
+            .data(),
+        };
+        with_creators(&mut instruction, creators);
+        instruction.accounts.extend(proof);
+        instruction
+    }
+}
@@ -0,0 +1,175 @@
+// Stands in for the Token Metadata program, whose binary the tests don't have.
+// It freezes and thaws delegated token accounts, and transfers programmable NFTs
+// between the frozen token accounts of their owners, with the master edition as
+// the freeze authority of the mint. It checks the accounts the marketplace
+// passes like the real program, but keeps no token records and has no rule sets.
+// Its other instructions fail.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        account_info::next_account_info,
+        entrypoint::ProgramResult,
+        program::{invoke, invoke_signed},
+        program_option::COption,
+        program_pack::Pack,
+    },
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address,
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+    },
+    metadata::mpl_token_metadata::{
+        self,
+        accounts::{MasterEdition, TokenRecord},
+    },
+    token::spl_token,
+};
+
+const FREEZE_DELEGATED_ACCOUNT: u8 = 26;
+const THAW_DELEGATED_ACCOUNT: u8 = 27;
+const TRANSFER: u8 = 49;
+const TRANSFER_V1: u8 = 0;
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match data.first() {
+        Some(&FREEZE_DELEGATED_ACCOUNT) => set_frozen(accounts, true),
+        Some(&THAW_DELEGATED_ACCOUNT) => set_frozen(accounts, false),
+        Some(&TRANSFER) if data.get(1) == Some(&TRANSFER_V1) => transfer(accounts),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// Only the delegate of the token account can freeze or thaw it.
+fn set_frozen(accounts: &[AccountInfo], frozen: bool) -> ProgramResult {
+    let [delegate, token_account, edition, mint, token_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    if !delegate.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let token = spl_token::state::Account::unpack(&token_account.try_borrow_data()?)?;
+    if token.delegate != COption::Some(*delegate.key) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    freeze_or_thaw(frozen, token_account, edition, mint, token_program)
+}
+
+// Only the owner of a pNFT can transfer it here, and never to a token account
+// other than the associated one of its new owner, which is created if needed.
+fn transfer(accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let token = next_account_info(accounts)?;
+    let token_owner = next_account_info(accounts)?;
+    let destination_token = next_account_info(accounts)?;
+    let destination_owner = next_account_info(accounts)?;
+    let mint = next_account_info(accounts)?;
+    let _metadata = next_account_info(accounts)?;
+    let edition = next_account_info(accounts)?;
+    let token_record = next_account_info(accounts)?;
+    let destination_token_record = next_account_info(accounts)?;
+    let authority = next_account_info(accounts)?;
+    let payer = next_account_info(accounts)?;
+    let system_program = next_account_info(accounts)?;
+    let _sysvar_instructions = next_account_info(accounts)?;
+    let token_program = next_account_info(accounts)?;
+    let ata_program = next_account_info(accounts)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let source = spl_token::state::Account::unpack(&token.try_borrow_data()?)?;
+    if source.owner != *token_owner.key || *authority.key != *token_owner.key {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if *destination_token.key != get_associated_token_address(destination_owner.key, mint.key)
+        || *token_record.key != TokenRecord::find_pda(mint.key, token.key).0
+        || *destination_token_record.key != TokenRecord::find_pda(mint.key, destination_token.key).0
+    {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            payer.key,
+            destination_owner.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            payer.clone(),
+            destination_token.clone(),
+            destination_owner.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            ata_program.clone(),
+        ],
+    )?;
+
+    if source.is_frozen() {
+        freeze_or_thaw(false, token, edition, mint, token_program)?;
+    }
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            token.key,
+            destination_token.key,
+            authority.key,
+            &[],
+            1,
+        )?,
+        &[
+            token.clone(),
+            destination_token.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+    )?;
+    freeze_or_thaw(true, destination_token, edition, mint, token_program)
+}
+
+fn freeze_or_thaw<'a>(
+    frozen: bool,
+    token_account: &AccountInfo<'a>,
+    edition: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (address, bump) = MasterEdition::find_pda(mint.key);
+    if *edition.key != address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let instruction = match frozen {
+        true => spl_token::instruction::freeze_account,
+        false => spl_token::instruction::thaw_account,
+    }(
+        token_program.key,
+        token_account.key,
+        mint.key,
+        edition.key,
+        &[],
+    )?;
+    invoke_signed(
+        &instruction,
+        &[
+            token_account.clone(),
+            mint.clone(),
+            edition.clone(),
+            token_program.clone(),
+        ],
+        &[&[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key.as_ref(),
+            b"edition",
+            &[bump],
+        ]],
+    )
+}
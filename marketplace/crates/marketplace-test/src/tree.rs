@@ -0,0 +1,100 @@
+// A tree of compressed NFTs, kept by the Bubblegum double (see `bubblegum`), and
+// a copy of its leaves to prove them with and of their metadata, as an indexer
+// would have. The copy only follows transfers the tests tell it about with
+// `set_owner`.
+
+use anchor_lang::prelude::*;
+use marketplace::cnft::CompressedNft;
+use mpl_bubblegum::types::LeafSchema;
+use spl_concurrent_merkle_tree::{
+    hash::hash_to_parent,
+    node::{empty_node, Node},
+};
+
+use crate::bubblegum::{Tree, MAX_DEPTH};
+
+pub struct TestTree {
+    pub address: Pubkey,
+    pub(crate) leaves: Vec<Node>,
+    pub(crate) metadata: Vec<Vec<u8>>, // The serialized `MetadataArgs` of each leaf.
+}
+
+impl TestTree {
+    pub fn tree_config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[self.address.as_ref()], &mpl_bubblegum::ID).0
+    }
+
+    // The leaf of `nft` when `owner` owns it, without a delegate.
+    pub fn leaf(&self, nft: &CompressedNft, owner: &Pubkey) -> Node {
+        LeafSchema::V1 {
+            id: nft.asset_id(&self.address),
+            owner: *owner,
+            delegate: *owner,
+            nonce: nft.nonce,
+            data_hash: nft.data_hash(),
+            creator_hash: nft.creator_hash(),
+        }
+        .hash()
+    }
+
+    // Record that `owner` now owns `nft`.
+    pub fn set_owner(&mut self, nft: &CompressedNft, owner: &Pubkey) {
+        self.leaves[nft.index as usize] = self.leaf(nft, owner);
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        levels(&self.leaves).last().unwrap()[0]
+    }
+
+    // The proof of the leaf at `index`, as the accounts Bubblegum takes.
+    pub fn proof(&self, index: u32) -> Vec<Pubkey> {
+        proof(&self.leaves, index)
+            .into_iter()
+            .map(Pubkey::new_from_array)
+            .collect()
+    }
+}
+
+// The tree as account compression keeps it once `leaves` are appended to it.
+pub fn merkle_tree(leaves: &[Node]) -> Box<Tree> {
+    let mut tree = Box::new(Tree::new());
+    tree.initialize().unwrap();
+    for leaf in leaves {
+        tree.append(*leaf).unwrap();
+    }
+
+    tree
+}
+
+// The proof of the leaf at `index` among `leaves`, as its sibling at every
+// level up to the root.
+pub fn proof(leaves: &[Node], index: u32) -> Vec<Node> {
+    levels(leaves)
+        .iter()
+        .take(MAX_DEPTH)
+        .enumerate()
+        .map(|(level, nodes)| nodes[(index as usize >> level) ^ 1])
+        .collect()
+}
+
+fn levels(leaves: &[Node]) -> Vec<Vec<Node>> {
+    let mut level = leaves.to_vec();
+    level.resize(1 << MAX_DEPTH, empty_node(0));
+
+    let mut levels = vec![level];
+    while levels.last().unwrap().len() > 1 {
+        let parents = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| {
+                let mut node = pair[0];
+                hash_to_parent(&mut node, &pair[1], true);
+                node
+            })
+            .collect();
+        levels.push(parents);
+    }
+
+    levels
+}
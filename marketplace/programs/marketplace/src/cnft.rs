@@ -0,0 +1,242 @@
+use anchor_lang::{prelude::*, solana_program::keccak};
+use anchor_spl::metadata::mpl_token_metadata::types::Creator;
+use mpl_bubblegum::{
+    instructions::TransferInstructionArgs,
+    types::{Collection, MetadataArgs},
+    utils::get_asset_id,
+};
+
+use crate::errors::MarketplaceError;
+
+// A compressed NFT, a leaf of a Bubblegum Merkle tree, as read from an indexer
+// along with its proof. The leaf only keeps hashes of the metadata, which
+// Bubblegum checks these against on every transfer, so the royalties of a sale
+// can be taken from here.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CompressedNft {
+    pub root: [u8; 32],
+    pub metadata_hash: [u8; 32], // Keccak hash of its serialized Bubblegum `MetadataArgs`.
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<LeafCreator>,
+    pub nonce: u64,
+    pub index: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LeafCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+impl CompressedNft {
+    pub fn asset_id(&self, merkle_tree: &Pubkey) -> Pubkey {
+        get_asset_id(merkle_tree, self.nonce)
+    }
+
+    // Same as Bubblegum's hash of the metadata.
+    pub fn data_hash(&self) -> [u8; 32] {
+        keccak::hashv(&[
+            &self.metadata_hash,
+            &self.seller_fee_basis_points.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    // Same as Bubblegum's hash of the creators.
+    pub fn creator_hash(&self) -> [u8; 32] {
+        let creators = self
+            .creators
+            .iter()
+            .map(|c| [c.address.as_ref(), &[c.verified as u8], &[c.share]].concat())
+            .collect::<Vec<_>>();
+
+        keccak::hashv(&creators.iter().map(|c| c.as_slice()).collect::<Vec<_>>()).to_bytes()
+    }
+
+    // The collection in `metadata`, the serialized `MetadataArgs` the leaf
+    // hashes.
+    pub fn collection(&self, metadata: &[u8]) -> Result<Option<Collection>> {
+        require!(
+            keccak::hash(metadata).to_bytes() == self.metadata_hash,
+            MarketplaceError::InvalidMetadata
+        );
+        let metadata = MetadataArgs::try_from_slice(metadata)
+            .map_err(|_| MarketplaceError::InvalidMetadata)?;
+
+        Ok(metadata.collection)
+    }
+
+    pub fn metadata_creators(&self) -> Vec<Creator> {
+        self.creators
+            .iter()
+            .map(|c| Creator {
+                address: c.address,
+                verified: c.verified,
+                share: c.share,
+            })
+            .collect()
+    }
+
+    pub fn transfer_args(&self) -> TransferInstructionArgs {
+        TransferInstructionArgs {
+            root: self.root,
+            data_hash: self.data_hash(),
+            creator_hash: self.creator_hash(),
+            nonce: self.nonce,
+            index: self.index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marketplace_test::tree::{merkle_tree, proof};
+    use mpl_bubblegum::{
+        hash::{hash_creators, hash_metadata},
+        types::{Creator as BubblegumCreator, LeafSchema, TokenProgramVersion},
+    };
+    use spl_concurrent_merkle_tree::node::Node;
+
+    fn metadata(creators: &[(Pubkey, bool, u8)]) -> MetadataArgs {
+        MetadataArgs {
+            name: "Compressed".to_string(),
+            symbol: "CNFT".to_string(),
+            uri: "https://example.com/cnft.json".to_string(),
+            seller_fee_basis_points: 500,
+            primary_sale_happened: true,
+            is_mutable: false,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            token_program_version: TokenProgramVersion::Original,
+            creators: creators
+                .iter()
+                .map(|&(address, verified, share)| BubblegumCreator {
+                    address,
+                    verified,
+                    share,
+                })
+                .collect(),
+        }
+    }
+
+    fn compressed_nft(metadata: &MetadataArgs, nonce: u64) -> CompressedNft {
+        CompressedNft {
+            root: [0; 32],
+            metadata_hash: keccak::hashv(&[&metadata.try_to_vec().unwrap()]).to_bytes(),
+            seller_fee_basis_points: metadata.seller_fee_basis_points,
+            creators: metadata
+                .creators
+                .iter()
+                .map(|c| LeafCreator {
+                    address: c.address,
+                    verified: c.verified,
+                    share: c.share,
+                })
+                .collect(),
+            nonce,
+            index: nonce as u32,
+        }
+    }
+
+    fn leaf(nft: &CompressedNft, merkle_tree: &Pubkey, owner: Pubkey) -> Node {
+        LeafSchema::V1 {
+            id: nft.asset_id(merkle_tree),
+            owner,
+            delegate: owner,
+            nonce: nft.nonce,
+            data_hash: nft.data_hash(),
+            creator_hash: nft.creator_hash(),
+        }
+        .hash()
+    }
+
+    // The leaves of a tree with a leaf owned by `owner` at index 1, after
+    // another one.
+    fn leaves(nft: &CompressedNft, merkle_tree: &Pubkey, owner: Pubkey) -> Vec<Node> {
+        vec![[7; 32], leaf(nft, merkle_tree, owner)]
+    }
+
+    #[test]
+    fn leaf_hashes_match_bubblegum() {
+        let metadata = metadata(&[
+            (Pubkey::new_unique(), true, 60),
+            (Pubkey::new_unique(), false, 40),
+        ]);
+        let nft = compressed_nft(&metadata, 1);
+
+        assert_eq!(nft.data_hash(), hash_metadata(&metadata).unwrap());
+        assert_eq!(nft.creator_hash(), hash_creators(&metadata.creators));
+    }
+
+    #[test]
+    fn collection_is_read_from_the_hashed_metadata() {
+        let mut metadata = metadata(&[]);
+        let collection = Collection {
+            verified: true,
+            key: Pubkey::new_unique(),
+        };
+        metadata.collection = Some(collection.clone());
+        let nft = compressed_nft(&metadata, 1);
+
+        let serialized = metadata.try_to_vec().unwrap();
+        assert_eq!(nft.collection(&serialized).unwrap(), Some(collection));
+
+        // Other metadata claiming the same collection doesn't match the leaf.
+        metadata.name = "Forged".to_string();
+        assert!(nft.collection(&metadata.try_to_vec().unwrap()).is_err());
+    }
+
+    #[test]
+    fn listed_leaf_is_provable_in_the_tree() {
+        let (address, maker, listing) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let nft = compressed_nft(&metadata(&[(Pubkey::new_unique(), true, 100)]), 1);
+        let mut leaves = leaves(&nft, &address, maker);
+        let mut tree = merkle_tree(&leaves);
+
+        let root = tree.get_root();
+        let maker_leaf = leaf(&nft, &address, maker);
+        tree.prove_leaf(root, maker_leaf, &proof(&leaves, 1), 1)
+            .unwrap();
+
+        // Listing transfers the leaf to the listing.
+        let listing_leaf = leaf(&nft, &address, listing);
+        tree.set_leaf(root, maker_leaf, listing_leaf, &proof(&leaves, 1), 1)
+            .unwrap();
+        leaves[1] = listing_leaf;
+
+        let root = tree.get_root();
+        tree.prove_leaf(root, listing_leaf, &proof(&leaves, 1), 1)
+            .unwrap();
+        assert!(tree
+            .prove_leaf(root, maker_leaf, &proof(&leaves, 1), 1)
+            .is_err());
+    }
+
+    #[test]
+    fn forged_royalties_fail_the_proof() {
+        let (address, listing) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let creator = Pubkey::new_unique();
+        let nft = compressed_nft(&metadata(&[(creator, true, 100)]), 1);
+        let leaves = leaves(&nft, &address, listing);
+        let tree = merkle_tree(&leaves);
+        let root = tree.get_root();
+
+        let mut no_royalties = nft.clone();
+        no_royalties.seller_fee_basis_points = 0;
+        let mut no_creators = nft.clone();
+        no_creators.creators.clear();
+
+        for forged in [no_royalties, no_creators] {
+            let leaf = leaf(&forged, &address, listing);
+            assert!(tree.prove_leaf(root, leaf, &proof(&leaves, 1), 1).is_err());
+        }
+    }
+}
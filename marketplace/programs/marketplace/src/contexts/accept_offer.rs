@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    metadata::{
+        mpl_token_metadata::instructions::{
+            ThawDelegatedAccountCpi, ThawDelegatedAccountCpiAccounts,
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    errors::MarketplaceError,
+    pnft::is_programmable,
+    settlement::settle,
+    state::{Listing, Marketplace, Offer, OfferTarget},
+};
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Holds the NFT unless it is listed, or if the listing is escrowless.
+    #[account(
+        mut,
+        associated_token::mint=mint,
+        associated_token::authority=seller
+    )]
+    pub seller_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    // Hold the NFT if it is listed. The listing is closed by the sale.
+    #[account(
+        mut,
+        seeds=[b"listing", marketplace.key().as_ref(), mint.key().as_ref()],
+        bump=listing.bump,
+        constraint=listing.maker == seller.key() @ MarketplaceError::InvalidListing,
+        close=seller
+    )]
+    pub listing: Option<Box<Account<'info, Listing>>>,
+    #[account(mut)]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer=seller,
+        associated_token::mint=mint,
+        associated_token::authority=bidder
+    )]
+    pub bidder_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one=bidder,
+        has_one=marketplace,
+        seeds=[
+            b"offer",
+            marketplace.key().as_ref(),
+            bidder.key().as_ref(),
+            offer.target.key().as_ref(),
+        ],
+        bump=offer.bump,
+        close=bidder
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(
+        mut,
+        seeds=[b"treasury", marketplace.key().as_ref()],
+        bump=marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    // Only needed to thaw the NFT of an escrowless listing.
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptOffer<'info> {
+    // Check that the offer is live and covers the NFT.
+    pub fn check_offer(&self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.offer.expires_at,
+            MarketplaceError::OfferExpired
+        );
+        // Offers are settled with plain token transfers, which pNFTs don't allow.
+        require!(
+            !is_programmable(&self.metadata),
+            MarketplaceError::UnsupportedTokenStandard
+        );
+        // The listing escrows the bids of an English auction.
+        if let Some(listing) = &self.listing {
+            require!(!listing.has_bids(), MarketplaceError::AuctionHasBids);
+        }
+
+        match self.offer.target {
+            OfferTarget::Mint(mint) => {
+                require_keys_eq!(mint, self.mint.key(), MarketplaceError::InvalidOfferTarget)
+            }
+            OfferTarget::Collection(collection) => require!(
+                self.metadata
+                    .collection
+                    .as_ref()
+                    .is_some_and(|c| c.verified && c.key == collection),
+                MarketplaceError::InvalidOfferTarget
+            ),
+        }
+
+        Ok(())
+    }
+
+    // Pay royalties to the verified creators, passed in remaining_accounts in the
+    // order of the metadata, the marketplace fee to the treasury, and the rest of
+    // the offer to the seller, out of the escrow.
+    pub fn transfer_sol(&self, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let mut settlement = settle(
+            &self.marketplace,
+            &self.metadata,
+            self.offer.price,
+            creators,
+        )?;
+        settlement.fold_unpayable_royalties(&Rent::get()?);
+
+        self.offer.sub_lamports(self.offer.price)?;
+        for (creator, amount) in settlement.royalties {
+            creator.add_lamports(amount)?;
+        }
+        self.treasury.add_lamports(settlement.fee)?;
+        self.seller.add_lamports(settlement.proceeds)?;
+
+        Ok(())
+    }
+
+    // Transfer NFT from the vault if it is listed, or else from the seller, to
+    // the bidder. The NFT of an escrowless listing is thawed and transferred from
+    // the seller by the listing, as its delegate.
+    pub fn transfer_nft(&mut self) -> Result<()> {
+        match (&self.listing, &self.vault) {
+            (Some(listing), None) if listing.escrowless => {
+                let (Some(seller_ata), Some(master_edition)) =
+                    (&self.seller_ata, &self.master_edition)
+                else {
+                    return err!(MarketplaceError::InvalidListing);
+                };
+
+                let seeds = &[
+                    b"listing",
+                    &self.marketplace.key().to_bytes()[..],
+                    &self.mint.key().to_bytes()[..],
+                    &[listing.bump],
+                ];
+                let signer_seeds = &[&seeds[..]];
+
+                ThawDelegatedAccountCpi::new(
+                    &self.metadata_program.to_account_info(),
+                    ThawDelegatedAccountCpiAccounts {
+                        delegate: &listing.to_account_info(),
+                        token_account: &seller_ata.to_account_info(),
+                        edition: &master_edition.to_account_info(),
+                        mint: &self.mint.to_account_info(),
+                        token_program: &self.token_program.to_account_info(),
+                    },
+                )
+                .invoke_signed(signer_seeds)?;
+
+                let accounts = TransferChecked {
+                    from: seller_ata.to_account_info(),
+                    to: self.bidder_ata.to_account_info(),
+                    authority: listing.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    accounts,
+                    signer_seeds,
+                );
+
+                transfer_checked(cpi_ctx, 1, self.mint.decimals)?;
+            }
+            (Some(listing), Some(vault)) => {
+                require_keys_eq!(
+                    vault.key(),
+                    get_associated_token_address_with_program_id(
+                        &listing.key(),
+                        &self.mint.key(),
+                        &self.token_program.key()
+                    ),
+                    MarketplaceError::InvalidListing
+                );
+
+                let seeds = &[
+                    b"listing",
+                    &self.marketplace.key().to_bytes()[..],
+                    &self.mint.key().to_bytes()[..],
+                    &[listing.bump],
+                ];
+                let signer_seeds = &[&seeds[..]];
+
+                let accounts = TransferChecked {
+                    from: vault.to_account_info(),
+                    to: self.bidder_ata.to_account_info(),
+                    authority: listing.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    accounts,
+                    signer_seeds,
+                );
+
+                transfer_checked(cpi_ctx, 1, self.mint.decimals)?;
+
+                // Close the vault.
+                let accounts = CloseAccount {
+                    account: vault.to_account_info(),
+                    destination: self.seller.to_account_info(),
+                    authority: listing.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    accounts,
+                    signer_seeds,
+                );
+
+                close_account(cpi_ctx)?;
+            }
+            (None, None) => {
+                let seller_ata = self
+                    .seller_ata
+                    .as_ref()
+                    .ok_or(MarketplaceError::InvalidListing)?;
+
+                let accounts = TransferChecked {
+                    from: seller_ata.to_account_info(),
+                    to: self.bidder_ata.to_account_info(),
+                    authority: self.seller.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+
+                transfer_checked(cpi_ctx, 1, self.mint.decimals)?;
+            }
+            _ => return err!(MarketplaceError::InvalidListing),
+        }
+
+        Ok(())
+    }
+}
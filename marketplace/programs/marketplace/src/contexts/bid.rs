@@ -0,0 +1,75 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{
+    errors::MarketplaceError,
+    state::{Listing, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct Bid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        mut,
+        seeds=[b"listing", marketplace.key().as_ref(), listing.mint.as_ref()],
+        bump=listing.bump,
+        constraint=listing.maker != bidder.key() @ MarketplaceError::InvalidListing
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// CHECK: The highest bidder so far, who is refunded, checked against the
+    /// listing. Not needed for the first bid. Any account can be refunded, so
+    /// that one that changed hands since it bid can't stall the auction.
+    #[account(mut)]
+    pub previous_bidder: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Bid<'info> {
+    pub fn place_bid(&mut self, amount: u64) -> Result<()> {
+        let outbid = self
+            .listing
+            .bid(self.bidder.key(), amount, Clock::get()?.unix_timestamp)?;
+
+        // Escrow the bid in the listing account.
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.bidder.to_account_info(),
+            to: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer(cpi_ctx, amount)?;
+
+        // Refund the bid that was outbid.
+        if let Some((previous_bidder, previous_bid)) = outbid {
+            let account = self
+                .previous_bidder
+                .as_ref()
+                .ok_or(MarketplaceError::InvalidListing)?;
+            require_keys_eq!(
+                account.key(),
+                previous_bidder,
+                MarketplaceError::InvalidListing
+            );
+
+            self.listing.sub_lamports(previous_bid)?;
+            account.add_lamports(previous_bid)?;
+        }
+
+        Ok(())
+    }
+}
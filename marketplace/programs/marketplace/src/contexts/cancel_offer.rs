@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Marketplace, Offer};
+
+// Closing the offer refunds the escrowed price along with the rent, whether or
+// not it has expired.
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        mut,
+        has_one=bidder,
+        has_one=marketplace,
+        seeds=[
+            b"offer",
+            marketplace.key().as_ref(),
+            bidder.key().as_ref(),
+            offer.target.key().as_ref(),
+        ],
+        bump=offer.bump,
+        close=bidder
+    )]
+    pub offer: Account<'info, Offer>,
+}
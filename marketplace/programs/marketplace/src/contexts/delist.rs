@@ -1,13 +1,26 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
+    metadata::{
+        mpl_token_metadata::{
+            self,
+            instructions::{
+                ThawDelegatedAccountCpi, ThawDelegatedAccountCpiAccounts, TransferV1Cpi,
+                TransferV1CpiAccounts, TransferV1InstructionArgs,
+            },
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
     token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
+        close_account, revoke, transfer_checked, CloseAccount, Mint, Revoke, TokenAccount,
+        TokenInterface, TransferChecked,
     },
 };
 
-use crate::state::{Listing, Marketplace};
+use crate::{
+    errors::MarketplaceError,
+    state::{Listing, Marketplace},
+};
 
 #[derive(Accounts)]
 pub struct Delist<'info> {
@@ -15,7 +28,7 @@ pub struct Delist<'info> {
     pub maker: Signer<'info>,
 
     #[account(
-        seeds=[b"marketplace", marketplace.name.as_str().as_bytes()],
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
         bump=marketplace.bump
     )]
     pub marketplace: Account<'info, Marketplace>,
@@ -29,22 +42,65 @@ pub struct Delist<'info> {
     )]
     pub maker_ata: InterfaceAccount<'info, TokenAccount>,
 
+    // Holds the NFT unless the listing is escrowless.
     #[account(
-        init,
-        payer=maker,
+        mut,
         associated_token::mint=maker_mint,
         associated_token::authority=listing
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
+        has_one=maker,
         seeds=[b"listing", marketplace.key().as_ref(), maker_mint.key().as_ref()],
         bump=listing.bump,
+        constraint=!listing.has_bids() @ MarketplaceError::AuctionHasBids,
         close=maker
     )]
     pub listing: Account<'info, Listing>,
 
+    // Only needed to thaw the NFT of an escrowless listing, or for programmable
+    // NFTs.
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+    )]
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+    pub metadata_program: Option<Program<'info, Metadata>>,
+
+    // Only needed for programmable NFTs.
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub maker_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: The rule set of the NFT, checked by the Token Metadata program.
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: The instructions sysvar, checked by address.
+    #[account(address=anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
@@ -52,6 +108,17 @@ pub struct Delist<'info> {
 
 impl<'info> Delist<'info> {
     pub fn withdraw_nft(&mut self) -> Result<()> {
+        if self.listing.programmable {
+            return self.withdraw_pnft();
+        }
+        if self.listing.escrowless {
+            return self.thaw_nft();
+        }
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or(MarketplaceError::InvalidListing)?;
+
         let seeds = &[
             b"listing",
             &self.marketplace.key().to_bytes()[..],
@@ -62,7 +129,7 @@ impl<'info> Delist<'info> {
 
         // Transfer NFT from vault to maker.
         let accounts = TransferChecked {
-            from: self.vault.to_account_info(),
+            from: vault.to_account_info(),
             to: self.maker_ata.to_account_info(),
             authority: self.listing.to_account_info(),
             mint: self.maker_mint.to_account_info(),
@@ -79,7 +146,124 @@ impl<'info> Delist<'info> {
         Ok(())
     }
 
+    // Thaw the NFT in the maker's token account and revoke the listing's
+    // delegation.
+    fn thaw_nft(&self) -> Result<()> {
+        let (Some(master_edition), Some(metadata_program)) =
+            (&self.master_edition, &self.metadata_program)
+        else {
+            return err!(MarketplaceError::InvalidListing);
+        };
+
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.maker_mint.key().to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        ThawDelegatedAccountCpi::new(
+            &metadata_program.to_account_info(),
+            ThawDelegatedAccountCpiAccounts {
+                delegate: &self.listing.to_account_info(),
+                token_account: &self.maker_ata.to_account_info(),
+                edition: &master_edition.to_account_info(),
+                mint: &self.maker_mint.to_account_info(),
+                token_program: &self.token_program.to_account_info(),
+            },
+        )
+        .invoke_signed(signer_seeds)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Revoke {
+            source: self.maker_ata.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        revoke(cpi_ctx)?;
+
+        Ok(())
+    }
+
+    // Return a programmable NFT from the vault through the Token Metadata
+    // program. They are never listed escrowless.
+    fn withdraw_pnft(&self) -> Result<()> {
+        let (
+            Some(metadata),
+            Some(master_edition),
+            Some(metadata_program),
+            Some(maker_token_record),
+            Some(sysvar_instructions),
+        ) = (
+            &self.metadata,
+            &self.master_edition,
+            &self.metadata_program,
+            &self.maker_token_record,
+            &self.sysvar_instructions,
+        )
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        let (Some(vault), Some(vault_token_record)) = (&self.vault, &self.vault_token_record)
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.maker_mint.key().to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        TransferV1Cpi::new(
+            &metadata_program.to_account_info(),
+            TransferV1CpiAccounts {
+                token: &vault.to_account_info(),
+                token_owner: &self.listing.to_account_info(),
+                destination_token: &self.maker_ata.to_account_info(),
+                destination_owner: &self.maker.to_account_info(),
+                mint: &self.maker_mint.to_account_info(),
+                metadata: &metadata.to_account_info(),
+                edition: Some(&master_edition.to_account_info()),
+                token_record: Some(vault_token_record),
+                destination_token_record: Some(maker_token_record),
+                authority: &self.listing.to_account_info(),
+                payer: &self.maker.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+                sysvar_instructions,
+                spl_token_program: &self.token_program.to_account_info(),
+                spl_ata_program: &self.associated_token_program.to_account_info(),
+                authorization_rules_program: self.authorization_rules_program.as_deref(),
+                authorization_rules: self.authorization_rules.as_deref(),
+            },
+            TransferV1InstructionArgs {
+                amount: 1,
+                authorization_data: None,
+            },
+        )
+        .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+
     pub fn close_vault(&mut self) -> Result<()> {
+        // Escrowless listings have no vault.
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
+        // The Token Metadata program may already have closed the emptied vault of
+        // a programmable NFT.
+        if vault.to_account_info().lamports() == 0 {
+            return Ok(());
+        }
+
         let seeds = &[
             b"listing",
             &self.marketplace.key().to_bytes()[..],
@@ -90,7 +274,7 @@ impl<'info> Delist<'info> {
 
         // Close the vault.
         let accounts = CloseAccount {
-            account: self.vault.to_account_info(),
+            account: vault.to_account_info(),
             destination: self.maker.to_account_info(),
             authority: self.listing.to_account_info(),
         };
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use mpl_bubblegum::{
+    instructions::{TransferCpi, TransferCpiAccounts},
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+};
+
+use crate::{
+    cnft::CompressedNft,
+    errors::MarketplaceError,
+    state::{Listing, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct DelistCompressed<'info> {
+    // The maker, or anyone once the listing has expired.
+    pub signer: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// CHECK: The tree the NFT is a leaf of, checked against the listing.
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Checked by Bubblegum.
+    #[account(
+        seeds=[merkle_tree.key().as_ref()],
+        seeds::program=mpl_bubblegum::ID,
+        bump
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one=maker,
+        seeds=[b"listing", marketplace.key().as_ref(), listing.mint.as_ref()],
+        bump=listing.bump,
+        constraint=listing.merkle_tree == Some(merkle_tree.key()) @ MarketplaceError::InvalidListing,
+        close=maker
+    )]
+    pub listing: Account<'info, Listing>,
+
+    /// CHECK: Checked by address.
+    #[account(address=mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DelistCompressed<'info> {
+    // Transfer the leaf from the listing back to the maker. The proof of the leaf
+    // is passed in remaining_accounts.
+    pub fn withdraw_nft(
+        &mut self,
+        nft: &CompressedNft,
+        proof: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            self.signer.key() == self.maker.key()
+                || self.listing.is_expired(Clock::get()?.unix_timestamp),
+            MarketplaceError::ListingNotExpired
+        );
+        require_keys_eq!(
+            nft.asset_id(&self.merkle_tree.key()),
+            self.listing.mint,
+            MarketplaceError::InvalidListing
+        );
+
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.listing.mint.to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let proof = proof
+            .iter()
+            .map(|node| (node, false, false))
+            .collect::<Vec<_>>();
+
+        TransferCpi::new(
+            &self.bubblegum_program.to_account_info(),
+            TransferCpiAccounts {
+                tree_config: &self.tree_config.to_account_info(),
+                leaf_owner: (&self.listing.to_account_info(), true),
+                leaf_delegate: (&self.listing.to_account_info(), false),
+                new_leaf_owner: &self.maker.to_account_info(),
+                merkle_tree: &self.merkle_tree.to_account_info(),
+                log_wrapper: &self.log_wrapper.to_account_info(),
+                compression_program: &self.compression_program.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+            },
+            nft.transfer_args(),
+        )
+        .invoke_signed_with_remaining_accounts(signer_seeds, &proof)?;
+
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::token_interface::{Mint, TokenInterface};
 
-use crate::{errors::MarketplaceError, state::Marketplace};
+use crate::{
+    errors::MarketplaceError,
+    state::{Marketplace, RoyaltyPolicy, MAX_FEE},
+};
 
 #[derive(Accounts)]
 #[instruction(name: String)]
@@ -12,13 +18,14 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer=admin,
-        seeds=[b"marketplace", name.as_str().as_bytes()],
+        seeds=[b"marketplace", name.as_bytes()],
         bump,
         space=Marketplace::INIT_SPACE
     )]
     pub marketplace: Account<'info, Marketplace>,
 
     #[account(
+        mut,
         seeds=[b"treasury", marketplace.key().as_ref()],
         bump
     )]
@@ -41,13 +48,16 @@ pub struct Initialize<'info> {
 impl<'info> Initialize<'info> {
     pub fn init(&mut self, name: String, fee: u16, bumps: &InitializeBumps) -> Result<()> {
         require!(
-            name.len() > 0 && name.len() < 4 + 33,
+            !name.is_empty() && name.len() < 4 + 33,
             MarketplaceError::NameTooLong
         );
+        require!(fee <= MAX_FEE, MarketplaceError::FeeTooHigh);
 
         self.marketplace.set_inner(Marketplace {
             admin: self.admin.key(),
             fee,
+            royalty_policy: RoyaltyPolicy::Enforced,
+            payment_mints: vec![],
             bump: bumps.marketplace,
             treasury_bump: bumps.treasury,
             rewards_bump: bumps.rewards_mint,
@@ -56,4 +66,25 @@ impl<'info> Initialize<'info> {
 
         Ok(())
     }
+
+    // Fund the treasury with its rent exemption, so that fees smaller than that
+    // can be paid into it.
+    pub fn fund_treasury(&mut self) -> Result<()> {
+        let lamports = Rent::get()?
+            .minimum_balance(0)
+            .saturating_sub(self.treasury.lamports());
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.admin.to_account_info(),
+            to: self.treasury.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer(cpi_ctx, lamports)?;
+
+        Ok(())
+    }
 }
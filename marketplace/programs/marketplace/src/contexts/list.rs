@@ -1,11 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    metadata::{
+        mpl_token_metadata::instructions::{
+            TransferV1Cpi, TransferV1CpiAccounts, TransferV1InstructionArgs,
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
     token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::{Listing, Marketplace};
+use crate::{
+    errors::MarketplaceError,
+    pnft::is_programmable,
+    state::{Listing, ListingKind, Marketplace},
+};
 
 #[derive(Accounts)]
 pub struct List<'info> {
@@ -13,7 +22,7 @@ pub struct List<'info> {
     pub maker: Signer<'info>,
 
     #[account(
-        seeds=[b"marketplace", marketplace.name.as_str().as_bytes()],
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
         bump=marketplace.bump
     )]
     pub marketplace: Account<'info, Marketplace>,
@@ -45,7 +54,9 @@ pub struct List<'info> {
     pub listing: Account<'info, Listing>,
 
     pub collection_mint: InterfaceAccount<'info, Mint>,
+    // Token Metadata writes to it when it transfers a pNFT.
     #[account(
+        mut,
         seeds = [
             b"metadata",
             metadata_program.key().as_ref(),
@@ -54,7 +65,7 @@ pub struct List<'info> {
         seeds::program = metadata_program.key(),
         bump,
         constraint = metadata.collection.as_ref().unwrap().key.as_ref() == collection_mint.key().as_ref(),
-        constraint = metadata.collection.as_ref().unwrap().verified == true,
+        constraint = metadata.collection.as_ref().unwrap().verified,
     )]
     pub metadata: Account<'info, MetadataAccount>,
 
@@ -69,6 +80,22 @@ pub struct List<'info> {
         bump,
     )]
     pub master_edition: Account<'info, MasterEditionAccount>,
+
+    // Only needed for programmable NFTs.
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub maker_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: The rule set of the NFT, checked by the Token Metadata program.
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: The instructions sysvar, checked by address.
+    #[account(address=anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
     pub metadata_program: Program<'info, Metadata>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -77,12 +104,106 @@ pub struct List<'info> {
 
 impl<'info> List<'info> {
     // Store details about the listing.
-    pub fn create_listing(&mut self, price: u64, bumps: &ListBumps) -> Result<()> {
+    pub fn create_listing(
+        &mut self,
+        price: u64,
+        expires_at: Option<i64>,
+        payment_mint: Option<Pubkey>,
+        bumps: &ListBumps,
+    ) -> Result<()> {
+        check_terms(&self.marketplace, expires_at, payment_mint)?;
+
         self.listing.set_inner(Listing {
             maker: self.maker.key(),
             mint: self.maker_mint.key(),
             price,
             bump: bumps.listing,
+            kind: ListingKind::FixedPrice,
+            expires_at,
+            payment_mint,
+            escrowless: false,
+            programmable: is_programmable(&self.metadata),
+            merkle_tree: None,
+        });
+
+        Ok(())
+    }
+
+    // Store details about an English auction. Bids start at `reserve_price`.
+    pub fn create_english_auction(
+        &mut self,
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: i64,
+        extension: i64,
+        bumps: &ListBumps,
+    ) -> Result<()> {
+        // Settling is permissionless, and has no room for the accounts of pNFTs.
+        require!(
+            !is_programmable(&self.metadata),
+            MarketplaceError::UnsupportedTokenStandard
+        );
+        // Outbid bidders are refunded their bid, which has to be enough for an
+        // account that has since been emptied to hold.
+        require!(
+            reserve_price >= Rent::get()?.minimum_balance(0)
+                && min_increment > 0
+                && extension >= 0
+                && end_time > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidAuction
+        );
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: self.maker_mint.key(),
+            price: reserve_price,
+            bump: bumps.listing,
+            kind: ListingKind::English {
+                min_increment,
+                end_time,
+                extension,
+                highest_bid: 0,
+                highest_bidder: None,
+            },
+            expires_at: None,
+            payment_mint: None,
+            escrowless: false,
+            programmable: is_programmable(&self.metadata),
+            merkle_tree: None,
+        });
+
+        Ok(())
+    }
+
+    // Store details about a Dutch auction.
+    pub fn create_dutch_auction(
+        &mut self,
+        start_price: u64,
+        end_price: u64,
+        start_time: i64,
+        end_time: i64,
+        bumps: &ListBumps,
+    ) -> Result<()> {
+        require!(
+            start_price > end_price && end_price > 0 && end_time > start_time,
+            MarketplaceError::InvalidAuction
+        );
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: self.maker_mint.key(),
+            price: start_price,
+            bump: bumps.listing,
+            kind: ListingKind::Dutch {
+                end_price,
+                start_time,
+                end_time,
+            },
+            expires_at: None,
+            payment_mint: None,
+            escrowless: false,
+            programmable: is_programmable(&self.metadata),
+            merkle_tree: None,
         });
 
         Ok(())
@@ -90,6 +211,10 @@ impl<'info> List<'info> {
 
     // Deposit NFT to the vault.
     pub fn deposit_nft(&mut self) -> Result<()> {
+        if self.listing.programmable {
+            return self.deposit_pnft();
+        }
+
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
@@ -105,4 +230,67 @@ impl<'info> List<'info> {
 
         Ok(())
     }
+
+    // Deposit a programmable NFT to the vault through the Token Metadata program.
+    fn deposit_pnft(&self) -> Result<()> {
+        let (Some(maker_token_record), Some(vault_token_record), Some(sysvar_instructions)) = (
+            &self.maker_token_record,
+            &self.vault_token_record,
+            &self.sysvar_instructions,
+        ) else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        TransferV1Cpi::new(
+            &self.metadata_program.to_account_info(),
+            TransferV1CpiAccounts {
+                token: &self.maker_ata.to_account_info(),
+                token_owner: &self.maker.to_account_info(),
+                destination_token: &self.vault.to_account_info(),
+                destination_owner: &self.listing.to_account_info(),
+                mint: &self.maker_mint.to_account_info(),
+                metadata: &self.metadata.to_account_info(),
+                edition: Some(&self.master_edition.to_account_info()),
+                token_record: Some(maker_token_record),
+                destination_token_record: Some(vault_token_record),
+                authority: &self.maker.to_account_info(),
+                payer: &self.maker.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+                sysvar_instructions,
+                spl_token_program: &self.token_program.to_account_info(),
+                spl_ata_program: &self.associated_token_program.to_account_info(),
+                authorization_rules_program: self.authorization_rules_program.as_deref(),
+                authorization_rules: self.authorization_rules.as_deref(),
+            },
+            TransferV1InstructionArgs {
+                amount: 1,
+                authorization_data: None,
+            },
+        )
+        .invoke()?;
+
+        Ok(())
+    }
+}
+
+// Check the optional terms of a fixed price listing.
+pub(crate) fn check_terms(
+    marketplace: &Marketplace,
+    expires_at: Option<i64>,
+    payment_mint: Option<Pubkey>,
+) -> Result<()> {
+    if let Some(mint) = payment_mint {
+        require!(
+            marketplace.payment_mints.contains(&mint),
+            MarketplaceError::PaymentMintNotAllowed
+        );
+    }
+    if let Some(expires_at) = expires_at {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidExpiry
+        );
+    }
+
+    Ok(())
 }
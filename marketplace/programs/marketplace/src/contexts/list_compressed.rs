@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use mpl_bubblegum::{
+    instructions::{TransferCpi, TransferCpiAccounts},
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+};
+
+use crate::{
+    cnft::CompressedNft,
+    contexts::list::check_terms,
+    errors::MarketplaceError,
+    state::{Listing, ListingKind, Marketplace},
+};
+
+#[derive(Accounts)]
+#[instruction(nft: CompressedNft)]
+pub struct ListCompressed<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    /// CHECK: The tree the NFT is a leaf of, checked by Bubblegum.
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Checked by Bubblegum.
+    #[account(
+        seeds=[merkle_tree.key().as_ref()],
+        seeds::program=mpl_bubblegum::ID,
+        bump
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    // Takes ownership of the leaf.
+    #[account(
+        init,
+        payer=maker,
+        seeds=[
+            b"listing",
+            marketplace.key().as_ref(),
+            nft.asset_id(&merkle_tree.key()).as_ref(),
+        ],
+        bump,
+        space=Listing::INIT_SPACE
+    )]
+    pub listing: Account<'info, Listing>,
+
+    // Has to be the verified collection of the leaf, as for other listings.
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Checked by address.
+    #[account(address=mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ListCompressed<'info> {
+    // Store details about the listing, of an NFT in the verified collection
+    // according to its `metadata`.
+    pub fn create_listing(
+        &mut self,
+        nft: &CompressedNft,
+        metadata: &[u8],
+        price: u64,
+        expires_at: Option<i64>,
+        bumps: &ListCompressedBumps,
+    ) -> Result<()> {
+        require!(
+            nft.collection(metadata)?
+                .is_some_and(|c| c.verified && c.key == self.collection_mint.key()),
+            MarketplaceError::InvalidCollection
+        );
+        check_terms(&self.marketplace, expires_at, None)?;
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: nft.asset_id(&self.merkle_tree.key()),
+            price,
+            bump: bumps.listing,
+            kind: ListingKind::FixedPrice,
+            expires_at,
+            payment_mint: None,
+            escrowless: false,
+            programmable: false,
+            merkle_tree: Some(self.merkle_tree.key()),
+        });
+
+        Ok(())
+    }
+
+    // Transfer the leaf from the maker to the listing. The proof of the leaf is
+    // passed in remaining_accounts. A leaf delegated to someone else has to be
+    // revoked first.
+    pub fn deposit_nft(&mut self, nft: &CompressedNft, proof: &[AccountInfo<'info>]) -> Result<()> {
+        let proof = proof
+            .iter()
+            .map(|node| (node, false, false))
+            .collect::<Vec<_>>();
+
+        TransferCpi::new(
+            &self.bubblegum_program.to_account_info(),
+            TransferCpiAccounts {
+                tree_config: &self.tree_config.to_account_info(),
+                leaf_owner: (&self.maker.to_account_info(), true),
+                leaf_delegate: (&self.maker.to_account_info(), false),
+                new_leaf_owner: &self.listing.to_account_info(),
+                merkle_tree: &self.merkle_tree.to_account_info(),
+                log_wrapper: &self.log_wrapper.to_account_info(),
+                compression_program: &self.compression_program.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+            },
+            nft.transfer_args(),
+        )
+        .invoke_with_remaining_accounts(&proof)?;
+
+        Ok(())
+    }
+}
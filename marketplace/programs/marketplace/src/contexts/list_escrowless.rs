@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{
+        mpl_token_metadata::instructions::{
+            FreezeDelegatedAccountCpi, FreezeDelegatedAccountCpiAccounts,
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
+    token_interface::{approve, Approve, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    contexts::list::check_terms,
+    errors::MarketplaceError,
+    pnft::is_programmable,
+    state::{Listing, ListingKind, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct ListEscrowless<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    // The token that the maker wants to list. It stays in the maker's token
+    // account, frozen with the listing as its delegate.
+    pub maker_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint=maker_mint,
+        associated_token::authority=maker,
+    )]
+    pub maker_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer=maker,
+        seeds=[b"listing", marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump,
+        space=Listing::INIT_SPACE
+    )]
+    pub listing: Account<'info, Listing>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().unwrap().key.as_ref() == collection_mint.key().as_ref(),
+        constraint = metadata.collection.as_ref().unwrap().verified,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Account<'info, MasterEditionAccount>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ListEscrowless<'info> {
+    // Store details about the listing.
+    pub fn create_listing(
+        &mut self,
+        price: u64,
+        expires_at: Option<i64>,
+        payment_mint: Option<Pubkey>,
+        bumps: &ListEscrowlessBumps,
+    ) -> Result<()> {
+        // A pNFT can only be locked in place by making the listing its sale
+        // delegate, which the maker can revoke while the listing stays up, so the
+        // listing couldn't be relied on. pNFTs are listed with `list` instead.
+        require!(
+            !is_programmable(&self.metadata),
+            MarketplaceError::EscrowlessProgrammable
+        );
+        check_terms(&self.marketplace, expires_at, payment_mint)?;
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: self.maker_mint.key(),
+            price,
+            bump: bumps.listing,
+            kind: ListingKind::FixedPrice,
+            expires_at,
+            payment_mint,
+            escrowless: true,
+            programmable: false,
+            merkle_tree: None,
+        });
+
+        Ok(())
+    }
+
+    // Delegate the NFT to the listing and freeze it in the maker's token account.
+    pub fn freeze_nft(&mut self) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Approve {
+            to: self.maker_ata.to_account_info(),
+            delegate: self.listing.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        approve(cpi_ctx, 1)?;
+
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.maker_mint.key().to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        FreezeDelegatedAccountCpi::new(
+            &self.metadata_program.to_account_info(),
+            FreezeDelegatedAccountCpiAccounts {
+                delegate: &self.listing.to_account_info(),
+                token_account: &self.maker_ata.to_account_info(),
+                edition: &self.master_edition.to_account_info(),
+                mint: &self.maker_mint.to_account_info(),
+                token_program: &self.token_program.to_account_info(),
+            },
+        )
+        .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+}
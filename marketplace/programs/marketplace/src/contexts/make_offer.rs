@@ -0,0 +1,82 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{
+    errors::MarketplaceError,
+    state::{Marketplace, Offer, OfferTarget},
+};
+
+#[derive(Accounts)]
+#[instruction(target: OfferTarget)]
+pub struct MakeOffer<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        init,
+        payer=bidder,
+        seeds=[
+            b"offer",
+            marketplace.key().as_ref(),
+            bidder.key().as_ref(),
+            target.key().as_ref(),
+        ],
+        bump,
+        space=Offer::INIT_SPACE
+    )]
+    pub offer: Account<'info, Offer>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeOffer<'info> {
+    // Store details about the offer.
+    pub fn create_offer(
+        &mut self,
+        target: OfferTarget,
+        price: u64,
+        expires_at: i64,
+        bumps: &MakeOfferBumps,
+    ) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            MarketplaceError::InvalidExpiry
+        );
+
+        self.offer.set_inner(Offer {
+            bidder: self.bidder.key(),
+            marketplace: self.marketplace.key(),
+            target,
+            price,
+            expires_at,
+            bump: bumps.offer,
+        });
+
+        Ok(())
+    }
+
+    // Escrow the price in the offer account.
+    pub fn deposit_sol(&mut self) -> Result<()> {
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.bidder.to_account_info(),
+            to: self.offer.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer(cpi_ctx, self.offer.price)?;
+
+        Ok(())
+    }
+}
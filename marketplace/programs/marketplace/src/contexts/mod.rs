@@ -9,3 +9,42 @@ pub use delist::*;
 
 pub mod purchase;
 pub use purchase::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod treasury;
+pub use treasury::*;
+
+pub mod make_offer;
+pub use make_offer::*;
+
+pub mod cancel_offer;
+pub use cancel_offer::*;
+
+pub mod accept_offer;
+pub use accept_offer::*;
+
+pub mod bid;
+pub use bid::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod update_listing;
+pub use update_listing::*;
+
+pub mod expire_listing;
+pub use expire_listing::*;
+
+pub mod list_escrowless;
+pub use list_escrowless::*;
+
+pub mod list_compressed;
+pub use list_compressed::*;
+
+pub mod delist_compressed;
+pub use delist_compressed::*;
+
+pub mod purchase_compressed;
+pub use purchase_compressed::*;
@@ -3,14 +3,27 @@ use anchor_lang::{
     system_program::{transfer, Transfer},
 };
 use anchor_spl::{
-    associated_token::AssociatedToken,
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    metadata::{
+        mpl_token_metadata::instructions::{
+            ThawDelegatedAccountCpi, ThawDelegatedAccountCpiAccounts, TransferV1Cpi,
+            TransferV1CpiAccounts, TransferV1InstructionArgs,
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
     token_interface::{
         close_account, mint_to, transfer_checked, CloseAccount, Mint, MintTo, TokenAccount,
         TokenInterface, TransferChecked,
     },
 };
 
-use crate::state::{Listing, Marketplace};
+use crate::{
+    errors::MarketplaceError,
+    settlement::settle,
+    state::{Listing, Marketplace},
+};
 
 #[derive(Accounts)]
 pub struct Purchase<'info> {
@@ -21,7 +34,7 @@ pub struct Purchase<'info> {
     pub maker: SystemAccount<'info>,
 
     #[account(
-        seeds=[b"marketplace", marketplace.name.as_str().as_bytes()],
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
         bump=marketplace.bump
     )]
     pub marketplace: Account<'info, Marketplace>,
@@ -35,15 +48,24 @@ pub struct Purchase<'info> {
     )]
     pub taker_ata: InterfaceAccount<'info, TokenAccount>,
 
+    // Holds the NFT, or the maker's token account does if the listing is
+    // escrowless.
     #[account(
         mut,
         associated_token::mint=maker_mint,
         associated_token::authority=listing
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        mut,
+        associated_token::mint=maker_mint,
+        associated_token::authority=maker
+    )]
+    pub maker_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
+        has_one=maker,
         seeds=[b"listing", marketplace.key().as_ref(), maker_mint.key().as_ref()],
         bump=listing.bump,
         close=maker
@@ -51,6 +73,7 @@ pub struct Purchase<'info> {
     pub listing: Account<'info, Listing>,
 
     #[account(
+        mut,
         seeds=[b"treasury", marketplace.key().as_ref()],
         bump=marketplace.treasury_bump
     )]
@@ -67,45 +90,220 @@ pub struct Purchase<'info> {
 
     #[account(
         init_if_needed,
-        payer=maker,
+        payer=taker,
         associated_token::mint=rewards_mint,
         associated_token::authority=taker
     )]
     pub rewards_ata: InterfaceAccount<'info, TokenAccount>,
 
+    // Token Metadata writes to it when it transfers a pNFT.
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    // Only needed to thaw the NFT of an escrowless listing, or for programmable
+    // NFTs.
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Option<Box<Account<'info, MasterEditionAccount>>>,
+
+    // Only needed for programmable NFTs.
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub vault_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    #[account(mut)]
+    pub taker_token_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: The rule set of the NFT, checked by the Token Metadata program.
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+    /// CHECK: Checked by the Token Metadata program.
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: The instructions sysvar, checked by address.
+    #[account(address=anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub sysvar_instructions: Option<UncheckedAccount<'info>>,
+
+    // Only needed for listings priced in a token.
+    pub payment_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    #[account(
+        mut,
+        associated_token::mint=payment_mint,
+        associated_token::authority=taker,
+        associated_token::token_program=payment_token_program
+    )]
+    pub taker_payment_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        init_if_needed,
+        payer=taker,
+        associated_token::mint=payment_mint,
+        associated_token::authority=maker,
+        associated_token::token_program=payment_token_program
+    )]
+    pub maker_payment_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        init_if_needed,
+        payer=taker,
+        associated_token::mint=payment_mint,
+        associated_token::authority=treasury,
+        associated_token::token_program=payment_token_program
+    )]
+    pub treasury_payment_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub metadata_program: Program<'info, Metadata>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Purchase<'info> {
-    // Transfer SOL from taker to maker.
-    pub fn transfer_sol(&self) -> Result<()> {
-        let marketplace_fee = (self.marketplace.fee as u64)
-            .checked_mul(self.listing.price)
-            .unwrap()
-            .checked_div(10000_u64)
-            .unwrap();
+    // Settle in the payment mint of the listing, or in SOL if it has none.
+    pub fn transfer_payment(
+        &self,
+        max_price: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let price = self
+            .listing
+            .purchase_price(Clock::get()?.unix_timestamp, max_price)?;
+        match self.listing.payment_mint {
+            Some(payment_mint) => self.transfer_tokens(payment_mint, price, remaining_accounts),
+            None => self.transfer_sol(price, remaining_accounts),
+        }
+    }
+
+    // Pay royalties to the verified creators, passed in remaining_accounts in the
+    // order of the metadata, the marketplace fee to the treasury, and the rest of
+    // the price to the maker.
+    fn transfer_sol(&self, price: u64, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let mut settlement = settle(&self.marketplace, &self.metadata, price, creators)?;
+        settlement.fold_unpayable_royalties(&Rent::get()?);
+
+        for (creator, amount) in settlement.royalties {
+            self.pay(creator.clone(), amount)?;
+        }
+        self.pay(self.treasury.to_account_info(), settlement.fee)?;
+        self.pay(self.maker.to_account_info(), settlement.proceeds)?;
+
+        Ok(())
+    }
 
+    fn pay(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
             from: self.taker.to_account_info(),
-            to: self.maker.to_account_info(),
+            to,
         };
 
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
 
-        // Subtract fees from listing price before transferring to maker.
-        let amount = self.listing.price.checked_sub(marketplace_fee).unwrap();
+        transfer(cpi_ctx, amount)?;
 
-        transfer(cpi_ctx, self.listing.price - amount)?;
+        Ok(())
+    }
+
+    // Same split as transfer_sol, from the taker's token account. The wallets of
+    // the verified creators are followed in remaining_accounts by their associated
+    // token accounts, in the same order, which are created if needed.
+    fn transfer_tokens(
+        &self,
+        payment_mint: Pubkey,
+        price: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (Some(mint), Some(taker_ata), Some(maker_ata), Some(treasury_ata), Some(token_program)) = (
+            &self.payment_mint,
+            &self.taker_payment_ata,
+            &self.maker_payment_ata,
+            &self.treasury_payment_ata,
+            &self.payment_token_program,
+        ) else {
+            return err!(MarketplaceError::InvalidPaymentAccounts);
+        };
+        require_keys_eq!(
+            mint.key(),
+            payment_mint,
+            MarketplaceError::InvalidPaymentAccounts
+        );
+        let (creators, creator_atas) = remaining_accounts.split_at(remaining_accounts.len() / 2);
+        require!(
+            creators.len() == creator_atas.len(),
+            MarketplaceError::InvalidPaymentAccounts
+        );
+
+        let settlement = settle(&self.marketplace, &self.metadata, price, creators)?;
+
+        let pay = |to: AccountInfo<'info>, amount: u64| {
+            let cpi_accounts = TransferChecked {
+                from: taker_ata.to_account_info(),
+                to,
+                authority: self.taker.to_account_info(),
+                mint: mint.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+
+            transfer_checked(cpi_ctx, amount, mint.decimals)
+        };
+
+        for ((creator, amount), creator_ata) in settlement.royalties.into_iter().zip(creator_atas) {
+            require_keys_eq!(
+                creator_ata.key(),
+                get_associated_token_address_with_program_id(
+                    creator.key,
+                    &payment_mint,
+                    &token_program.key()
+                ),
+                MarketplaceError::InvalidPaymentAccounts
+            );
+
+            let cpi_accounts = Create {
+                payer: self.taker.to_account_info(),
+                associated_token: creator_ata.clone(),
+                authority: creator.clone(),
+                mint: mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                cpi_accounts,
+            );
+
+            create_idempotent(cpi_ctx)?;
+
+            pay(creator_ata.clone(), amount)?;
+        }
+        pay(treasury_ata.to_account_info(), settlement.fee)?;
+        pay(maker_ata.to_account_info(), settlement.proceeds)?;
 
         Ok(())
     }
 
-    // Transfer NFT from vault to taker.
+    // Transfer NFT from the vault, or thaw it in the maker's token account and
+    // transfer it from there as its delegate, to the taker.
     pub fn transfer_nft(&self) -> Result<()> {
+        if self.listing.programmable {
+            return self.transfer_pnft();
+        }
+
         let seeds = &[
             b"listing",
             &self.marketplace.key().to_bytes()[..],
@@ -114,9 +312,39 @@ impl<'info> Purchase<'info> {
         ];
         let signer_seeds = &[&seeds[..]];
 
-        // Transfer NFT from vault to taker.
+        let from = match self.listing.escrowless {
+            true => {
+                let (Some(maker_ata), Some(master_edition)) =
+                    (&self.maker_ata, &self.master_edition)
+                else {
+                    return err!(MarketplaceError::InvalidListing);
+                };
+
+                ThawDelegatedAccountCpi::new(
+                    &self.metadata_program.to_account_info(),
+                    ThawDelegatedAccountCpiAccounts {
+                        delegate: &self.listing.to_account_info(),
+                        token_account: &maker_ata.to_account_info(),
+                        edition: &master_edition.to_account_info(),
+                        mint: &self.maker_mint.to_account_info(),
+                        token_program: &self.token_program.to_account_info(),
+                    },
+                )
+                .invoke_signed(signer_seeds)?;
+
+                maker_ata.to_account_info()
+            }
+            false => self
+                .vault
+                .as_ref()
+                .ok_or(MarketplaceError::InvalidListing)?
+                .to_account_info(),
+        };
+
+        // Transfer NFT to taker. Spending the delegated amount also clears the
+        // delegation of an escrowless listing.
         let accounts = TransferChecked {
-            from: self.vault.to_account_info(),
+            from,
             to: self.taker_ata.to_account_info(),
             authority: self.listing.to_account_info(),
             mint: self.maker_mint.to_account_info(),
@@ -130,12 +358,75 @@ impl<'info> Purchase<'info> {
 
         transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)?;
 
+        Ok(())
+    }
+
+    // Transfer a programmable NFT from the vault to the taker through the Token
+    // Metadata program. They are never listed escrowless.
+    fn transfer_pnft(&self) -> Result<()> {
+        let (
+            Some(vault),
+            Some(vault_token_record),
+            Some(taker_token_record),
+            Some(master_edition),
+            Some(sysvar_instructions),
+        ) = (
+            &self.vault,
+            &self.vault_token_record,
+            &self.taker_token_record,
+            &self.master_edition,
+            &self.sysvar_instructions,
+        )
+        else {
+            return err!(MarketplaceError::MissingProgrammableAccounts);
+        };
+
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.maker_mint.key().to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        TransferV1Cpi::new(
+            &self.metadata_program.to_account_info(),
+            TransferV1CpiAccounts {
+                token: &vault.to_account_info(),
+                token_owner: &self.listing.to_account_info(),
+                destination_token: &self.taker_ata.to_account_info(),
+                destination_owner: &self.taker.to_account_info(),
+                mint: &self.maker_mint.to_account_info(),
+                metadata: &self.metadata.to_account_info(),
+                edition: Some(&master_edition.to_account_info()),
+                token_record: Some(vault_token_record),
+                destination_token_record: Some(taker_token_record),
+                authority: &self.listing.to_account_info(),
+                payer: &self.taker.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+                sysvar_instructions,
+                spl_token_program: &self.token_program.to_account_info(),
+                spl_ata_program: &self.associated_token_program.to_account_info(),
+                authorization_rules_program: self.authorization_rules_program.as_deref(),
+                authorization_rules: self.authorization_rules.as_deref(),
+            },
+            TransferV1InstructionArgs {
+                amount: 1,
+                authorization_data: None,
+            },
+        )
+        .invoke_signed(signer_seeds)?;
+
+        Ok(())
+    }
+
+    pub fn mint_rewards(&self) -> Result<()> {
         // Reward 1 reward token to the taker.
         // We'll need the marketplace's signature here.
         let seeds = &[
             b"marketplace",
-            self.marketplace.name.as_str().as_bytes(),
-            &[self.listing.bump],
+            self.marketplace.name.as_bytes(),
+            &[self.marketplace.bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
@@ -157,6 +448,16 @@ impl<'info> Purchase<'info> {
     }
 
     pub fn close_vault(&mut self) -> Result<()> {
+        // Escrowless listings have no vault.
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
+        // The Token Metadata program may already have closed the emptied vault of
+        // a programmable NFT.
+        if vault.to_account_info().lamports() == 0 {
+            return Ok(());
+        }
+
         let seeds = &[
             b"listing",
             &self.marketplace.key().to_bytes()[..],
@@ -167,7 +468,7 @@ impl<'info> Purchase<'info> {
 
         // Close the vault.
         let accounts = CloseAccount {
-            account: self.vault.to_account_info(),
+            account: vault.to_account_info(),
             destination: self.maker.to_account_info(),
             authority: self.listing.to_account_info(),
         };
@@ -0,0 +1,214 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface},
+};
+use mpl_bubblegum::{
+    instructions::{TransferCpi, TransferCpiAccounts},
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+};
+
+use crate::{
+    cnft::CompressedNft,
+    errors::MarketplaceError,
+    settlement::{royalty_recipients, settle_with},
+    state::{Listing, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct PurchaseCompressed<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+
+    /// CHECK: The tree the NFT is a leaf of, checked against the listing.
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Checked by Bubblegum.
+    #[account(
+        seeds=[merkle_tree.key().as_ref()],
+        seeds::program=mpl_bubblegum::ID,
+        bump
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one=maker,
+        seeds=[b"listing", marketplace.key().as_ref(), listing.mint.as_ref()],
+        bump=listing.bump,
+        constraint=listing.merkle_tree == Some(merkle_tree.key()) @ MarketplaceError::InvalidListing,
+        close=maker
+    )]
+    pub listing: Box<Account<'info, Listing>>,
+
+    #[account(
+        mut,
+        seeds=[b"treasury", marketplace.key().as_ref()],
+        bump=marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"rewards", marketplace.key().as_ref()],
+        bump=marketplace.rewards_bump,
+        mint::decimals=6,
+        mint::authority=marketplace
+    )]
+    pub rewards_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer=taker,
+        associated_token::mint=rewards_mint,
+        associated_token::authority=taker
+    )]
+    pub rewards_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Checked by address.
+    #[account(address=mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Checked by address.
+    #[account(address=SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PurchaseCompressed<'info> {
+    // Pay royalties, the marketplace fee and the maker like `purchase`, with the
+    // royalty terms of the leaf, which Bubblegum checks when it is transferred.
+    // The accounts of the verified creators come first in remaining_accounts,
+    // and the returned proof of the leaf after them.
+    pub fn transfer_sol<'a>(
+        &self,
+        nft: &CompressedNft,
+        max_price: u64,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<&'a [AccountInfo<'info>]> {
+        require_keys_eq!(
+            nft.asset_id(&self.merkle_tree.key()),
+            self.listing.mint,
+            MarketplaceError::InvalidListing
+        );
+
+        let metadata_creators = nft.metadata_creators();
+        let recipients = royalty_recipients(&self.marketplace, &metadata_creators);
+        require!(
+            remaining_accounts.len() >= recipients,
+            MarketplaceError::InvalidCreators
+        );
+        let (creators, proof) = remaining_accounts.split_at(recipients);
+
+        let price = self
+            .listing
+            .purchase_price(Clock::get()?.unix_timestamp, max_price)?;
+        let mut settlement = settle_with(
+            &self.marketplace,
+            nft.seller_fee_basis_points,
+            &metadata_creators,
+            price,
+            creators,
+        )?;
+        settlement.fold_unpayable_royalties(&Rent::get()?);
+
+        for (creator, amount) in settlement.royalties {
+            self.pay(creator.clone(), amount)?;
+        }
+        self.pay(self.treasury.to_account_info(), settlement.fee)?;
+        self.pay(self.maker.to_account_info(), settlement.proceeds)?;
+
+        Ok(proof)
+    }
+
+    fn pay(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to,
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+
+    // Transfer the leaf from the listing to the taker.
+    pub fn transfer_nft(&self, nft: &CompressedNft, proof: &[AccountInfo<'info>]) -> Result<()> {
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.listing.mint.to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let proof = proof
+            .iter()
+            .map(|node| (node, false, false))
+            .collect::<Vec<_>>();
+
+        TransferCpi::new(
+            &self.bubblegum_program.to_account_info(),
+            TransferCpiAccounts {
+                tree_config: &self.tree_config.to_account_info(),
+                leaf_owner: (&self.listing.to_account_info(), true),
+                leaf_delegate: (&self.listing.to_account_info(), false),
+                new_leaf_owner: &self.taker.to_account_info(),
+                merkle_tree: &self.merkle_tree.to_account_info(),
+                log_wrapper: &self.log_wrapper.to_account_info(),
+                compression_program: &self.compression_program.to_account_info(),
+                system_program: &self.system_program.to_account_info(),
+            },
+            nft.transfer_args(),
+        )
+        .invoke_signed_with_remaining_accounts(signer_seeds, &proof)?;
+
+        Ok(())
+    }
+
+    // Reward 1 reward token to the taker.
+    pub fn mint_rewards(&self) -> Result<()> {
+        let seeds = &[
+            b"marketplace",
+            self.marketplace.name.as_bytes(),
+            &[self.marketplace.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let accounts = MintTo {
+            mint: self.rewards_mint.to_account_info(),
+            to: self.rewards_ata.to_account_info(),
+            authority: self.marketplace.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+
+        mint_to(cpi_ctx, 1)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    errors::MarketplaceError,
+    settlement::settle,
+    state::{Listing, ListingKind, Marketplace},
+};
+
+// Anyone can settle an English auction once it has ended with a bid. Auctions
+// without bids are cancelled with `delist`.
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// CHECK: The highest bidder, checked against the listing. Like a refunded
+    /// bidder in `bid`, it needn't be a system account.
+    pub winner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    pub maker_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer=payer,
+        associated_token::mint=maker_mint,
+        associated_token::authority=winner
+    )]
+    pub winner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint=maker_mint,
+        associated_token::authority=listing
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one=maker,
+        seeds=[b"listing", marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump=listing.bump,
+        close=maker
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        mut,
+        seeds=[b"treasury", marketplace.key().as_ref()],
+        bump=marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleAuction<'info> {
+    // Pay royalties to the verified creators, passed in remaining_accounts in the
+    // order of the metadata, the marketplace fee to the treasury, and the rest of
+    // the winning bid to the maker, out of the listing escrow.
+    pub fn transfer_sol(&self, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let ListingKind::English {
+            end_time,
+            highest_bid,
+            highest_bidder,
+            ..
+        } = self.listing.kind
+        else {
+            return err!(MarketplaceError::NotEnglishAuction);
+        };
+        require!(
+            Clock::get()?.unix_timestamp >= end_time,
+            MarketplaceError::AuctionNotEnded
+        );
+        require!(
+            highest_bidder == Some(self.winner.key()),
+            MarketplaceError::NoBids
+        );
+
+        let mut settlement = settle(&self.marketplace, &self.metadata, highest_bid, creators)?;
+        settlement.fold_unpayable_royalties(&Rent::get()?);
+
+        self.listing.sub_lamports(highest_bid)?;
+        for (creator, amount) in settlement.royalties {
+            creator.add_lamports(amount)?;
+        }
+        self.treasury.add_lamports(settlement.fee)?;
+        self.maker.add_lamports(settlement.proceeds)?;
+
+        Ok(())
+    }
+
+    // Transfer NFT from vault to winner, and close the vault.
+    pub fn transfer_nft(&mut self) -> Result<()> {
+        let seeds = &[
+            b"listing",
+            &self.marketplace.key().to_bytes()[..],
+            &self.maker_mint.key().to_bytes()[..],
+            &[self.listing.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.winner_ata.to_account_info(),
+            authority: self.listing.to_account_info(),
+            mint: self.maker_mint.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)?;
+
+        let accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            signer_seeds,
+        );
+
+        close_account(cpi_ctx)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{errors::MarketplaceError, state::Marketplace};
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one=admin @ MarketplaceError::InvalidAdmin,
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        mut,
+        seeds=[b"treasury", marketplace.key().as_ref()],
+        bump=marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawTreasury<'info> {
+    // Transfer collected fees to the admin. The treasury keeps its rent exemption.
+    pub fn withdraw_treasury(&mut self, amount: u64) -> Result<()> {
+        let available = self
+            .treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, MarketplaceError::InsufficientTreasury);
+
+        let marketplace = self.marketplace.key();
+        let seeds = &[
+            b"treasury",
+            marketplace.as_ref(),
+            &[self.marketplace.treasury_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: self.treasury.to_account_info(),
+            to: self.admin.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+}
+
+// Fees of listings priced in a token are collected in the treasury's associated
+// token account for that mint.
+#[derive(Accounts)]
+pub struct WithdrawTreasuryTokens<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one=admin @ MarketplaceError::InvalidAdmin,
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        seeds=[b"treasury", marketplace.key().as_ref()],
+        bump=marketplace.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint=payment_mint,
+        associated_token::authority=treasury,
+        associated_token::token_program=token_program
+    )]
+    pub treasury_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint=payment_mint,
+        token::token_program=token_program
+    )]
+    pub admin_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WithdrawTreasuryTokens<'info> {
+    pub fn withdraw_treasury_tokens(&mut self, amount: u64) -> Result<()> {
+        require!(
+            amount <= self.treasury_ata.amount,
+            MarketplaceError::InsufficientTreasury
+        );
+
+        let marketplace = self.marketplace.key();
+        let seeds = &[
+            b"treasury",
+            marketplace.as_ref(),
+            &[self.marketplace.treasury_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.treasury_ata.to_account_info(),
+            to: self.admin_ata.to_account_info(),
+            authority: self.treasury.to_account_info(),
+            mint: self.payment_mint.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, amount, self.payment_mint.decimals)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::MarketplaceError,
+    state::{Marketplace, RoyaltyPolicy, MAX_FEE, MAX_PAYMENT_MINTS},
+};
+
+#[derive(Accounts)]
+pub struct UpdateMarketplace<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one=admin @ MarketplaceError::InvalidAdmin,
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+}
+
+impl<'info> UpdateMarketplace<'info> {
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        require!(fee <= MAX_FEE, MarketplaceError::FeeTooHigh);
+
+        self.marketplace.fee = fee;
+
+        Ok(())
+    }
+
+    pub fn update_royalty_policy(&mut self, royalty_policy: RoyaltyPolicy) -> Result<()> {
+        self.marketplace.royalty_policy = royalty_policy;
+
+        Ok(())
+    }
+
+    // Allow listings to be priced in `mint`.
+    pub fn add_payment_mint(&mut self, mint: Pubkey) -> Result<()> {
+        let payment_mints = &mut self.marketplace.payment_mints;
+        require!(
+            !payment_mints.contains(&mint),
+            MarketplaceError::PaymentMintAlreadyAllowed
+        );
+        require!(
+            payment_mints.len() < MAX_PAYMENT_MINTS,
+            MarketplaceError::TooManyPaymentMints
+        );
+
+        payment_mints.push(mint);
+
+        Ok(())
+    }
+
+    // Existing listings priced in `mint` can still be purchased.
+    pub fn remove_payment_mint(&mut self, mint: Pubkey) -> Result<()> {
+        let payment_mints = &mut self.marketplace.payment_mints;
+        let index = payment_mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(MarketplaceError::PaymentMintNotAllowed)?;
+
+        payment_mints.remove(index);
+
+        Ok(())
+    }
+}
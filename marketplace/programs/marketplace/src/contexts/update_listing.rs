@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::MarketplaceError,
+    state::{Listing, ListingKind, Marketplace},
+};
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds=[b"marketplace", marketplace.name.as_bytes()],
+        bump=marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+
+    #[account(
+        mut,
+        has_one=maker,
+        seeds=[b"listing", marketplace.key().as_ref(), listing.mint.as_ref()],
+        bump=listing.bump
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+impl<'info> UpdateListing<'info> {
+    // Change the price and expiry of a fixed-price listing in place.
+    pub fn update_listing(&mut self, price: u64, expires_at: Option<i64>) -> Result<()> {
+        require!(
+            self.listing.kind == ListingKind::FixedPrice,
+            MarketplaceError::NotFixedPrice
+        );
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                MarketplaceError::InvalidExpiry
+            );
+        }
+
+        self.listing.price = price;
+        self.listing.expires_at = expires_at;
+
+        Ok(())
+    }
+}
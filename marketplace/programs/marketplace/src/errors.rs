@@ -4,4 +4,97 @@ use anchor_lang::error_code;
 pub enum MarketplaceError {
     #[msg("The given name is too long")]
     NameTooLong,
+
+    #[msg("The fee must not exceed MAX_FEE basis points")]
+    FeeTooHigh,
+
+    #[msg("The signer is not the marketplace admin")]
+    InvalidAdmin,
+
+    #[msg("The treasury does not hold enough lamports")]
+    InsufficientTreasury,
+
+    #[msg("The creator accounts don't match the verified creators in the metadata")]
+    InvalidCreators,
+
+    #[msg("The royalties and marketplace fee exceed the price")]
+    PriceTooLow,
+
+    #[msg("The price must be greater than zero")]
+    InvalidPrice,
+
+    #[msg("The offer has expired")]
+    OfferExpired,
+
+    #[msg("The expiry must be in the future")]
+    InvalidExpiry,
+
+    #[msg("The NFT doesn't match the offer")]
+    InvalidOfferTarget,
+
+    #[msg("The listing accounts are invalid")]
+    InvalidListing,
+
+    #[msg("The auction parameters are invalid")]
+    InvalidAuction,
+
+    #[msg("The listing can't be purchased at a fixed price")]
+    NotForSale,
+
+    #[msg("The listing is not an English auction")]
+    NotEnglishAuction,
+
+    #[msg("The bid is below the reserve price or the minimum increment")]
+    BidTooLow,
+
+    #[msg("The auction has ended")]
+    AuctionEnded,
+
+    #[msg("The auction has not ended")]
+    AuctionNotEnded,
+
+    #[msg("The auction has bids")]
+    AuctionHasBids,
+
+    #[msg("The auction has no bids")]
+    NoBids,
+
+    #[msg("The listing is not at a fixed price")]
+    NotFixedPrice,
+
+    #[msg("The listing has expired")]
+    ListingExpired,
+
+    #[msg("The listing has not expired")]
+    ListingNotExpired,
+
+    #[msg("The mint is not an accepted payment mint")]
+    PaymentMintNotAllowed,
+
+    #[msg("The mint is already an accepted payment mint")]
+    PaymentMintAlreadyAllowed,
+
+    #[msg("The marketplace accepts MAX_PAYMENT_MINTS payment mints at most")]
+    TooManyPaymentMints,
+
+    #[msg("The payment accounts are invalid")]
+    InvalidPaymentAccounts,
+
+    #[msg("The accounts of the programmable NFT are missing")]
+    MissingProgrammableAccounts,
+
+    #[msg("The token standard of the NFT is not supported here")]
+    UnsupportedTokenStandard,
+
+    #[msg("Programmable NFTs can't be listed without escrow")]
+    EscrowlessProgrammable,
+
+    #[msg("The price of the listing is above the taker's maximum")]
+    PriceAboveMax,
+
+    #[msg("The metadata doesn't match the compressed NFT")]
+    InvalidMetadata,
+
+    #[msg("The NFT is not in the verified collection")]
+    InvalidCollection,
 }
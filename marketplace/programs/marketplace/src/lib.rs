@@ -2,11 +2,16 @@ use anchor_lang::prelude::*;
 
 declare_id!("3E2FszhTgmzqxJzYHJpKJBvHxhftzvE3fYNpn1bTiw4a");
 
+pub mod cnft;
 mod contexts;
-mod errors;
-mod state;
+pub mod errors;
+mod pnft;
+mod settlement;
+pub mod state;
 
+use cnft::CompressedNft;
 use contexts::*;
+use state::{OfferTarget, RoyaltyPolicy};
 
 #[program]
 pub mod marketplace {
@@ -14,25 +19,240 @@ pub mod marketplace {
 
     pub fn initialize(ctx: Context<Initialize>, name: String, fee: u16) -> Result<()> {
         ctx.accounts.init(name, fee, &ctx.bumps)?;
+        ctx.accounts.fund_treasury()?;
         Ok(())
     }
 
-    pub fn list(ctx: Context<List>, price: u64) -> Result<()> {
-        ctx.accounts.create_listing(price, &ctx.bumps)?;
+    // `payment_mint` prices the listing in that token instead of SOL. Auctions are
+    // always in SOL.
+    // Programmable NFTs, recognized from their metadata, also take their token
+    // records, rule set and the instructions sysvar, here and wherever they move.
+    pub fn list(
+        ctx: Context<List>,
+        price: u64,
+        expires_at: Option<i64>,
+        payment_mint: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_listing(price, expires_at, payment_mint, &ctx.bumps)?;
         ctx.accounts.deposit_nft()?;
         Ok(())
     }
 
+    // Bids start at `reserve_price`, which has to cover the rent of an empty
+    // account for outbid bidders to always be refundable.
+    pub fn list_english_auction(
+        ctx: Context<List>,
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: i64,
+        extension: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_english_auction(
+            reserve_price,
+            min_increment,
+            end_time,
+            extension,
+            &ctx.bumps,
+        )?;
+        ctx.accounts.deposit_nft()?;
+        Ok(())
+    }
+
+    // Dutch auctions are bought with `purchase` at the current price.
+    pub fn list_dutch_auction(
+        ctx: Context<List>,
+        start_price: u64,
+        end_price: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_dutch_auction(
+            start_price,
+            end_price,
+            start_time,
+            end_time,
+            &ctx.bumps,
+        )?;
+        ctx.accounts.deposit_nft()?;
+        Ok(())
+    }
+
+    // Like `list`, but the NFT stays in the maker's token account, frozen with the
+    // listing as its delegate, instead of moving to a vault. pNFTs can't be
+    // listed this way, see `ListEscrowless::create_listing`.
+    pub fn list_escrowless(
+        ctx: Context<ListEscrowless>,
+        price: u64,
+        expires_at: Option<i64>,
+        payment_mint: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_listing(price, expires_at, payment_mint, &ctx.bumps)?;
+        ctx.accounts.freeze_nft()?;
+        Ok(())
+    }
+
+    // Compressed NFTs are listed at a fixed price in SOL. The listing takes
+    // ownership of the leaf, whose proof is passed in remaining_accounts.
+    // `metadata` is the serialized Bubblegum `MetadataArgs` of the NFT, which
+    // shows its collection.
+    pub fn list_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListCompressed<'info>>,
+        nft: CompressedNft,
+        metadata: Vec<u8>,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_listing(&nft, &metadata, price, expires_at, &ctx.bumps)?;
+        ctx.accounts.deposit_nft(&nft, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    // Also cancels auctions, as long as they have no bids.
     pub fn delist(ctx: Context<Delist>) -> Result<()> {
         ctx.accounts.withdraw_nft()?;
         ctx.accounts.close_vault()?;
         Ok(())
     }
 
-    pub fn purchase(ctx: Context<Purchase>) -> Result<()> {
-        ctx.accounts.transfer_sol()?;
+    // Takes the proof of the leaf in remaining_accounts, like `list_compressed`.
+    // Permissionless once the listing has expired, like `expire_listing`.
+    pub fn delist_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, DelistCompressed<'info>>,
+        nft: CompressedNft,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_nft(&nft, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn update_listing(
+        ctx: Context<UpdateListing>,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts.update_listing(price, expires_at)?;
+        Ok(())
+    }
+
+    // Permissionless once the listing has expired.
+    pub fn expire_listing(ctx: Context<ExpireListing>) -> Result<()> {
+        ctx.accounts.withdraw_nft()?;
+        ctx.accounts.close_vault()?;
+        Ok(())
+    }
+
+    // The accounts of the verified creators are passed in remaining_accounts, in
+    // the order of the NFT metadata, unless the marketplace disables royalties.
+    // Listings priced in a token also take the creators' token accounts after
+    // them, in the same order. Fails if the price is above `max_price`, which
+    // the maker may have raised with `update_listing` since the taker saw it.
+    pub fn purchase<'info>(
+        ctx: Context<'_, '_, '_, 'info, Purchase<'info>>,
+        max_price: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .transfer_payment(max_price, ctx.remaining_accounts)?;
         ctx.accounts.transfer_nft()?;
+        ctx.accounts.mint_rewards()?;
         ctx.accounts.close_vault()?;
         Ok(())
     }
+
+    // The royalties are paid by the terms in `nft`, which Bubblegum checks
+    // against the leaf. Takes the creator accounts like `purchase`, followed by
+    // the proof of the leaf. Fails above `max_price` like `purchase`.
+    pub fn purchase_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, PurchaseCompressed<'info>>,
+        nft: CompressedNft,
+        max_price: u64,
+    ) -> Result<()> {
+        let proof = ctx
+            .accounts
+            .transfer_sol(&nft, max_price, ctx.remaining_accounts)?;
+        ctx.accounts.transfer_nft(&nft, proof)?;
+        ctx.accounts.mint_rewards()?;
+        Ok(())
+    }
+
+    pub fn bid(ctx: Context<Bid>, amount: u64) -> Result<()> {
+        ctx.accounts.place_bid(amount)?;
+        Ok(())
+    }
+
+    // Takes the creator accounts like `purchase`.
+    pub fn settle_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>,
+    ) -> Result<()> {
+        // The escrow is paid out by moving lamports directly, which has to come
+        // after the CPIs of the NFT transfer.
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.transfer_sol(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn make_offer(
+        ctx: Context<MakeOffer>,
+        target: OfferTarget,
+        price: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_offer(target, price, expires_at, &ctx.bumps)?;
+        ctx.accounts.deposit_sol()?;
+        Ok(())
+    }
+
+    pub fn cancel_offer(_ctx: Context<CancelOffer>) -> Result<()> {
+        Ok(())
+    }
+
+    // Takes the listing and vault if the NFT is listed, or else the seller's
+    // token account, and the creator accounts like `purchase`. Escrowless
+    // listings take the seller's token account and the master edition instead of
+    // the vault.
+    pub fn accept_offer<'info>(ctx: Context<'_, '_, '_, 'info, AcceptOffer<'info>>) -> Result<()> {
+        ctx.accounts.check_offer()?;
+        // Like `settle_auction`, the NFT moves before the escrow is paid out.
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.transfer_sol(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn update_fee(ctx: Context<UpdateMarketplace>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)?;
+        Ok(())
+    }
+
+    pub fn update_royalty_policy(
+        ctx: Context<UpdateMarketplace>,
+        royalty_policy: RoyaltyPolicy,
+    ) -> Result<()> {
+        ctx.accounts.update_royalty_policy(royalty_policy)?;
+        Ok(())
+    }
+
+    pub fn add_payment_mint(ctx: Context<UpdateMarketplace>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.add_payment_mint(mint)?;
+        Ok(())
+    }
+
+    pub fn remove_payment_mint(ctx: Context<UpdateMarketplace>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.remove_payment_mint(mint)?;
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_treasury(amount)?;
+        Ok(())
+    }
+
+    pub fn withdraw_treasury_tokens(
+        ctx: Context<WithdrawTreasuryTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_treasury_tokens(amount)?;
+        Ok(())
+    }
 }
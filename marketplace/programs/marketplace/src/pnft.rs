@@ -0,0 +1,13 @@
+use anchor_spl::metadata::{mpl_token_metadata::types::TokenStandard, MetadataAccount};
+
+// Programmable NFTs stay frozen in their token accounts, and can only be moved
+// or delegated through the Token Metadata program, which keeps their token
+// records and checks their rule set.
+pub fn is_programmable(metadata: &MetadataAccount) -> bool {
+    matches!(
+        metadata.token_standard,
+        Some(
+            TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition
+        )
+    )
+}
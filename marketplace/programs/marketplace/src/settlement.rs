@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::{mpl_token_metadata::types::Creator, MetadataAccount};
+
+use crate::{
+    errors::MarketplaceError,
+    state::{Marketplace, RoyaltyPolicy},
+};
+
+// How the price of a sale is split between the creators, the treasury and the
+// seller.
+pub struct Settlement<'a, 'info> {
+    pub royalties: Vec<(&'a AccountInfo<'info>, u64)>,
+    pub fee: u64,
+    pub proceeds: u64,
+}
+
+impl Settlement<'_, '_> {
+    // A royalty paid in lamports can't leave a creator account with a balance
+    // below its rent exemption, so royalties too small for that go to the seller
+    // rather than failing the sale.
+    pub fn fold_unpayable_royalties(&mut self, rent: &Rent) {
+        for (creator, amount) in self.royalties.iter_mut() {
+            let balance = creator.lamports().saturating_add(*amount);
+            if *amount > 0 && !rent.is_exempt(balance, creator.data_len()) {
+                // Royalties and proceeds add up to at most the price.
+                self.proceeds += *amount;
+                *amount = 0;
+            }
+        }
+    }
+}
+
+// Split a sale at `price` of the NFT with `metadata`. The accounts of the verified
+// creators are passed in `creators`, in the order of the metadata, and are only
+// needed when the marketplace enforces royalties.
+pub fn settle<'a, 'info>(
+    marketplace: &Marketplace,
+    metadata: &MetadataAccount,
+    price: u64,
+    creators: &'a [AccountInfo<'info>],
+) -> Result<Settlement<'a, 'info>> {
+    settle_with(
+        marketplace,
+        metadata.seller_fee_basis_points,
+        metadata.creators.as_deref().unwrap_or_default(),
+        price,
+        creators,
+    )
+}
+
+// Same as `settle`, for an NFT without a metadata account, whose royalty terms
+// are checked elsewhere.
+pub fn settle_with<'a, 'info>(
+    marketplace: &Marketplace,
+    seller_fee_basis_points: u16,
+    metadata_creators: &[Creator],
+    price: u64,
+    creators: &'a [AccountInfo<'info>],
+) -> Result<Settlement<'a, 'info>> {
+    let owed = match marketplace.royalty_policy {
+        RoyaltyPolicy::Enforced => royalties(seller_fee_basis_points, metadata_creators, price),
+        RoyaltyPolicy::Disabled => vec![],
+    };
+    require!(
+        creators.len() == owed.len(),
+        MarketplaceError::InvalidCreators
+    );
+
+    let fee = marketplace.fee_amount(price);
+    let mut proceeds = price - fee;
+    let mut royalties = Vec::with_capacity(owed.len());
+    for ((address, amount), creator) in owed.into_iter().zip(creators) {
+        require_keys_eq!(creator.key(), address, MarketplaceError::InvalidCreators);
+        require!(creator.is_writable, MarketplaceError::InvalidCreators);
+        proceeds = proceeds
+            .checked_sub(amount)
+            .ok_or(MarketplaceError::PriceTooLow)?;
+        royalties.push((creator, amount));
+    }
+
+    Ok(Settlement {
+        royalties,
+        fee,
+        proceeds,
+    })
+}
+
+// Number of creator accounts a sale is settled with.
+pub fn royalty_recipients(marketplace: &Marketplace, creators: &[Creator]) -> usize {
+    match marketplace.royalty_policy {
+        RoyaltyPolicy::Enforced => creators.iter().filter(|creator| creator.verified).count(),
+        RoyaltyPolicy::Disabled => 0,
+    }
+}
+
+// Royalties owed to the verified creators of an NFT sold at `price`, in the
+// order they appear in its metadata. Rounding dust and the shares of unverified
+// creators stay with the seller.
+pub fn royalties(
+    seller_fee_basis_points: u16,
+    creators: &[Creator],
+    price: u64,
+) -> Vec<(Pubkey, u64)> {
+    let total = price as u128 * seller_fee_basis_points.min(10_000) as u128 / 10_000;
+
+    creators
+        .iter()
+        .filter(|creator| creator.verified)
+        .map(|creator| {
+            let amount = total * creator.share.min(100) as u128 / 100;
+            (creator.address, amount as u64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creator(verified: bool, share: u8) -> Creator {
+        Creator {
+            address: Pubkey::new_unique(),
+            verified,
+            share,
+        }
+    }
+
+    #[test]
+    fn royalties_are_split_by_share() {
+        let creators = [creator(true, 70), creator(true, 30)];
+        let royalties = royalties(500, &creators, 1_000_000);
+
+        assert_eq!(
+            royalties,
+            vec![(creators[0].address, 35_000), (creators[1].address, 15_000)]
+        );
+    }
+
+    #[test]
+    fn unverified_creators_are_not_paid() {
+        let creators = [creator(false, 50), creator(true, 50)];
+        let royalties = royalties(1_000, &creators, 999);
+
+        assert_eq!(royalties, vec![(creators[1].address, 49)]);
+    }
+}
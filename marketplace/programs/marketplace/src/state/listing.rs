@@ -1,13 +1,234 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::MarketplaceError;
+
 #[account]
 pub struct Listing {
     pub maker: Pubkey,
-    pub mint: Pubkey,
-    pub price: u64,
+    pub mint: Pubkey, // Or asset id of a compressed NFT.
+    pub price: u64, // Fixed price, reserve price of an English auction or start price of a Dutch one.
     pub bump: u8,
+    pub kind: ListingKind,
+    pub expires_at: Option<i64>, // After which it can't be purchased and anyone can return the NFT.
+    pub payment_mint: Option<Pubkey>, // Mint the price is in, or SOL if none.
+    pub escrowless: bool, // Whether the NFT stays frozen in the maker's token account instead of a vault.
+    pub programmable: bool, // Whether the NFT is a pNFT, moved through the Token Metadata program.
+    pub merkle_tree: Option<Pubkey>, // Bubblegum tree of a compressed NFT, which the listing owns a leaf of.
 }
 
 impl Space for Listing {
-    const INIT_SPACE: usize = 8 + 32 + 32 + 8 + 1;
+    const INIT_SPACE: usize = 8
+        + 32
+        + 32
+        + 8
+        + 1
+        + (1 + 8 + 8 + 8 + 8 + (1 + 32))
+        + (1 + 8)
+        + (1 + 32)
+        + 1
+        + 1
+        + (1 + 32);
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListingKind {
+    FixedPrice,
+    // The highest bid is escrowed in the listing account until the auction is
+    // settled or outbid. A bid within `extension` seconds of the end pushes the
+    // end back to `extension` seconds after the bid.
+    English {
+        min_increment: u64,
+        end_time: i64,
+        extension: i64,
+        highest_bid: u64,
+        highest_bidder: Option<Pubkey>,
+    },
+    // The price falls linearly from `price` at `start_time` to `end_price` at
+    // `end_time`, and stays there.
+    Dutch {
+        end_price: u64,
+        start_time: i64,
+        end_time: i64,
+    },
+}
+
+impl Listing {
+    // Price a purchase pays at `now`. English auctions can only be bid on.
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        require!(!self.is_expired(now), MarketplaceError::ListingExpired);
+
+        match self.kind {
+            ListingKind::FixedPrice => Ok(self.price),
+            ListingKind::Dutch {
+                end_price,
+                start_time,
+                end_time,
+            } => {
+                let elapsed = now.clamp(start_time, end_time) - start_time;
+                let decay = (self.price - end_price) as u128 * elapsed as u128
+                    / (end_time - start_time) as u128;
+                Ok(self.price - decay as u64)
+            }
+            ListingKind::English { .. } => err!(MarketplaceError::NotForSale),
+        }
+    }
+
+    // The current price, unless the maker raised it above what the taker agreed
+    // to pay.
+    pub fn purchase_price(&self, now: i64, max_price: u64) -> Result<u64> {
+        let price = self.current_price(now)?;
+        require!(price <= max_price, MarketplaceError::PriceAboveMax);
+
+        Ok(price)
+    }
+
+    // Record a bid of `amount` by `bidder` at `now`, and return the bid it
+    // outbids, which has to be refunded.
+    pub fn bid(&mut self, bidder: Pubkey, amount: u64, now: i64) -> Result<Option<(Pubkey, u64)>> {
+        let reserve_price = self.price;
+        let ListingKind::English {
+            min_increment,
+            end_time,
+            extension,
+            highest_bid,
+            highest_bidder,
+        } = &mut self.kind
+        else {
+            return err!(MarketplaceError::NotEnglishAuction);
+        };
+
+        require!(now < *end_time, MarketplaceError::AuctionEnded);
+        let min_bid = match highest_bidder {
+            Some(_) => highest_bid
+                .checked_add(*min_increment)
+                .ok_or(MarketplaceError::BidTooLow)?,
+            None => reserve_price,
+        };
+        require!(amount >= min_bid, MarketplaceError::BidTooLow);
+
+        let outbid = highest_bidder.map(|bidder| (bidder, *highest_bid));
+        *highest_bid = amount;
+        *highest_bidder = Some(bidder);
+        *end_time = (*end_time).max(now + *extension);
+
+        Ok(outbid)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    // Whether an English auction has a bid, so it has to be settled rather than
+    // cancelled.
+    pub fn has_bids(&self) -> bool {
+        matches!(
+            self.kind,
+            ListingKind::English {
+                highest_bidder: Some(_),
+                ..
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(price: u64, kind: ListingKind) -> Listing {
+        Listing {
+            maker: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            price,
+            bump: 0,
+            kind,
+            expires_at: None,
+            payment_mint: None,
+            escrowless: false,
+            programmable: false,
+            merkle_tree: None,
+        }
+    }
+
+    #[test]
+    fn purchases_pay_at_most_their_max_price() {
+        let listing = listing(1_000, ListingKind::FixedPrice);
+
+        assert_eq!(listing.purchase_price(0, 1_000).unwrap(), 1_000);
+        assert_eq!(listing.purchase_price(0, u64::MAX).unwrap(), 1_000);
+        assert!(listing.purchase_price(0, 999).is_err());
+    }
+
+    #[test]
+    fn dutch_price_decays_linearly() {
+        let listing = listing(
+            1_000,
+            ListingKind::Dutch {
+                end_price: 200,
+                start_time: 100,
+                end_time: 200,
+            },
+        );
+
+        assert_eq!(listing.current_price(0).unwrap(), 1_000);
+        assert_eq!(listing.current_price(150).unwrap(), 600);
+        assert_eq!(listing.current_price(199).unwrap(), 208);
+        assert_eq!(listing.current_price(1_000).unwrap(), 200);
+    }
+
+    #[test]
+    fn english_bids_outbid_by_the_increment() {
+        let mut listing = listing(
+            1_000,
+            ListingKind::English {
+                min_increment: 100,
+                end_time: 1_000,
+                extension: 60,
+                highest_bid: 0,
+                highest_bidder: None,
+            },
+        );
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(listing.bid(alice, 999, 0).is_err());
+        assert_eq!(listing.bid(alice, 1_000, 0).unwrap(), None);
+        assert!(listing.bid(bob, 1_099, 0).is_err());
+        assert_eq!(listing.bid(bob, 1_100, 0).unwrap(), Some((alice, 1_000)));
+        assert!(listing.has_bids());
+    }
+
+    #[test]
+    fn late_english_bids_extend_the_auction() {
+        let mut listing = listing(
+            1_000,
+            ListingKind::English {
+                min_increment: 100,
+                end_time: 1_000,
+                extension: 60,
+                highest_bid: 0,
+                highest_bidder: None,
+            },
+        );
+
+        listing.bid(Pubkey::new_unique(), 1_000, 900).unwrap();
+        listing.bid(Pubkey::new_unique(), 1_100, 980).unwrap();
+        assert!(matches!(
+            listing.kind,
+            ListingKind::English {
+                end_time: 1_040,
+                ..
+            }
+        ));
+
+        assert!(listing.bid(Pubkey::new_unique(), 1_200, 1_040).is_err());
+    }
+
+    #[test]
+    fn expired_listings_have_no_price() {
+        let mut listing = listing(1_000, ListingKind::FixedPrice);
+        listing.expires_at = Some(100);
+
+        assert_eq!(listing.current_price(99).unwrap(), 1_000);
+        assert!(listing.current_price(100).is_err());
+    }
 }
@@ -1,15 +1,38 @@
 use anchor_lang::prelude::*;
 
+// Highest marketplace fee, in basis points.
+pub const MAX_FEE: u16 = 1_000;
+
+// Most mints listings can be priced in, besides SOL.
+pub const MAX_PAYMENT_MINTS: usize = 8;
+
 #[account]
 pub struct Marketplace {
-    pub admin: Pubkey,     // 32
-    pub fee: u16,          // 2
-    pub bump: u8,          // 1
-    pub treasury_bump: u8, // 1
-    pub rewards_bump: u8,  // 1
-    pub name: String,      // 32 bytes only (4 + 32)
+    pub admin: Pubkey,                 // 32
+    pub fee: u16,                      // 2
+    pub royalty_policy: RoyaltyPolicy, // 1
+    pub payment_mints: Vec<Pubkey>,    // 4 + 32 * MAX_PAYMENT_MINTS
+    pub bump: u8,                      // 1
+    pub treasury_bump: u8,             // 1
+    pub rewards_bump: u8,              // 1
+    pub name: String,                  // 32 bytes only (4 + 32)
 }
 
 impl Space for Marketplace {
-    const INIT_SPACE: usize = 8 + 32 + 2 + 1 + 1 + 1 + (4 + 32);
+    const INIT_SPACE: usize = 8 + 32 + 2 + 1 + (4 + 32 * MAX_PAYMENT_MINTS) + 1 + 1 + 1 + (4 + 32);
+}
+
+// Whether sales pay the creator royalties set in the NFT metadata.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoyaltyPolicy {
+    Enforced,
+    Disabled,
+}
+
+impl Marketplace {
+    // Marketplace fee on a sale at `price`, rounded down. Never more than the
+    // price since the fee is capped at `MAX_FEE`.
+    pub fn fee_amount(&self, price: u64) -> u64 {
+        (price as u128 * self.fee as u128 / 10_000) as u64
+    }
 }
@@ -3,3 +3,6 @@ pub use marketplace::*;
 
 pub mod listing;
 pub use listing::*;
+
+pub mod offer;
+pub use offer::*;
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+// A bid of `price` lamports, escrowed in the offer account on top of its rent,
+// for the NFT or any verified NFT of the collection in `target`.
+#[account]
+pub struct Offer {
+    pub bidder: Pubkey,
+    pub marketplace: Pubkey,
+    pub target: OfferTarget,
+    pub price: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Space for Offer {
+    const INIT_SPACE: usize = 8 + 32 + 32 + (1 + 32) + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OfferTarget {
+    Mint(Pubkey),
+    Collection(Pubkey),
+}
+
+impl OfferTarget {
+    // The mint of the NFT or of the collection, used in the offer seeds.
+    pub fn key(&self) -> Pubkey {
+        match self {
+            OfferTarget::Mint(mint) | OfferTarget::Collection(mint) => *mint,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use anchor_lang::{
+    prelude::{Pubkey, Rent},
+    solana_program::{instruction::InstructionError, native_token::LAMPORTS_PER_SOL},
+};
+use marketplace::errors::MarketplaceError;
+use marketplace_test::{
+    account::Account,
+    market::{ata, TestMarketplace},
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const RESERVE: u64 = LAMPORTS_PER_SOL;
+const INCREMENT: u64 = LAMPORTS_PER_SOL / 10;
+
+// Hand the account of `user` to another program, as happens to an account
+// that changes hands while its bid stands.
+fn reassign(market: &mut TestMarketplace, user: &Keypair) {
+    let mut account = market.svm.account(&user.pubkey()).unwrap();
+    account.owner = Pubkey::new_unique();
+    market.svm.set_account(&user.pubkey(), &account);
+}
+
+#[test]
+fn bids_refund_the_outbid_bidder() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let first = market.user();
+    let second = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let end_time = market.svm.clock().unix_timestamp + 1_000;
+
+    let list = market.list_english_auction_instruction(
+        &maker.pubkey(),
+        &nft,
+        RESERVE,
+        INCREMENT,
+        end_time,
+    );
+    market.process(&[list], &[&maker]).unwrap();
+
+    let bid = market.bid_instruction(&first.pubkey(), &nft, RESERVE - 1, None);
+    assert!(market.process(&[bid], &[&first]).is_err());
+
+    let before = market.svm.lamports(&first.pubkey());
+    let bid = market.bid_instruction(&first.pubkey(), &nft, RESERVE, None);
+    market.process(&[bid], &[&first]).unwrap();
+    assert_eq!(before - market.svm.lamports(&first.pubkey()), RESERVE);
+
+    let amount = RESERVE + INCREMENT;
+    let bid = market.bid_instruction(&second.pubkey(), &nft, amount - 1, Some(first.pubkey()));
+    assert!(market.process(&[bid], &[&second]).is_err());
+    // The refund has to go to the highest bidder.
+    let bid = market.bid_instruction(&second.pubkey(), &nft, amount, Some(maker.pubkey()));
+    assert!(market.process(&[bid], &[&second]).is_err());
+    let bid = market.bid_instruction(&second.pubkey(), &nft, amount, None);
+    assert!(market.process(&[bid], &[&second]).is_err());
+
+    let bid = market.bid_instruction(&second.pubkey(), &nft, amount, Some(first.pubkey()));
+    market.process(&[bid], &[&second]).unwrap();
+    assert_eq!(market.svm.lamports(&first.pubkey()), before);
+}
+
+#[test]
+fn bids_refund_a_bidder_that_is_no_longer_a_system_account() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let first = market.user();
+    let second = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let end_time = market.svm.clock().unix_timestamp + 1_000;
+
+    let list = market.list_english_auction_instruction(
+        &maker.pubkey(),
+        &nft,
+        RESERVE,
+        INCREMENT,
+        end_time,
+    );
+    let bid = market.bid_instruction(&first.pubkey(), &nft, RESERVE, None);
+    market.process(&[list], &[&maker]).unwrap();
+    market.process(&[bid], &[&first]).unwrap();
+
+    reassign(&mut market, &first);
+    let before = market.svm.lamports(&first.pubkey());
+    let amount = RESERVE + INCREMENT;
+    let bid = market.bid_instruction(&second.pubkey(), &nft, amount, Some(first.pubkey()));
+    market.process(&[bid], &[&second]).unwrap();
+
+    assert_eq!(market.svm.lamports(&first.pubkey()) - before, RESERVE);
+}
+
+#[test]
+fn reserves_cover_the_refund_of_an_emptied_account() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let first = market.user();
+    let second = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let end_time = market.svm.clock().unix_timestamp + 1_000;
+    let reserve = Rent::default().minimum_balance(0);
+
+    let list = market.list_english_auction_instruction(
+        &maker.pubkey(),
+        &nft,
+        reserve - 1,
+        INCREMENT,
+        end_time,
+    );
+    assert_eq!(
+        market.process(&[list], &[&maker]).unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MarketplaceError::InvalidAuction.into())
+        )
+    );
+
+    let list = market.list_english_auction_instruction(
+        &maker.pubkey(),
+        &nft,
+        reserve,
+        INCREMENT,
+        end_time,
+    );
+    let bid = market.bid_instruction(&first.pubkey(), &nft, reserve, None);
+    market.process(&[list], &[&maker]).unwrap();
+    market.process(&[bid], &[&first]).unwrap();
+
+    // The first bidder spends everything they have left.
+    market.svm.set_account(&first.pubkey(), &Account::default());
+    let bid = market.bid_instruction(
+        &second.pubkey(),
+        &nft,
+        reserve + INCREMENT,
+        Some(first.pubkey()),
+    );
+    market.process(&[bid], &[&second]).unwrap();
+
+    assert_eq!(market.svm.lamports(&first.pubkey()), reserve);
+}
+
+#[test]
+fn settle_auction_pays_the_maker_and_sends_the_nft_to_the_winner() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let winner = market.user();
+    let payer = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let end_time = market.svm.clock().unix_timestamp + 1_000;
+
+    let list = market.list_english_auction_instruction(
+        &maker.pubkey(),
+        &nft,
+        RESERVE,
+        INCREMENT,
+        end_time,
+    );
+    let bid = market.bid_instruction(&winner.pubkey(), &nft, RESERVE, None);
+    market.process(&[list], &[&maker]).unwrap();
+    market.process(&[bid], &[&winner]).unwrap();
+
+    let settle = market.settle_auction_instruction(
+        &payer.pubkey(),
+        &maker.pubkey(),
+        &winner.pubkey(),
+        &nft,
+        &[],
+    );
+    assert!(market
+        .process(std::slice::from_ref(&settle), &[&payer])
+        .is_err());
+
+    market.svm.set_clock(end_time);
+    let wrong = market.settle_auction_instruction(
+        &payer.pubkey(),
+        &maker.pubkey(),
+        &payer.pubkey(),
+        &nft,
+        &[],
+    );
+    assert!(market.process(&[wrong], &[&payer]).is_err());
+
+    // The winner's account changing hands doesn't hold up the settlement.
+    reassign(&mut market, &winner);
+    let listing = market.listing(&nft.mint);
+    let vault = ata(&listing, &nft.mint);
+    // The listing, less the escrowed bid, and the vault are closed to the maker.
+    let rent = market.svm.lamports(&listing) - RESERVE + market.svm.lamports(&vault);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    market.process(&[settle], &[&payer]).unwrap();
+
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - maker_before,
+        RESERVE - RESERVE / 40 + rent
+    );
+    assert_eq!(
+        market.svm.token_balance(&ata(&winner.pubkey(), &nft.mint)),
+        1
+    );
+    assert!(market.svm.account(&listing).is_none());
+}
@@ -0,0 +1,271 @@
+use anchor_lang::{
+    error::ErrorCode,
+    prelude::Pubkey,
+    solana_program::{instruction::InstructionError, native_token::LAMPORTS_PER_SOL},
+};
+use marketplace::{cnft::CompressedNft, errors::MarketplaceError, state::Listing};
+use marketplace_test::{
+    market::{ata, TestMarketplace},
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    tree::TestTree,
+};
+use mpl_bubblegum::types::Collection;
+
+// 2.5%
+const FEE: u16 = 250;
+const PRICE: u64 = 10 * LAMPORTS_PER_SOL;
+
+// Bubblegum's answer to a leaf that isn't in the tree as given.
+const NOT_IN_TREE: TransactionError =
+    TransactionError::InstructionError(0, InstructionError::InvalidArgument);
+
+// A tree with a leaf of someone else, and a compressed NFT of `maker` after it
+// whose royalties go to a funded creator.
+fn minted_cnft(market: &mut TestMarketplace, maker: &Keypair) -> (TestTree, CompressedNft, Pubkey) {
+    let mut tree = market.create_tree();
+    let creator = market.user().pubkey();
+    market.mint_cnft(&mut tree, &Pubkey::new_unique(), 0, &[]);
+    let nft = market.mint_cnft(&mut tree, &maker.pubkey(), 500, &[(creator, 100)]);
+
+    (tree, nft, creator)
+}
+
+fn listed_cnft(market: &mut TestMarketplace, maker: &Keypair) -> (TestTree, CompressedNft, Pubkey) {
+    listed_cnft_until(market, maker, None)
+}
+
+fn listed_cnft_until(
+    market: &mut TestMarketplace,
+    maker: &Keypair,
+    expires_at: Option<i64>,
+) -> (TestTree, CompressedNft, Pubkey) {
+    let (mut tree, nft, creator) = minted_cnft(market, maker);
+    let list = market.list_compressed_instruction(&maker.pubkey(), &tree, &nft, PRICE, expires_at);
+    market.process(&[list], &[maker]).unwrap();
+    tree.set_owner(&nft, &market.listing(&nft.asset_id(&tree.address)));
+
+    (tree, nft, creator)
+}
+
+#[test]
+fn list_and_delist_move_the_leaf() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let (tree, nft, _) = listed_cnft(&mut market, &maker);
+    let listing = market.listing(&nft.asset_id(&tree.address));
+
+    // The listing owns the leaf.
+    assert_eq!(market.tree_root(&tree), tree.root());
+    let state: Listing = market.svm.anchor_account(&listing);
+    assert_eq!(state.merkle_tree, Some(tree.address));
+    assert_eq!(state.price, PRICE);
+
+    let mut tree = tree;
+    let delist =
+        market.delist_compressed_instruction(&maker.pubkey(), &maker.pubkey(), &tree, &nft);
+    market.process(&[delist], &[&maker]).unwrap();
+    tree.set_owner(&nft, &maker.pubkey());
+
+    assert_eq!(market.tree_root(&tree), tree.root());
+    assert!(market.svm.account(&listing).is_none());
+}
+
+#[test]
+fn only_the_leaf_owner_lists_it() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let thief = market.user();
+    let (tree, nft, _) = minted_cnft(&mut market, &maker);
+
+    let list = market.list_compressed_instruction(&thief.pubkey(), &tree, &nft, PRICE, None);
+    assert_eq!(market.process(&[list], &[&thief]).unwrap_err(), NOT_IN_TREE);
+
+    assert_eq!(market.tree_root(&tree), tree.root());
+    let listing = market.listing(&nft.asset_id(&tree.address));
+    assert!(market.svm.account(&listing).is_none());
+}
+
+#[test]
+fn purchase_pays_by_the_leaf_and_moves_it_to_the_taker() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let (mut tree, nft, creator) = listed_cnft(&mut market, &maker);
+    let listing = market.listing(&nft.asset_id(&tree.address));
+
+    let listing_rent = market.svm.lamports(&listing);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    let creator_before = market.svm.lamports(&creator);
+    let treasury_before = market.svm.lamports(&market.treasury);
+    let purchase = market.purchase_compressed_instruction(
+        &taker.pubkey(),
+        &maker.pubkey(),
+        &tree,
+        &nft,
+        &[creator],
+    );
+    market.process(&[purchase], &[&taker]).unwrap();
+    tree.set_owner(&nft, &taker.pubkey());
+
+    let (royalty, fee) = (PRICE / 20, PRICE / 40);
+    assert_eq!(market.svm.lamports(&creator) - creator_before, royalty);
+    assert_eq!(market.svm.lamports(&market.treasury) - treasury_before, fee);
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - maker_before,
+        PRICE - royalty - fee + listing_rent
+    );
+    assert_eq!(market.tree_root(&tree), tree.root());
+    assert!(market.svm.account(&listing).is_none());
+    let rewards_ata = ata(&taker.pubkey(), &market.rewards_mint);
+    assert_eq!(market.svm.token_balance(&rewards_ata), 1);
+}
+
+#[test]
+fn purchase_pays_at_most_the_max_price() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let (tree, nft, creator) = listed_cnft(&mut market, &maker);
+    let asset_id = nft.asset_id(&tree.address);
+
+    // The maker raises the price after the taker saw it.
+    let purchase = market.purchase_compressed_instruction(
+        &taker.pubkey(),
+        &maker.pubkey(),
+        &tree,
+        &nft,
+        &[creator],
+    );
+    let update = market.update_listing_instruction(&maker.pubkey(), &asset_id, 2 * PRICE, None);
+    market.process(&[update], &[&maker]).unwrap();
+
+    let taker_before = market.svm.lamports(&taker.pubkey());
+    assert_eq!(
+        market.process(&[purchase], &[&taker]).unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MarketplaceError::PriceAboveMax.into())
+        )
+    );
+    assert_eq!(market.svm.lamports(&taker.pubkey()), taker_before);
+    assert_eq!(market.tree_root(&tree), tree.root());
+}
+
+#[test]
+fn purchase_with_forged_royalties_fails_the_proof() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let (tree, nft, creator) = listed_cnft(&mut market, &maker);
+
+    let no_royalties = CompressedNft {
+        seller_fee_basis_points: 0,
+        ..nft.clone()
+    };
+    let no_creators = CompressedNft {
+        creators: vec![],
+        ..nft.clone()
+    };
+    let taker_before = market.svm.lamports(&taker.pubkey());
+    for (forged, creators) in [(no_royalties, vec![creator]), (no_creators, vec![])] {
+        let purchase = market.purchase_compressed_instruction(
+            &taker.pubkey(),
+            &maker.pubkey(),
+            &tree,
+            &forged,
+            &creators,
+        );
+        assert_eq!(
+            market.process(&[purchase], &[&taker]).unwrap_err(),
+            NOT_IN_TREE
+        );
+    }
+
+    assert_eq!(market.svm.lamports(&taker.pubkey()), taker_before);
+    assert_eq!(market.tree_root(&tree), tree.root());
+}
+
+#[test]
+fn only_the_maker_delists_before_expiry() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let thief = market.user();
+    let expires_at = market.svm.clock().unix_timestamp + 100;
+    let (tree, nft, _) = listed_cnft_until(&mut market, &maker, Some(expires_at));
+
+    let delist =
+        market.delist_compressed_instruction(&thief.pubkey(), &maker.pubkey(), &tree, &nft);
+    assert_eq!(
+        market.process(&[delist], &[&thief]).unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MarketplaceError::ListingNotExpired.into())
+        )
+    );
+    // Nor can anyone send the leaf elsewhere.
+    let delist =
+        market.delist_compressed_instruction(&thief.pubkey(), &thief.pubkey(), &tree, &nft);
+    assert_eq!(
+        market.process(&[delist], &[&thief]).unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(ErrorCode::ConstraintHasOne.into())
+        )
+    );
+
+    // The listing still owns the leaf.
+    assert_eq!(market.tree_root(&tree), tree.root());
+    let listing = market.listing(&nft.asset_id(&tree.address));
+    assert!(market.svm.account(&listing).is_some());
+}
+
+#[test]
+fn anyone_delists_an_expired_listing_to_the_maker() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let payer = market.user();
+    let expires_at = market.svm.clock().unix_timestamp + 100;
+    let (mut tree, nft, _) = listed_cnft_until(&mut market, &maker, Some(expires_at));
+    let listing = market.listing(&nft.asset_id(&tree.address));
+    let rent = market.svm.lamports(&listing);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+
+    market.svm.set_clock(expires_at);
+    let delist =
+        market.delist_compressed_instruction(&payer.pubkey(), &maker.pubkey(), &tree, &nft);
+    market.process(&[delist], &[&payer]).unwrap();
+    tree.set_owner(&nft, &maker.pubkey());
+
+    assert_eq!(market.tree_root(&tree), tree.root());
+    assert!(market.svm.account(&listing).is_none());
+    assert_eq!(market.svm.lamports(&maker.pubkey()) - maker_before, rent);
+}
+
+#[test]
+fn only_cnfts_of_the_verified_collection_are_listed() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let mut tree = market.create_tree();
+    let other = Collection {
+        verified: true,
+        key: Pubkey::new_unique(),
+    };
+    let unverified = Collection {
+        verified: false,
+        key: market.collection_mint,
+    };
+
+    for collection in [None, Some(other), Some(unverified)] {
+        let nft = market.mint_cnft_in(&mut tree, &maker.pubkey(), 0, &[], collection);
+        let list = market.list_compressed_instruction(&maker.pubkey(), &tree, &nft, PRICE, None);
+        assert_eq!(
+            market.process(&[list], &[&maker]).unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(MarketplaceError::InvalidCollection.into())
+            )
+        );
+    }
+    assert_eq!(market.tree_root(&tree), tree.root());
+}
@@ -0,0 +1,51 @@
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_spl::token::spl_token;
+use marketplace_test::{
+    market::{ata, TestMarketplace},
+    signature::Signer,
+};
+
+#[test]
+fn delist_returns_the_nft_and_the_rent() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let before = market.svm.lamports(&maker.pubkey());
+
+    let list = market.list_instruction(&maker.pubkey(), &nft, LAMPORTS_PER_SOL);
+    market.process(&[list], &[&maker]).unwrap();
+    let delist = market.delist_instruction(&maker.pubkey(), &nft);
+    market.process(&[delist], &[&maker]).unwrap();
+
+    let listing = market.listing(&nft.mint);
+    assert_eq!(
+        market.svm.token_balance(&ata(&maker.pubkey(), &nft.mint)),
+        1
+    );
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&ata(&listing, &nft.mint)).is_none());
+    assert_eq!(market.svm.lamports(&maker.pubkey()), before);
+}
+
+#[test]
+fn only_the_maker_can_delist() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let thief = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    market
+        .svm
+        .create_ata(&thief.pubkey(), &nft.mint, &spl_token::ID);
+
+    let list = market.list_instruction(&maker.pubkey(), &nft, LAMPORTS_PER_SOL);
+    market.process(&[list], &[&maker]).unwrap();
+
+    let delist = market.delist_instruction(&thief.pubkey(), &nft);
+    assert!(market.process(&[delist], &[&thief]).is_err());
+    assert_eq!(
+        market
+            .svm
+            .token_balance(&ata(&market.listing(&nft.mint), &nft.mint)),
+        1
+    );
+}
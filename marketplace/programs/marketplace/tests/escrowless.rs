@@ -0,0 +1,162 @@
+use anchor_lang::{prelude::Pubkey, solana_program::native_token::LAMPORTS_PER_SOL};
+use anchor_spl::token::spl_token;
+use marketplace::state::OfferTarget;
+use marketplace_test::{
+    market::{ata, Nft, TestMarketplace},
+    signature::{Keypair, Signer},
+};
+
+const PRICE: u64 = 2 * LAMPORTS_PER_SOL;
+
+fn listed_nft(market: &mut TestMarketplace, maker: &Keypair, expires_at: Option<i64>) -> Nft {
+    let nft = market.mint_nft(&maker.pubkey());
+    let list = market.list_escrowless_instruction(&maker.pubkey(), &nft, PRICE, expires_at);
+    market.process(&[list], &[maker]).unwrap();
+    nft
+}
+
+// Whether the NFT sits frozen in the token account of `owner`, with the listing
+// as its delegate.
+fn frozen_by_listing(market: &TestMarketplace, owner: &Pubkey, nft: &Nft) -> bool {
+    let account = market.svm.token_account(&ata(owner, &nft.mint));
+    account.amount == 1
+        && account.is_frozen()
+        && account.delegate == Some(market.listing(&nft.mint)).into()
+}
+
+#[test]
+fn list_escrowless_freezes_the_nft_in_place() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = listed_nft(&mut market, &maker, None);
+
+    assert!(frozen_by_listing(&market, &maker.pubkey(), &nft));
+    assert!(market
+        .svm
+        .account(&ata(&market.listing(&nft.mint), &nft.mint))
+        .is_none());
+
+    // The maker can't move the NFT while it is listed.
+    let other = market.user();
+    let other_ata = market
+        .svm
+        .create_ata(&other.pubkey(), &nft.mint, &spl_token::ID);
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &ata(&maker.pubkey(), &nft.mint),
+        &other_ata,
+        &maker.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    assert!(market.process(&[transfer], &[&maker]).is_err());
+}
+
+#[test]
+fn purchase_thaws_the_nft_and_moves_it_to_the_taker() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let taker = market.user();
+    let nft = listed_nft(&mut market, &maker, None);
+
+    let listing = market.listing(&nft.mint);
+    let rent = market.svm.lamports(&listing);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    let purchase =
+        market.purchase_escrowless_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    let taker_ata = market.svm.token_account(&ata(&taker.pubkey(), &nft.mint));
+    assert_eq!(taker_ata.amount, 1);
+    assert!(!taker_ata.is_frozen());
+    // Spending the delegated amount clears the delegation.
+    let maker_ata = market.svm.token_account(&ata(&maker.pubkey(), &nft.mint));
+    assert_eq!(maker_ata.amount, 0);
+    assert!(!maker_ata.is_frozen());
+    assert!(maker_ata.delegate.is_none());
+
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - maker_before,
+        PRICE - PRICE / 40 + rent
+    );
+    assert!(market.svm.account(&listing).is_none());
+
+    // The vault of a listing is not the way to an escrowless NFT.
+    let nft = listed_nft(&mut market, &maker, None);
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    assert!(market.process(&[purchase], &[&taker]).is_err());
+}
+
+#[test]
+fn accept_offer_thaws_the_nft_and_moves_it_to_the_bidder() {
+    let mut market = TestMarketplace::new(250);
+    let seller = market.user();
+    let bidder = market.user();
+    let nft = listed_nft(&mut market, &seller, None);
+    let target = OfferTarget::Mint(nft.mint);
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE / 2, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+    let accept = market.accept_offer_escrowless_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        &[],
+    );
+    market.process(&[accept], &[&seller]).unwrap();
+
+    let bidder_ata = market.svm.token_account(&ata(&bidder.pubkey(), &nft.mint));
+    assert_eq!(bidder_ata.amount, 1);
+    assert!(!bidder_ata.is_frozen());
+    let seller_ata = market.svm.token_account(&ata(&seller.pubkey(), &nft.mint));
+    assert_eq!(seller_ata.amount, 0);
+    assert!(!seller_ata.is_frozen());
+    assert!(market.svm.account(&market.listing(&nft.mint)).is_none());
+}
+
+#[test]
+fn delist_thaws_the_nft_and_revokes_the_listing() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = listed_nft(&mut market, &maker, None);
+
+    let listing = market.listing(&nft.mint);
+    let rent = market.svm.lamports(&listing);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    let delist = market.delist_escrowless_instruction(&maker.pubkey(), &nft);
+    market.process(&[delist], &[&maker]).unwrap();
+
+    let maker_ata = market.svm.token_account(&ata(&maker.pubkey(), &nft.mint));
+    assert_eq!(maker_ata.amount, 1);
+    assert!(!maker_ata.is_frozen());
+    assert!(maker_ata.delegate.is_none());
+    assert_eq!(market.svm.lamports(&maker.pubkey()) - maker_before, rent);
+    assert!(market.svm.account(&listing).is_none());
+}
+
+#[test]
+fn expire_listing_thaws_the_nft_once_expired() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let payer = market.user();
+    let expires_at = market.svm.clock().unix_timestamp + 100;
+    let nft = listed_nft(&mut market, &maker, Some(expires_at));
+
+    let expire = market.expire_listing_instruction(&payer.pubkey(), &maker.pubkey(), &nft, true);
+    assert!(market
+        .process(std::slice::from_ref(&expire), &[&payer])
+        .is_err());
+
+    market.svm.set_clock(expires_at);
+    market.process(&[expire], &[&payer]).unwrap();
+
+    // The delegation is left for the maker to revoke, and is harmless once the
+    // listing is closed.
+    let maker_ata = market.svm.token_account(&ata(&maker.pubkey(), &nft.mint));
+    assert_eq!(maker_ata.amount, 1);
+    assert!(!maker_ata.is_frozen());
+    assert!(market.svm.account(&market.listing(&nft.mint)).is_none());
+}
@@ -0,0 +1,207 @@
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use marketplace::state::OfferTarget;
+use marketplace_test::{
+    market::{ata, TestMarketplace},
+    signature::Signer,
+};
+
+const PRICE: u64 = 2 * LAMPORTS_PER_SOL;
+
+// 2.5%
+const FEE: u16 = 250;
+
+#[test]
+fn cancel_offer_refunds_the_bidder() {
+    let mut market = TestMarketplace::new(FEE);
+    let bidder = market.user();
+    let target = OfferTarget::Collection(market.collection_mint);
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+
+    let before = market.svm.lamports(&bidder.pubkey());
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+
+    // The price is escrowed in the offer, on top of its rent.
+    let offer = market.offer(&bidder.pubkey(), &target);
+    let rent = market.svm.lamports(&offer) - PRICE;
+    assert_eq!(before - market.svm.lamports(&bidder.pubkey()), PRICE + rent);
+
+    let cancel = market.cancel_offer_instruction(&bidder.pubkey(), &target);
+    market.process(&[cancel], &[&bidder]).unwrap();
+
+    assert_eq!(market.svm.lamports(&bidder.pubkey()), before);
+    assert!(market.svm.account(&offer).is_none());
+}
+
+#[test]
+fn accept_mint_offer_on_an_unlisted_nft() {
+    let mut market = TestMarketplace::new(FEE);
+    let seller = market.user();
+    let bidder = market.user();
+    let nft = market.mint_nft(&seller.pubkey());
+    let target = OfferTarget::Mint(nft.mint);
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+
+    let offer = market.offer(&bidder.pubkey(), &target);
+    let offer_rent = market.svm.lamports(&offer) - PRICE;
+    let seller_before = market.svm.lamports(&seller.pubkey());
+    let bidder_before = market.svm.lamports(&bidder.pubkey());
+    let treasury_before = market.svm.lamports(&market.treasury);
+
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &[],
+    );
+    market.process(&[accept], &[&seller]).unwrap();
+
+    // The seller pays for the bidder's token account.
+    let bidder_ata = ata(&bidder.pubkey(), &nft.mint);
+    let ata_rent = market.svm.lamports(&bidder_ata);
+    let fee = PRICE / 40;
+    assert_eq!(
+        market.svm.lamports(&seller.pubkey()) + ata_rent - seller_before,
+        PRICE - fee
+    );
+    assert_eq!(market.svm.lamports(&market.treasury) - treasury_before, fee);
+    assert_eq!(
+        market.svm.lamports(&bidder.pubkey()) - bidder_before,
+        offer_rent
+    );
+
+    assert_eq!(market.svm.token_balance(&bidder_ata), 1);
+    assert_eq!(
+        market.svm.token_balance(&ata(&seller.pubkey(), &nft.mint)),
+        0
+    );
+    assert!(market.svm.account(&offer).is_none());
+}
+
+#[test]
+fn accept_collection_offer_on_a_listed_nft() {
+    let mut market = TestMarketplace::new(FEE);
+    let seller = market.user();
+    let bidder = market.user();
+    let nft = market.mint_nft(&seller.pubkey());
+    let target = OfferTarget::Collection(market.collection_mint);
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+
+    let list = market.list_instruction(&seller.pubkey(), &nft, 10 * PRICE);
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, expires_at);
+    market.process(&[list], &[&seller]).unwrap();
+    market.process(&[offer], &[&bidder]).unwrap();
+
+    let listing = market.listing(&nft.mint);
+    let vault = ata(&listing, &nft.mint);
+    // The listing and the vault are closed to the seller.
+    let listing_rent = market.svm.lamports(&listing) + market.svm.lamports(&vault);
+    let seller_before = market.svm.lamports(&seller.pubkey());
+
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        true,
+        &[],
+    );
+    market.process(&[accept], &[&seller]).unwrap();
+
+    let bidder_ata = ata(&bidder.pubkey(), &nft.mint);
+    let ata_rent = market.svm.lamports(&bidder_ata);
+    assert_eq!(
+        market.svm.lamports(&seller.pubkey()) + ata_rent - seller_before,
+        PRICE - PRICE / 40 + listing_rent
+    );
+    assert_eq!(market.svm.token_balance(&bidder_ata), 1);
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&vault).is_none());
+    assert!(market
+        .svm
+        .account(&market.offer(&bidder.pubkey(), &target))
+        .is_none());
+}
+
+#[test]
+fn accept_offer_requires_a_matching_nft() {
+    let mut market = TestMarketplace::new(FEE);
+    let seller = market.user();
+    let bidder = market.user();
+    let nft = market.mint_nft(&seller.pubkey());
+    let other = market.mint_nft(&seller.pubkey());
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+
+    // An offer on another mint.
+    let target = OfferTarget::Mint(other.mint);
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &[],
+    );
+    assert!(market.process(&[accept], &[&seller]).is_err());
+
+    // A collection offer on an NFT whose collection is not verified.
+    let target = OfferTarget::Collection(market.collection_mint);
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+    let mut metadata = market.metadata(&nft);
+    metadata.collection.as_mut().unwrap().verified = false;
+    market.set_metadata(&nft, &metadata);
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &[],
+    );
+    assert!(market.process(&[accept], &[&seller]).is_err());
+
+    assert_eq!(
+        market.svm.token_balance(&ata(&seller.pubkey(), &nft.mint)),
+        1
+    );
+}
+
+#[test]
+fn expired_offers_can_only_be_cancelled() {
+    let mut market = TestMarketplace::new(FEE);
+    let seller = market.user();
+    let bidder = market.user();
+    let nft = market.mint_nft(&seller.pubkey());
+    let target = OfferTarget::Mint(nft.mint);
+    let now = market.svm.clock().unix_timestamp;
+
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, now);
+    assert!(market.process(&[offer], &[&bidder]).is_err());
+
+    let before = market.svm.lamports(&bidder.pubkey());
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, now + 100);
+    market.process(&[offer], &[&bidder]).unwrap();
+    market.svm.set_clock(now + 100);
+
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &[],
+    );
+    assert!(market.process(&[accept], &[&seller]).is_err());
+
+    let cancel = market.cancel_offer_instruction(&bidder.pubkey(), &target);
+    market.process(&[cancel], &[&bidder]).unwrap();
+    assert_eq!(market.svm.lamports(&bidder.pubkey()), before);
+}
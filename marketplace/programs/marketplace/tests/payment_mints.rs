@@ -0,0 +1,183 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::{metadata::mpl_token_metadata::types::Creator, token::spl_token};
+use marketplace::state::Marketplace;
+use marketplace_test::{
+    market::{ata, Nft, TestMarketplace},
+    signature::{Keypair, Signer},
+};
+
+const PRICE: u64 = 1_000_000;
+
+// A marketplace that allows listings priced in a new mint, and a taker
+// holding enough of it for a purchase.
+fn market_with_payment_mint() -> (TestMarketplace, Pubkey, Keypair) {
+    // 2.5%
+    let mut market = TestMarketplace::new(250);
+    let admin = market.admin.insecure_clone();
+    let payment_mint = market.svm.create_mint(&spl_token::ID, 6);
+
+    let add = market.add_payment_mint_instruction(&admin.pubkey(), &payment_mint);
+    market.process(&[add], &[&admin]).unwrap();
+
+    let taker = market.user();
+    let taker_ata = market
+        .svm
+        .create_ata(&taker.pubkey(), &payment_mint, &spl_token::ID);
+    market.svm.mint_to(&payment_mint, &taker_ata, PRICE);
+
+    (market, payment_mint, taker)
+}
+
+fn listed_nft(market: &mut TestMarketplace, maker: &Keypair, payment_mint: &Pubkey) -> Nft {
+    let nft = market.mint_nft(&maker.pubkey());
+    let list = market.list_for_tokens_instruction(&maker.pubkey(), &nft, PRICE, payment_mint);
+    market.process(&[list], &[maker]).unwrap();
+    nft
+}
+
+#[test]
+fn payment_mints_are_allowlisted_by_the_admin() {
+    let (mut market, payment_mint, _) = market_with_payment_mint();
+    let admin = market.admin.insecure_clone();
+    let maker = market.user();
+    let other_mint = market.svm.create_mint(&spl_token::ID, 6);
+
+    let add = market.add_payment_mint_instruction(&maker.pubkey(), &other_mint);
+    assert!(market.process(&[add], &[&maker]).is_err());
+    let add = market.add_payment_mint_instruction(&admin.pubkey(), &payment_mint);
+    assert!(market.process(&[add], &[&admin]).is_err());
+    let state: Marketplace = market.svm.anchor_account(&market.marketplace);
+    assert_eq!(state.payment_mints, vec![payment_mint]);
+
+    let nft = market.mint_nft(&maker.pubkey());
+    let list = market.list_for_tokens_instruction(&maker.pubkey(), &nft, PRICE, &other_mint);
+    assert!(market.process(&[list], &[&maker]).is_err());
+
+    let remove = market.remove_payment_mint_instruction(&maker.pubkey(), &payment_mint);
+    assert!(market.process(&[remove], &[&maker]).is_err());
+    let remove = market.remove_payment_mint_instruction(&admin.pubkey(), &payment_mint);
+    market.process(&[remove], &[&admin]).unwrap();
+    let remove = market.remove_payment_mint_instruction(&admin.pubkey(), &payment_mint);
+    assert!(market.process(&[remove], &[&admin]).is_err());
+    let state: Marketplace = market.svm.anchor_account(&market.marketplace);
+    assert!(state.payment_mints.is_empty());
+
+    let list = market.list_for_tokens_instruction(&maker.pubkey(), &nft, PRICE, &payment_mint);
+    assert!(market.process(&[list], &[&maker]).is_err());
+}
+
+#[test]
+fn purchase_splits_the_price_in_the_payment_mint() {
+    let (mut market, payment_mint, taker) = market_with_payment_mint();
+    let maker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+
+    // 5% of royalties, to a creator that already has a token account for the
+    // payment mint and to one that doesn't.
+    let creators = [Pubkey::new_unique(), Pubkey::new_unique()];
+    market
+        .svm
+        .create_ata(&creators[0], &payment_mint, &spl_token::ID);
+    let mut metadata = market.metadata(&nft);
+    metadata.seller_fee_basis_points = 500;
+    metadata.creators = Some(
+        creators
+            .iter()
+            .map(|&address| Creator {
+                address,
+                verified: true,
+                share: 50,
+            })
+            .collect(),
+    );
+    market.set_metadata(&nft, &metadata);
+
+    let list = market.list_for_tokens_instruction(&maker.pubkey(), &nft, PRICE, &payment_mint);
+    market.process(&[list], &[&maker]).unwrap();
+
+    let listing = market.listing(&nft.mint);
+    let rent = market.svm.lamports(&listing) + market.svm.lamports(&ata(&listing, &nft.mint));
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    let purchase = market.purchase_with_tokens_instruction(
+        &taker.pubkey(),
+        &maker.pubkey(),
+        &nft,
+        &payment_mint,
+        &creators,
+    );
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    let token_balance = |owner: &Pubkey| market.svm.token_balance(&ata(owner, &payment_mint));
+    assert_eq!(token_balance(&taker.pubkey()), 0);
+    assert_eq!(token_balance(&creators[0]), PRICE / 40);
+    assert_eq!(token_balance(&creators[1]), PRICE / 40);
+    assert_eq!(token_balance(&market.treasury), PRICE / 40);
+    assert_eq!(token_balance(&maker.pubkey()), PRICE - 3 * PRICE / 40);
+
+    // The maker only gets back the rent of the listing and the vault in SOL.
+    assert_eq!(market.svm.lamports(&maker.pubkey()) - maker_before, rent);
+    assert_eq!(
+        market.svm.token_balance(&ata(&taker.pubkey(), &nft.mint)),
+        1
+    );
+}
+
+#[test]
+fn purchase_needs_the_creators_token_accounts() {
+    let (mut market, payment_mint, taker) = market_with_payment_mint();
+    let maker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let creator = Pubkey::new_unique();
+    let mut metadata = market.metadata(&nft);
+    metadata.seller_fee_basis_points = 500;
+    metadata.creators = Some(vec![Creator {
+        address: creator,
+        verified: true,
+        share: 100,
+    }]);
+    market.set_metadata(&nft, &metadata);
+
+    let list = market.list_for_tokens_instruction(&maker.pubkey(), &nft, PRICE, &payment_mint);
+    market.process(&[list], &[&maker]).unwrap();
+
+    // The token account of someone else.
+    let mut purchase = market.purchase_with_tokens_instruction(
+        &taker.pubkey(),
+        &maker.pubkey(),
+        &nft,
+        &payment_mint,
+        &[creator],
+    );
+    purchase.accounts.last_mut().unwrap().pubkey = ata(&maker.pubkey(), &payment_mint);
+    assert!(market.process(&[purchase], &[&taker]).is_err());
+
+    // Paying in SOL.
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[creator]);
+    assert!(market.process(&[purchase], &[&taker]).is_err());
+}
+
+#[test]
+fn listings_outlive_the_removal_of_their_payment_mint() {
+    let (mut market, payment_mint, taker) = market_with_payment_mint();
+    let admin = market.admin.insecure_clone();
+    let maker = market.user();
+    let nft = listed_nft(&mut market, &maker, &payment_mint);
+
+    let remove = market.remove_payment_mint_instruction(&admin.pubkey(), &payment_mint);
+    market.process(&[remove], &[&admin]).unwrap();
+
+    let purchase = market.purchase_with_tokens_instruction(
+        &taker.pubkey(),
+        &maker.pubkey(),
+        &nft,
+        &payment_mint,
+        &[],
+    );
+    market.process(&[purchase], &[&taker]).unwrap();
+    assert_eq!(
+        market
+            .svm
+            .token_balance(&ata(&maker.pubkey(), &payment_mint)),
+        PRICE - PRICE / 40
+    );
+}
@@ -0,0 +1,173 @@
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{Instruction, InstructionError},
+        native_token::LAMPORTS_PER_SOL,
+    },
+    InstructionData,
+};
+use marketplace::{errors::MarketplaceError, instruction, state::OfferTarget};
+use marketplace_test::{
+    market::{ata, Nft, TestMarketplace},
+    signature::Signer,
+    transaction::TransactionError,
+    ProcessResult,
+};
+
+const PRICE: u64 = 2 * LAMPORTS_PER_SOL;
+
+fn assert_error(result: ProcessResult, error: MarketplaceError) {
+    assert_eq!(
+        result.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error.into()))
+    );
+}
+
+// Whether `owner` holds the pNFT, frozen like pNFTs always are.
+fn holds(market: &TestMarketplace, owner: &Pubkey, nft: &Nft) -> bool {
+    let account = market.svm.token_account(&ata(owner, &nft.mint));
+    account.amount == 1 && account.is_frozen()
+}
+
+#[test]
+fn list_and_delist_move_the_pnft_through_token_metadata() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = market.mint_pnft(&maker.pubkey());
+
+    // Without the token records.
+    let list = market.list_instruction(&maker.pubkey(), &nft, PRICE);
+    assert_error(
+        market.process(&[list], &[&maker]),
+        MarketplaceError::MissingProgrammableAccounts,
+    );
+
+    let list = market.list_pnft_instruction(&maker.pubkey(), &nft, PRICE);
+    market.process(&[list], &[&maker]).unwrap();
+    let listing = market.listing(&nft.mint);
+    assert!(holds(&market, &listing, &nft));
+
+    let delist = market.delist_instruction(&maker.pubkey(), &nft);
+    assert_error(
+        market.process(&[delist], &[&maker]),
+        MarketplaceError::MissingProgrammableAccounts,
+    );
+
+    let delist = market.delist_pnft_instruction(&maker.pubkey(), &nft);
+    market.process(&[delist], &[&maker]).unwrap();
+    assert!(holds(&market, &maker.pubkey(), &nft));
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&ata(&listing, &nft.mint)).is_none());
+}
+
+#[test]
+fn expire_listing_returns_the_pnft_through_token_metadata() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let payer = market.user();
+    let nft = market.mint_pnft(&maker.pubkey());
+    let expires_at = market.svm.clock().unix_timestamp + 100;
+
+    let list = Instruction {
+        data: instruction::List {
+            price: PRICE,
+            expires_at: Some(expires_at),
+            payment_mint: None,
+        }
+        .data(),
+        ..market.list_pnft_instruction(&maker.pubkey(), &nft, PRICE)
+    };
+    market.process(&[list], &[&maker]).unwrap();
+    let listing = market.listing(&nft.mint);
+
+    market.svm.set_clock(expires_at);
+    let expire = market.expire_listing_instruction(&payer.pubkey(), &maker.pubkey(), &nft, false);
+    assert_error(
+        market.process(&[expire], &[&payer]),
+        MarketplaceError::MissingProgrammableAccounts,
+    );
+
+    let expire = market.expire_listing_pnft_instruction(&payer.pubkey(), &maker.pubkey(), &nft);
+    market.process(&[expire], &[&payer]).unwrap();
+    assert!(holds(&market, &maker.pubkey(), &nft));
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&ata(&listing, &nft.mint)).is_none());
+}
+
+#[test]
+fn purchase_moves_the_pnft_through_token_metadata() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let taker = market.user();
+    let nft = market.mint_pnft(&maker.pubkey());
+
+    let list = market.list_pnft_instruction(&maker.pubkey(), &nft, PRICE);
+    market.process(&[list], &[&maker]).unwrap();
+
+    let listing = market.listing(&nft.mint);
+    let rent = market.svm.lamports(&listing) + market.svm.lamports(&ata(&listing, &nft.mint));
+    let maker_before = market.svm.lamports(&maker.pubkey());
+
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    assert_error(
+        market.process(&[purchase], &[&taker]),
+        MarketplaceError::MissingProgrammableAccounts,
+    );
+
+    let purchase = market.purchase_pnft_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    assert!(holds(&market, &taker.pubkey(), &nft));
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - maker_before,
+        PRICE - PRICE / 40 + rent
+    );
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&ata(&listing, &nft.mint)).is_none());
+}
+
+#[test]
+fn pnfts_are_not_listed_escrowless() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = market.mint_pnft(&maker.pubkey());
+
+    let list = market.list_escrowless_instruction(&maker.pubkey(), &nft, PRICE, None);
+    assert_error(
+        market.process(&[list], &[&maker]),
+        MarketplaceError::EscrowlessProgrammable,
+    );
+    assert!(market.svm.account(&market.listing(&nft.mint)).is_none());
+}
+
+#[test]
+fn pnfts_are_not_auctioned_or_sold_to_offers() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let bidder = market.user();
+    let nft = market.mint_pnft(&maker.pubkey());
+
+    let end_time = market.svm.clock().unix_timestamp + 1_000;
+    let list = market.list_english_auction_instruction(&maker.pubkey(), &nft, PRICE, 1, end_time);
+    assert_error(
+        market.process(&[list], &[&maker]),
+        MarketplaceError::UnsupportedTokenStandard,
+    );
+
+    let target = OfferTarget::Mint(nft.mint);
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, PRICE, end_time);
+    market.process(&[offer], &[&bidder]).unwrap();
+    let accept = market.accept_offer_instruction(
+        &maker.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &[],
+    );
+    assert_error(
+        market.process(&[accept], &[&maker]),
+        MarketplaceError::UnsupportedTokenStandard,
+    );
+    assert!(holds(&market, &maker.pubkey(), &nft));
+}
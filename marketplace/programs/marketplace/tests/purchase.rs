@@ -0,0 +1,111 @@
+use anchor_lang::solana_program::{instruction::InstructionError, native_token::LAMPORTS_PER_SOL};
+use marketplace::errors::MarketplaceError;
+use marketplace_test::{
+    market::{ata, TestMarketplace},
+    signature::Signer,
+    transaction::TransactionError,
+};
+
+const PRICE: u64 = 2 * LAMPORTS_PER_SOL;
+
+#[test]
+fn purchase_pays_the_seller_and_the_treasury() {
+    // 2.5%
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let taker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+
+    let list = market.list_instruction(&maker.pubkey(), &nft, PRICE);
+    market.process(&[list], &[&maker]).unwrap();
+    assert_eq!(
+        market.svm.token_balance(&ata(&maker.pubkey(), &nft.mint)),
+        0
+    );
+
+    let listing = market.listing(&nft.mint);
+    let vault = ata(&listing, &nft.mint);
+    // The listing and the vault are closed to the maker.
+    let rent = market.svm.lamports(&listing) + market.svm.lamports(&vault);
+    let maker_before = market.svm.lamports(&maker.pubkey());
+    let treasury_before = market.svm.lamports(&market.treasury);
+
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    let fee = PRICE / 40;
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - maker_before,
+        PRICE - fee + rent
+    );
+    assert_eq!(market.svm.lamports(&market.treasury) - treasury_before, fee);
+
+    assert_eq!(
+        market.svm.token_balance(&ata(&taker.pubkey(), &nft.mint)),
+        1
+    );
+    assert!(market.svm.account(&listing).is_none());
+    assert!(market.svm.account(&vault).is_none());
+}
+
+#[test]
+fn purchase_pays_at_most_the_max_price() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let taker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+    let list = market.list_instruction(&maker.pubkey(), &nft, PRICE);
+    market.process(&[list], &[&maker]).unwrap();
+
+    // The maker raises the price after the taker saw it.
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    let update = market.update_listing_instruction(&maker.pubkey(), &nft.mint, 2 * PRICE, None);
+    market.process(&[update], &[&maker]).unwrap();
+
+    let taker_before = market.svm.lamports(&taker.pubkey());
+    assert_eq!(
+        market.process(&[purchase], &[&taker]).unwrap_err(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(MarketplaceError::PriceAboveMax.into())
+        )
+    );
+    assert_eq!(market.svm.lamports(&taker.pubkey()), taker_before);
+    assert!(market.svm.account(&market.listing(&nft.mint)).is_some());
+}
+
+#[test]
+fn purchase_rewards_the_taker() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let taker = market.user();
+
+    // The taker pays for their rewards account on the first purchase and
+    // reuses it after that.
+    for _ in 0..2 {
+        let nft = market.mint_nft(&maker.pubkey());
+        let list = market.list_instruction(&maker.pubkey(), &nft, PRICE);
+        market.process(&[list], &[&maker]).unwrap();
+
+        let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+        market.process(&[purchase], &[&taker]).unwrap();
+    }
+
+    let rewards_ata = ata(&taker.pubkey(), &market.rewards_mint);
+    assert_eq!(market.svm.token_balance(&rewards_ata), 2);
+    assert_eq!(market.svm.mint_supply(&market.rewards_mint), 2);
+}
+
+#[test]
+fn list_requires_a_verified_collection() {
+    let mut market = TestMarketplace::new(250);
+    let maker = market.user();
+    let nft = market.mint_nft(&maker.pubkey());
+
+    let mut metadata = market.metadata(&nft);
+    metadata.collection.as_mut().unwrap().verified = false;
+    market.set_metadata(&nft, &metadata);
+
+    let list = market.list_instruction(&maker.pubkey(), &nft, PRICE);
+    assert!(market.process(&[list], &[&maker]).is_err());
+}
@@ -0,0 +1,236 @@
+use anchor_lang::{
+    prelude::{Pubkey, Rent},
+    solana_program::native_token::LAMPORTS_PER_SOL,
+};
+use anchor_spl::metadata::mpl_token_metadata::types::Creator;
+use marketplace::state::{OfferTarget, RoyaltyPolicy};
+use marketplace_test::{
+    market::{ata, Nft, TestMarketplace},
+    signature::{Keypair, Signer},
+};
+
+// 2.5%
+const FEE: u16 = 250;
+
+// Give the NFT royalties of `seller_fee_basis_points`, split between `creators`
+// by share, all verified.
+fn set_royalties(
+    market: &mut TestMarketplace,
+    nft: &Nft,
+    seller_fee_basis_points: u16,
+    creators: &[(Pubkey, u8)],
+) {
+    let mut metadata = market.metadata(nft);
+    metadata.seller_fee_basis_points = seller_fee_basis_points;
+    metadata.creators = Some(
+        creators
+            .iter()
+            .map(|&(address, share)| Creator {
+                address,
+                verified: true,
+                share,
+            })
+            .collect(),
+    );
+    market.set_metadata(nft, &metadata);
+}
+
+// A listed NFT whose royalties go to a funded creator and to a creator
+// without an account, in that order.
+fn listed_nft(market: &mut TestMarketplace, maker: &Keypair, price: u64) -> (Nft, [Pubkey; 2]) {
+    let nft = market.mint_nft(&maker.pubkey());
+    let creators = [market.user().pubkey(), Pubkey::new_unique()];
+    set_royalties(market, &nft, 500, &[(creators[0], 50), (creators[1], 50)]);
+
+    let list = market.list_instruction(&maker.pubkey(), &nft, price);
+    market.process(&[list], &[maker]).unwrap();
+
+    (nft, creators)
+}
+
+fn lamports(market: &TestMarketplace, keys: &[Pubkey]) -> Vec<u64> {
+    keys.iter().map(|key| market.svm.lamports(key)).collect()
+}
+
+#[test]
+fn purchase_pays_royalties_by_share() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let price = 10 * LAMPORTS_PER_SOL;
+    let (nft, creators) = listed_nft(&mut market, &maker, price);
+
+    let before = lamports(&market, &creators);
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &creators);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    // 5% of the price, split in half.
+    let royalty = price / 40;
+    assert_eq!(
+        lamports(&market, &creators),
+        vec![before[0] + royalty, royalty]
+    );
+}
+
+#[test]
+fn purchase_needs_the_creators_in_order() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let (nft, creators) = listed_nft(&mut market, &maker, LAMPORTS_PER_SOL);
+
+    for wrong in [
+        vec![],
+        vec![creators[0]],
+        vec![creators[1], creators[0]],
+        vec![creators[0], Pubkey::new_unique()],
+    ] {
+        let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &wrong);
+        assert!(market.process(&[purchase], &[&taker]).is_err());
+    }
+}
+
+#[test]
+fn purchase_skips_royalties_when_disabled() {
+    let mut market = TestMarketplace::new(FEE);
+    let admin = market.admin.insecure_clone();
+    let maker = market.user();
+    let taker = market.user();
+    let price = LAMPORTS_PER_SOL;
+    let (nft, creators) = listed_nft(&mut market, &maker, price);
+
+    let update = market.update_royalty_policy_instruction(&admin.pubkey(), RoyaltyPolicy::Disabled);
+    market.process(&[update], &[&admin]).unwrap();
+
+    let before = market.svm.lamports(&maker.pubkey());
+    let listing_rent = market.svm.lamports(&market.listing(&nft.mint));
+    let vault_rent = market
+        .svm
+        .lamports(&ata(&market.listing(&nft.mint), &nft.mint));
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    assert_eq!(
+        market.svm.lamports(&maker.pubkey()) - before,
+        price - price / 40 + listing_rent + vault_rent
+    );
+    assert!(market.svm.account(&creators[1]).is_none());
+}
+
+// A royalty smaller than the rent exemption can't be paid to a creator without
+// an account. The seller gets it, and the funded creator is still paid.
+fn assert_unpayable_royalty_went_to_the_seller(
+    market: &TestMarketplace,
+    creators: &[Pubkey; 2],
+    creators_before: &[u64],
+    price: u64,
+) {
+    let royalty = price / 40;
+    assert!(royalty < Rent::default().minimum_balance(0));
+    assert_eq!(
+        lamports(market, creators),
+        vec![creators_before[0] + royalty, 0]
+    );
+}
+
+#[test]
+fn purchase_gives_unpayable_royalties_to_the_seller() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let taker = market.user();
+    let price = 10_000_000;
+    let (nft, creators) = listed_nft(&mut market, &maker, price);
+
+    let creators_before = lamports(&market, &creators);
+    let taker_before = market.svm.lamports(&taker.pubkey());
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &creators);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    assert_unpayable_royalty_went_to_the_seller(&market, &creators, &creators_before, price);
+    // The taker pays the price and the rent of their token accounts, no more.
+    let rent = 2 * Rent::default().minimum_balance(165);
+    assert_eq!(
+        taker_before - market.svm.lamports(&taker.pubkey()),
+        price + rent
+    );
+}
+
+#[test]
+fn accept_offer_gives_unpayable_royalties_to_the_seller() {
+    let mut market = TestMarketplace::new(FEE);
+    let seller = market.user();
+    let bidder = market.user();
+    let price = 10_000_000;
+    let nft = market.mint_nft(&seller.pubkey());
+    let creators = [market.user().pubkey(), Pubkey::new_unique()];
+    set_royalties(
+        &mut market,
+        &nft,
+        500,
+        &[(creators[0], 50), (creators[1], 50)],
+    );
+
+    let target = OfferTarget::Mint(nft.mint);
+    let expires_at = market.svm.clock().unix_timestamp + 1_000;
+    let offer = market.make_offer_instruction(&bidder.pubkey(), target, price, expires_at);
+    market.process(&[offer], &[&bidder]).unwrap();
+
+    let creators_before = lamports(&market, &creators);
+    let seller_before = market.svm.lamports(&seller.pubkey());
+    let accept = market.accept_offer_instruction(
+        &seller.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &target,
+        false,
+        &creators,
+    );
+    market.process(&[accept], &[&seller]).unwrap();
+
+    assert_unpayable_royalty_went_to_the_seller(&market, &creators, &creators_before, price);
+    // The seller pays for the bidder's token account.
+    let rent = Rent::default().minimum_balance(165);
+    assert_eq!(
+        market.svm.lamports(&seller.pubkey()) + rent - seller_before,
+        price - price / 40 - price / 40
+    );
+}
+
+#[test]
+fn settle_auction_gives_unpayable_royalties_to_the_seller() {
+    let mut market = TestMarketplace::new(FEE);
+    let maker = market.user();
+    let bidder = market.user();
+    let price = 10_000_000;
+    let nft = market.mint_nft(&maker.pubkey());
+    let creators = [market.user().pubkey(), Pubkey::new_unique()];
+    set_royalties(
+        &mut market,
+        &nft,
+        500,
+        &[(creators[0], 50), (creators[1], 50)],
+    );
+
+    let end_time = market.svm.clock().unix_timestamp + 100;
+    let list = market.list_english_auction_instruction(&maker.pubkey(), &nft, price, 1, end_time);
+    market.process(&[list], &[&maker]).unwrap();
+    let bid = market.bid_instruction(&bidder.pubkey(), &nft, price, None);
+    market.process(&[bid], &[&bidder]).unwrap();
+    market.svm.set_clock(end_time);
+
+    let creators_before = lamports(&market, &creators);
+    let settle = market.settle_auction_instruction(
+        &bidder.pubkey(),
+        &maker.pubkey(),
+        &bidder.pubkey(),
+        &nft,
+        &creators,
+    );
+    market.process(&[settle], &[&bidder]).unwrap();
+
+    assert_unpayable_royalty_went_to_the_seller(&market, &creators, &creators_before, price);
+    assert_eq!(
+        market.svm.token_balance(&ata(&bidder.pubkey(), &nft.mint)),
+        1
+    );
+}
@@ -0,0 +1,63 @@
+use anchor_lang::{prelude::Rent, solana_program::native_token::LAMPORTS_PER_SOL};
+use marketplace::state::{Marketplace, MAX_FEE};
+use marketplace_test::{market::TestMarketplace, signature::Signer};
+
+#[test]
+fn initialize_funds_the_treasury() {
+    let market = TestMarketplace::new(250);
+    assert_eq!(
+        market.svm.lamports(&market.treasury),
+        Rent::default().minimum_balance(0)
+    );
+}
+
+#[test]
+fn update_fee_is_capped_and_admin_only() {
+    let mut market = TestMarketplace::new(250);
+    let admin = market.admin.insecure_clone();
+    let other = market.user();
+
+    let update = market.update_fee_instruction(&other.pubkey(), 100);
+    assert!(market.process(&[update], &[&other]).is_err());
+
+    let update = market.update_fee_instruction(&admin.pubkey(), MAX_FEE + 1);
+    assert!(market.process(&[update], &[&admin]).is_err());
+
+    let update = market.update_fee_instruction(&admin.pubkey(), MAX_FEE);
+    market.process(&[update], &[&admin]).unwrap();
+    let state: Marketplace = market.svm.anchor_account(&market.marketplace);
+    assert_eq!(state.fee, MAX_FEE);
+}
+
+#[test]
+fn withdraw_treasury_keeps_rent_exemption() {
+    let mut market = TestMarketplace::new(250);
+    let admin = market.admin.insecure_clone();
+    let maker = market.user();
+    let taker = market.user();
+
+    let nft = market.mint_nft(&maker.pubkey());
+    let list = market.list_instruction(&maker.pubkey(), &nft, 4 * LAMPORTS_PER_SOL);
+    market.process(&[list], &[&maker]).unwrap();
+    let purchase = market.purchase_instruction(&taker.pubkey(), &maker.pubkey(), &nft, &[]);
+    market.process(&[purchase], &[&taker]).unwrap();
+
+    // 2.5% of 4 SOL.
+    let fees = LAMPORTS_PER_SOL / 10;
+
+    let withdraw = market.withdraw_treasury_instruction(&maker.pubkey(), fees);
+    assert!(market.process(&[withdraw], &[&maker]).is_err());
+
+    let withdraw = market.withdraw_treasury_instruction(&admin.pubkey(), fees + 1);
+    assert!(market.process(&[withdraw], &[&admin]).is_err());
+
+    let admin_before = market.svm.lamports(&admin.pubkey());
+    let withdraw = market.withdraw_treasury_instruction(&admin.pubkey(), fees);
+    market.process(&[withdraw], &[&admin]).unwrap();
+
+    assert_eq!(market.svm.lamports(&admin.pubkey()), admin_before + fees);
+    assert_eq!(
+        market.svm.lamports(&market.treasury),
+        Rent::default().minimum_balance(0)
+    );
+}